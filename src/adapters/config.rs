@@ -1,5 +1,20 @@
 use std::env;
 
+use crate::domain::value_objects::file_type::FileTypePolicy;
+
+/// Types accepted for upload and download when `ALLOWED_FILE_TYPES` is not set.
+const DEFAULT_ALLOWED_FILE_TYPES: &str = "application/pdf:pdf,\
+    image/png:png,\
+    image/jpeg:jpg|jpeg,\
+    image/gif:gif,\
+    image/webp:webp,\
+    text/plain:txt,\
+    text/csv:csv,\
+    application/zip:zip,\
+    application/vnd.openxmlformats-officedocument.wordprocessingml.document:docx,\
+    application/vnd.openxmlformats-officedocument.spreadsheetml.sheet:xlsx,\
+    application/vnd.openxmlformats-officedocument.presentationml.presentation:pptx";
+
 #[derive(Clone)]
 pub struct Config {
     pub secret: Vec<u8>,
@@ -10,6 +25,58 @@ pub struct Config {
     pub google_auth_url: String,
     pub google_token_url: String,
     pub google_redirect_url: String,
+    /// How long a started Google login stays valid, in seconds
+    pub oauth_state_ttl_secs: i64,
+    /// Size of each chunk sent to Drive during resumable uploads, in bytes
+    pub upload_chunk_size: usize,
+    /// Most files a batch upload accepts
+    pub batch_upload_max_files: usize,
+    /// Largest total size of a batch upload, in bytes. Batches are held in memory
+    pub batch_upload_max_size: u64,
+    /// Files of a batch sent to Drive at the same time
+    pub batch_upload_concurrency: usize,
+    /// File types that can be uploaded and downloaded, with their extensions and size limits.
+    /// Types without their own size limit take `MAX_UPLOAD_SIZE`
+    pub allowed_file_types: FileTypePolicy,
+    /// Deepest level the folder tree route walks
+    pub folder_tree_max_depth: u32,
+    /// Folders listed at the same time while walking a folder tree
+    pub folder_tree_concurrency: usize,
+    /// Most folders a search scoped to a folder can go through, subfolders included
+    pub search_max_folders: usize,
+    /// Longest a share link can stay valid, in seconds
+    pub share_link_max_ttl_secs: i64,
+    /// Longest an upload request can stay open, in seconds
+    pub upload_request_max_ttl_secs: i64,
+    /// Time between two reads of the Drive change log of every user with webhooks, in seconds
+    pub changes_poll_interval_secs: u64,
+    /// Users synced, and webhooks of a user called, at the same time
+    pub changes_concurrency: usize,
+    /// Public URL of `/api/public/drive-notifications`. Drive push notifications are only
+    /// requested when set, polling alone is used otherwise
+    pub drive_notifications_url: Option<String>,
+    /// Attempts of a webhook call before it is given up
+    pub webhook_max_attempts: u32,
+    /// Wait after the first failed webhook call, doubled after each one, in milliseconds
+    pub webhook_retry_delay_ms: u64,
+    /// Path to poppler's `pdftoppm`, which renders PDF thumbnails Drive doesn't have
+    pub pdf_renderer_path: String,
+    /// Longest a PDF thumbnail can take to render, in seconds
+    pub thumbnail_render_timeout_secs: u64,
+    /// Largest PDF downloaded to render a thumbnail, in bytes. It is held in memory
+    pub thumbnail_max_pdf_size: u64,
+    /// Path to `qpdf`, which merges and splits PDF files
+    pub pdf_editor_path: String,
+    /// Path to poppler's `pdfinfo`, which reads the page count, title and dates of PDFs
+    pub pdfinfo_path: String,
+    /// Path to poppler's `pdftotext`, which reads the text of each page of PDFs
+    pub pdftotext_path: String,
+    /// Longest a merge, a split or a read of PDF files can take, in seconds
+    pub pdf_edit_timeout_secs: u64,
+    /// Most files a merge takes or a split writes
+    pub pdf_max_files: usize,
+    /// Largest total size of the files merged, split or read, in bytes. They are held in memory
+    pub pdf_max_size: u64,
 }
 
 impl Config {
@@ -24,6 +91,92 @@ impl Config {
         let google_token_url = env::var("GOOGLE_TOKEN_URL").expect("GOOGLE_TOKEN_URL must be set");
         let google_redirect_url =
             env::var("GOOGLE_REDIRECT_URL").expect("GOOGLE_REDIRECT_URL must be set");
+        let oauth_state_ttl_secs = env::var("OAUTH_STATE_TTL_SECS")
+            .map(|x| x.parse().expect("OAUTH_STATE_TTL_SECS must be a number"))
+            .unwrap_or(600);
+        let upload_chunk_size = env::var("UPLOAD_CHUNK_SIZE")
+            .map(|x| x.parse().expect("UPLOAD_CHUNK_SIZE must be a number"))
+            .unwrap_or(8 * 1024 * 1024);
+        let max_upload_size: u64 = env::var("MAX_UPLOAD_SIZE")
+            .map(|x| x.parse().expect("MAX_UPLOAD_SIZE must be a number"))
+            .unwrap_or(100 * 1024 * 1024);
+        let batch_upload_max_files = env::var("BATCH_UPLOAD_MAX_FILES")
+            .map(|x| x.parse().expect("BATCH_UPLOAD_MAX_FILES must be a number"))
+            .unwrap_or(20);
+        let batch_upload_max_size = env::var("BATCH_UPLOAD_MAX_SIZE")
+            .map(|x| x.parse().expect("BATCH_UPLOAD_MAX_SIZE must be a number"))
+            .unwrap_or(200 * 1024 * 1024);
+        let batch_upload_concurrency = env::var("BATCH_UPLOAD_CONCURRENCY")
+            .map(|x| {
+                x.parse()
+                    .expect("BATCH_UPLOAD_CONCURRENCY must be a number")
+            })
+            .unwrap_or(4);
+        let allowed_file_types = FileTypePolicy::parse(
+            &env::var("ALLOWED_FILE_TYPES")
+                .unwrap_or_else(|_| DEFAULT_ALLOWED_FILE_TYPES.to_string()),
+            max_upload_size,
+        )
+        .expect("ALLOWED_FILE_TYPES is invalid");
+        let folder_tree_max_depth = env::var("FOLDER_TREE_MAX_DEPTH")
+            .map(|x| x.parse().expect("FOLDER_TREE_MAX_DEPTH must be a number"))
+            .unwrap_or(10);
+        let folder_tree_concurrency = env::var("FOLDER_TREE_CONCURRENCY")
+            .map(|x| x.parse().expect("FOLDER_TREE_CONCURRENCY must be a number"))
+            .unwrap_or(8);
+        let search_max_folders = env::var("SEARCH_MAX_FOLDERS")
+            .map(|x| x.parse().expect("SEARCH_MAX_FOLDERS must be a number"))
+            .unwrap_or(100);
+        let share_link_max_ttl_secs = env::var("SHARE_LINK_MAX_TTL_SECS")
+            .map(|x| x.parse().expect("SHARE_LINK_MAX_TTL_SECS must be a number"))
+            .unwrap_or(7 * 24 * 60 * 60);
+        let upload_request_max_ttl_secs = env::var("UPLOAD_REQUEST_MAX_TTL_SECS")
+            .map(|x| {
+                x.parse()
+                    .expect("UPLOAD_REQUEST_MAX_TTL_SECS must be a number")
+            })
+            .unwrap_or(30 * 24 * 60 * 60);
+        let changes_poll_interval_secs = env::var("CHANGES_POLL_INTERVAL_SECS")
+            .map(|x| {
+                x.parse()
+                    .expect("CHANGES_POLL_INTERVAL_SECS must be a number")
+            })
+            .unwrap_or(60);
+        let changes_concurrency = env::var("CHANGES_CONCURRENCY")
+            .map(|x| x.parse().expect("CHANGES_CONCURRENCY must be a number"))
+            .unwrap_or(4);
+        let drive_notifications_url = env::var("DRIVE_NOTIFICATIONS_URL")
+            .ok()
+            .filter(|x| !x.is_empty());
+        let webhook_max_attempts = env::var("WEBHOOK_MAX_ATTEMPTS")
+            .map(|x| x.parse().expect("WEBHOOK_MAX_ATTEMPTS must be a number"))
+            .unwrap_or(5);
+        let webhook_retry_delay_ms = env::var("WEBHOOK_RETRY_DELAY_MS")
+            .map(|x| x.parse().expect("WEBHOOK_RETRY_DELAY_MS must be a number"))
+            .unwrap_or(1000);
+        let pdf_renderer_path =
+            env::var("PDF_RENDERER_PATH").unwrap_or_else(|_| "pdftoppm".to_string());
+        let thumbnail_render_timeout_secs = env::var("THUMBNAIL_RENDER_TIMEOUT_SECS")
+            .map(|x| {
+                x.parse()
+                    .expect("THUMBNAIL_RENDER_TIMEOUT_SECS must be a number")
+            })
+            .unwrap_or(20);
+        let thumbnail_max_pdf_size = env::var("THUMBNAIL_MAX_PDF_SIZE")
+            .map(|x| x.parse().expect("THUMBNAIL_MAX_PDF_SIZE must be a number"))
+            .unwrap_or(50 * 1024 * 1024);
+        let pdf_editor_path = env::var("PDF_EDITOR_PATH").unwrap_or_else(|_| "qpdf".to_string());
+        let pdfinfo_path = env::var("PDFINFO_PATH").unwrap_or_else(|_| "pdfinfo".to_string());
+        let pdftotext_path = env::var("PDFTOTEXT_PATH").unwrap_or_else(|_| "pdftotext".to_string());
+        let pdf_edit_timeout_secs = env::var("PDF_EDIT_TIMEOUT_SECS")
+            .map(|x| x.parse().expect("PDF_EDIT_TIMEOUT_SECS must be a number"))
+            .unwrap_or(60);
+        let pdf_max_files = env::var("PDF_MAX_FILES")
+            .map(|x| x.parse().expect("PDF_MAX_FILES must be a number"))
+            .unwrap_or(20);
+        let pdf_max_size = env::var("PDF_MAX_SIZE")
+            .map(|x| x.parse().expect("PDF_MAX_SIZE must be a number"))
+            .unwrap_or(100 * 1024 * 1024);
 
         Config {
             secret: secret.into_bytes(),
@@ -34,6 +187,31 @@ impl Config {
             google_auth_url,
             google_token_url,
             google_redirect_url,
+            oauth_state_ttl_secs,
+            upload_chunk_size,
+            batch_upload_max_files,
+            batch_upload_max_size,
+            batch_upload_concurrency,
+            allowed_file_types,
+            folder_tree_max_depth,
+            folder_tree_concurrency,
+            search_max_folders,
+            share_link_max_ttl_secs,
+            upload_request_max_ttl_secs,
+            changes_poll_interval_secs,
+            changes_concurrency,
+            drive_notifications_url,
+            webhook_max_attempts,
+            webhook_retry_delay_ms,
+            pdf_renderer_path,
+            thumbnail_render_timeout_secs,
+            thumbnail_max_pdf_size,
+            pdf_editor_path,
+            pdfinfo_path,
+            pdftotext_path,
+            pdf_edit_timeout_secs,
+            pdf_max_files,
+            pdf_max_size,
         }
     }
 }
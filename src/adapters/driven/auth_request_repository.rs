@@ -0,0 +1,126 @@
+use std::time::Duration;
+
+use chrono::{DateTime, TimeZone, Utc};
+use mongodb::{
+    bson::{self, doc},
+    options::IndexOptions,
+    Client, Collection, IndexModel,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    application::ports::auth_request_repository::{self, AuthRequestRepositoryTrait},
+    domain::entities::auth_request::AuthRequest,
+};
+
+/// Stored shape of an `AuthRequest`. Dates are kept as BSON dates so Mongo can drop
+/// expired requests on its own through a TTL index.
+#[derive(Serialize, Deserialize)]
+struct AuthRequestDocument {
+    #[serde(rename = "_id")]
+    state: String,
+    pkce_verifier: String,
+    created_at: bson::DateTime,
+    expires_at: bson::DateTime,
+    used_at: Option<bson::DateTime>,
+}
+
+impl From<AuthRequest> for AuthRequestDocument {
+    fn from(auth_request: AuthRequest) -> Self {
+        Self {
+            state: auth_request.state,
+            pkce_verifier: auth_request.pkce_verifier,
+            created_at: to_bson_date(auth_request.created_at),
+            expires_at: to_bson_date(auth_request.expires_at),
+            used_at: auth_request.used_at.map(to_bson_date),
+        }
+    }
+}
+
+impl From<AuthRequestDocument> for AuthRequest {
+    fn from(document: AuthRequestDocument) -> Self {
+        Self {
+            state: document.state,
+            pkce_verifier: document.pkce_verifier,
+            created_at: from_bson_date(document.created_at),
+            expires_at: from_bson_date(document.expires_at),
+            used_at: document.used_at.map(from_bson_date),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct AuthRequestRepository {
+    collection: Collection<AuthRequestDocument>,
+}
+
+impl AuthRequestRepository {
+    pub async fn new(db_url: &str, db_name: &str, collection_name: &str) -> Self {
+        let client = Client::with_uri_str(db_url)
+            .await
+            .expect("Failed to initialize client");
+        let db = client.database(db_name);
+        let collection = db.collection(collection_name);
+
+        let ttl_index = IndexModel::builder()
+            .keys(doc! { "expires_at": 1 })
+            .options(
+                IndexOptions::builder()
+                    .expire_after(Duration::from_secs(0))
+                    .build(),
+            )
+            .build();
+        if let Err(err) = collection.create_index(ttl_index).await {
+            // Expiry is still checked on every callback, the index only cleans up
+            eprintln!("Error creating auth request TTL index: {:?}", err);
+        }
+
+        Self { collection }
+    }
+}
+
+impl AuthRequestRepositoryTrait for AuthRequestRepository {
+    async fn create(
+        &self,
+        auth_request: AuthRequest,
+    ) -> Result<AuthRequest, auth_request_repository::Error> {
+        let document = AuthRequestDocument::from(auth_request.clone());
+        match self.collection.insert_one(&document).await {
+            Ok(_) => Ok(auth_request),
+            Err(err) => Err(auth_request_repository::Error::Unknown(err.to_string())),
+        }
+    }
+
+    async fn consume(&self, state: &str) -> Result<AuthRequest, auth_request_repository::Error> {
+        let filter = doc! { "_id": state, "used_at": null };
+        let update = doc! { "$set": { "used_at": to_bson_date(Utc::now()) } };
+        match self
+            .collection
+            .find_one_and_update(filter, update)
+            .return_document(mongodb::options::ReturnDocument::After)
+            .await
+        {
+            Ok(Some(document)) => return Ok(document.into()),
+            Ok(None) => (),
+            Err(err) => return Err(auth_request_repository::Error::Unknown(err.to_string())),
+        }
+
+        // Tell a replayed state apart from one that never existed
+        let filter = doc! { "_id": state };
+        match self.collection.find_one(filter).await {
+            Ok(Some(_)) => Err(auth_request_repository::Error::AlreadyUsed),
+            Ok(None) => Err(auth_request_repository::Error::NotFound),
+            Err(err) => Err(auth_request_repository::Error::Unknown(err.to_string())),
+        }
+    }
+}
+
+fn to_bson_date(date: DateTime<Utc>) -> bson::DateTime {
+    bson::DateTime::from_millis(date.timestamp_millis())
+}
+
+fn from_bson_date(date: bson::DateTime) -> DateTime<Utc> {
+    Utc.timestamp_millis_opt(date.timestamp_millis())
+        .single()
+        .unwrap_or_default()
+}
@@ -0,0 +1,221 @@
+use chrono::{DateTime, TimeZone, Utc};
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{self, doc},
+    options::{IndexOptions, ReturnDocument},
+    Client, Collection, IndexModel,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    application::ports::change_cursor_repository::{self, ChangeCursorRepositoryTrait},
+    domain::{
+        entities::change_cursor::{ChangeCursor, WatchChannel},
+        value_objects::id::Id,
+    },
+};
+
+/// Stored shape of a `ChangeCursor`, one per user.
+#[derive(Serialize, Deserialize)]
+struct ChangeCursorDocument {
+    #[serde(rename = "_id")]
+    user_id: Id,
+    page_token: String,
+    updated_at: bson::DateTime,
+    channel: Option<WatchChannelDocument>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct WatchChannelDocument {
+    id: String,
+    resource_id: String,
+    expires_at: bson::DateTime,
+}
+
+impl From<ChangeCursor> for ChangeCursorDocument {
+    fn from(cursor: ChangeCursor) -> Self {
+        Self {
+            user_id: cursor.user_id,
+            page_token: cursor.page_token,
+            updated_at: to_bson_date(cursor.updated_at),
+            channel: cursor.channel.map(|channel| WatchChannelDocument {
+                id: channel.id,
+                resource_id: channel.resource_id,
+                expires_at: to_bson_date(channel.expires_at),
+            }),
+        }
+    }
+}
+
+impl From<ChangeCursorDocument> for ChangeCursor {
+    fn from(document: ChangeCursorDocument) -> Self {
+        Self {
+            user_id: document.user_id,
+            page_token: document.page_token,
+            updated_at: from_bson_date(document.updated_at),
+            channel: document.channel.map(|channel| WatchChannel {
+                id: channel.id,
+                resource_id: channel.resource_id,
+                expires_at: from_bson_date(channel.expires_at),
+            }),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct ChangeCursorRepository {
+    collection: Collection<ChangeCursorDocument>,
+}
+
+impl ChangeCursorRepository {
+    pub async fn new(db_url: &str, db_name: &str, collection_name: &str) -> Self {
+        let client = Client::with_uri_str(db_url)
+            .await
+            .expect("Failed to initialize client");
+        let db = client.database(db_name);
+        let collection = db.collection(collection_name);
+
+        let index = IndexModel::builder()
+            .keys(doc! { "channel.id": 1 })
+            .options(IndexOptions::builder().sparse(true).build())
+            .build();
+        if let Err(err) = collection.create_index(index).await {
+            eprintln!("Error creating change cursor indexes: {:?}", err);
+        }
+
+        Self { collection }
+    }
+
+    async fn find_one(
+        &self,
+        filter: bson::Document,
+    ) -> Result<ChangeCursor, change_cursor_repository::Error> {
+        match self.collection.find_one(filter).await {
+            Ok(Some(document)) => Ok(document.into()),
+            Ok(None) => Err(change_cursor_repository::Error::NotFound),
+            Err(err) => Err(change_cursor_repository::Error::Unknown(err.to_string())),
+        }
+    }
+}
+
+impl ChangeCursorRepositoryTrait for ChangeCursorRepository {
+    async fn create(
+        &self,
+        cursor: ChangeCursor,
+    ) -> Result<ChangeCursor, change_cursor_repository::Error> {
+        let filter = doc! { "_id": cursor.user_id.to_string() };
+        let document = bson::to_document(&ChangeCursorDocument::from(cursor))
+            .map_err(|err| change_cursor_repository::Error::Unknown(err.to_string()))?;
+        // Only written when missing, a user registering two webhooks keeps a single cursor
+        match self
+            .collection
+            .find_one_and_update(filter, doc! { "$setOnInsert": document })
+            .upsert(true)
+            .return_document(ReturnDocument::After)
+            .await
+        {
+            Ok(Some(document)) => Ok(document.into()),
+            Ok(None) => Err(change_cursor_repository::Error::NotFound),
+            Err(err) => Err(change_cursor_repository::Error::Unknown(err.to_string())),
+        }
+    }
+
+    async fn find_by_user(
+        &self,
+        user_id: Id,
+    ) -> Result<ChangeCursor, change_cursor_repository::Error> {
+        self.find_one(doc! { "_id": user_id.to_string() }).await
+    }
+
+    async fn find_by_channel(
+        &self,
+        channel_id: &str,
+    ) -> Result<ChangeCursor, change_cursor_repository::Error> {
+        self.find_one(doc! { "channel.id": channel_id }).await
+    }
+
+    async fn find_all(&self) -> Result<Vec<ChangeCursor>, change_cursor_repository::Error> {
+        let documents: Vec<ChangeCursorDocument> = self
+            .collection
+            .find(doc! {})
+            .await
+            .map_err(|err| change_cursor_repository::Error::Unknown(err.to_string()))?
+            .try_collect()
+            .await
+            .map_err(|err| change_cursor_repository::Error::Unknown(err.to_string()))?;
+
+        Ok(documents.into_iter().map(ChangeCursor::from).collect())
+    }
+
+    async fn advance(
+        &self,
+        cursor: ChangeCursor,
+        from_page_token: &str,
+    ) -> Result<ChangeCursor, change_cursor_repository::Error> {
+        let filter = doc! {
+            "_id": cursor.user_id.to_string(),
+            "page_token": from_page_token,
+        };
+        let update = doc! {
+            "$set": {
+                "page_token": &cursor.page_token,
+                "updated_at": to_bson_date(cursor.updated_at),
+            }
+        };
+        match self
+            .collection
+            .find_one_and_update(filter, update)
+            .return_document(ReturnDocument::After)
+            .await
+        {
+            Ok(Some(document)) => return Ok(document.into()),
+            Ok(None) => (),
+            Err(err) => return Err(change_cursor_repository::Error::Unknown(err.to_string())),
+        }
+
+        match self.find_by_user(cursor.user_id).await {
+            Ok(_) => Err(change_cursor_repository::Error::Outdated),
+            Err(err) => Err(err),
+        }
+    }
+
+    async fn update_channel(
+        &self,
+        cursor: ChangeCursor,
+    ) -> Result<ChangeCursor, change_cursor_repository::Error> {
+        let filter = doc! { "_id": cursor.user_id.to_string() };
+        let channel = bson::to_bson(&ChangeCursorDocument::from(cursor).channel)
+            .map_err(|err| change_cursor_repository::Error::Unknown(err.to_string()))?;
+        match self
+            .collection
+            .find_one_and_update(filter, doc! { "$set": { "channel": channel } })
+            .return_document(ReturnDocument::After)
+            .await
+        {
+            Ok(Some(document)) => Ok(document.into()),
+            Ok(None) => Err(change_cursor_repository::Error::NotFound),
+            Err(err) => Err(change_cursor_repository::Error::Unknown(err.to_string())),
+        }
+    }
+
+    async fn delete(&self, user_id: Id) -> Result<(), change_cursor_repository::Error> {
+        let filter = doc! { "_id": user_id.to_string() };
+        match self.collection.delete_one(filter).await {
+            Ok(result) if result.deleted_count == 0 => {
+                Err(change_cursor_repository::Error::NotFound)
+            }
+            Ok(_) => Ok(()),
+            Err(err) => Err(change_cursor_repository::Error::Unknown(err.to_string())),
+        }
+    }
+}
+
+fn to_bson_date(date: DateTime<Utc>) -> bson::DateTime {
+    bson::DateTime::from_millis(date.timestamp_millis())
+}
+
+fn from_bson_date(date: bson::DateTime) -> DateTime<Utc> {
+    Utc.timestamp_millis_opt(date.timestamp_millis())
+        .single()
+        .unwrap_or_default()
+}
@@ -0,0 +1,106 @@
+use std::collections::HashMap;
+
+use futures::TryStreamExt;
+use mongodb::{bson::doc, options::IndexOptions, Client, Collection, IndexModel};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    application::ports::file_parents_repository::{self, FileParentsRepositoryTrait},
+    domain::value_objects::id::Id,
+};
+
+/// Stored parents of one file of a user.
+#[derive(Serialize, Deserialize)]
+struct FileParentsDocument {
+    user_id: Id,
+    file_id: String,
+    parents: Vec<String>,
+}
+
+#[derive(Clone)]
+pub struct FileParentsRepository {
+    collection: Collection<FileParentsDocument>,
+}
+
+impl FileParentsRepository {
+    pub async fn new(db_url: &str, db_name: &str, collection_name: &str) -> Self {
+        let client = Client::with_uri_str(db_url)
+            .await
+            .expect("Failed to initialize client");
+        let db = client.database(db_name);
+        let collection = db.collection(collection_name);
+
+        let index = IndexModel::builder()
+            .keys(doc! { "user_id": 1, "file_id": 1 })
+            .options(IndexOptions::builder().unique(true).build())
+            .build();
+        if let Err(err) = collection.create_index(index).await {
+            eprintln!("Error creating file parents indexes: {:?}", err);
+        }
+
+        Self { collection }
+    }
+}
+
+impl FileParentsRepositoryTrait for FileParentsRepository {
+    async fn find(
+        &self,
+        user_id: Id,
+        file_ids: &[String],
+    ) -> Result<HashMap<String, Vec<String>>, file_parents_repository::Error> {
+        let filter = doc! { "user_id": user_id.to_string(), "file_id": { "$in": file_ids } };
+        let documents: Vec<FileParentsDocument> = self
+            .collection
+            .find(filter)
+            .await
+            .map_err(|err| file_parents_repository::Error::Unknown(err.to_string()))?
+            .try_collect()
+            .await
+            .map_err(|err| file_parents_repository::Error::Unknown(err.to_string()))?;
+
+        Ok(documents
+            .into_iter()
+            .map(|x| (x.file_id, x.parents))
+            .collect())
+    }
+
+    async fn save(
+        &self,
+        user_id: Id,
+        parents: HashMap<String, Vec<String>>,
+    ) -> Result<(), file_parents_repository::Error> {
+        for (file_id, parents) in parents {
+            let filter = doc! { "user_id": user_id.to_string(), "file_id": &file_id };
+            let update = doc! {
+                "$set": { "parents": parents },
+                "$setOnInsert": { "user_id": user_id.to_string(), "file_id": &file_id },
+            };
+            self.collection
+                .update_one(filter, update)
+                .upsert(true)
+                .await
+                .map_err(|err| file_parents_repository::Error::Unknown(err.to_string()))?;
+        }
+        Ok(())
+    }
+
+    async fn delete(
+        &self,
+        user_id: Id,
+        file_ids: &[String],
+    ) -> Result<(), file_parents_repository::Error> {
+        let filter = doc! { "user_id": user_id.to_string(), "file_id": { "$in": file_ids } };
+        match self.collection.delete_many(filter).await {
+            Ok(_) => Ok(()),
+            Err(err) => Err(file_parents_repository::Error::Unknown(err.to_string())),
+        }
+    }
+
+    async fn delete_by_user(&self, user_id: Id) -> Result<(), file_parents_repository::Error> {
+        let filter = doc! { "user_id": user_id.to_string() };
+        match self.collection.delete_many(filter).await {
+            Ok(_) => Ok(()),
+            Err(err) => Err(file_parents_repository::Error::Unknown(err.to_string())),
+        }
+    }
+}
@@ -1,21 +1,46 @@
+use std::{collections::HashMap, time::Duration};
+
 use crate::{
-    application::ports::google_drive_service::{self, GoogleDriveServiceTrait},
-    domain::value_objects::file_info::FileInfo,
+    application::ports::google_drive_service::{
+        self, AuthorizationUrl, ByteStream, ChannelRequest, GoogleDriveServiceTrait, Image,
+    },
+    domain::{
+        entities::change_cursor::WatchChannel,
+        value_objects::{
+            byte_range::ByteRange,
+            drive_about::{DriveAbout, StorageQuota},
+            file_change::{ChangesPage, FileChange},
+            file_info::{FileField, FileInfo, FileOwner, FOLDER_MIME_TYPE},
+            file_query::{FileQuery, SortField, SortKey, MAX_PAGE_SIZE},
+            files_page::FilesPage,
+            google_token::GoogleToken,
+            permission::{
+                Permission, PermissionGrant, PermissionRole, PermissionType, PermissionUpdate,
+            },
+            revision::Revision,
+            search_query::SearchQuery,
+            shared_drive::SharedDrive,
+        },
+    },
+};
+use chrono::{SecondsFormat, TimeZone, Utc};
+use futures::{StreamExt, TryStreamExt};
+use google_drive3::{
+    api::{Channel, File},
+    hyper_rustls,
+    yup_oauth2::AccessTokenAuthenticator,
+    DriveHub,
 };
-use google_drive3::{hyper_rustls, yup_oauth2::AccessTokenAuthenticator, DriveHub};
 use oauth2::{
-    basic::{BasicClient, BasicErrorResponseType},
+    basic::{BasicClient, BasicErrorResponseType, BasicTokenResponse},
     reqwest::async_http_client,
-    AuthUrl, AuthorizationCode, ClientId, ClientSecret, CsrfToken, RedirectUrl, Scope,
-    StandardErrorResponse, TokenResponse, TokenUrl,
+    AuthUrl, AuthorizationCode, ClientId, ClientSecret, CsrfToken, PkceCodeChallenge,
+    PkceCodeVerifier, RedirectUrl, RefreshToken, RequestTokenError, Scope, TokenResponse, TokenUrl,
 };
-use reqwest::Client;
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use reqwest::{header, Client};
 use serde::Deserialize;
 use serde_json::json;
-use tokio::{
-    fs::File,
-    io::{AsyncReadExt, AsyncWriteExt},
-};
 
 #[derive(Deserialize)]
 struct UserInfo {
@@ -29,6 +54,7 @@ pub struct GoogleDriveService {
     auth_url: AuthUrl,
     token_url: TokenUrl,
     redirect_url: RedirectUrl,
+    upload_chunk_size: usize,
 }
 
 impl GoogleDriveService {
@@ -38,6 +64,7 @@ impl GoogleDriveService {
         auth_url: String,
         token_url: String,
         redirect_url: String,
+        upload_chunk_size: usize,
     ) -> Self {
         Self {
             client_id: ClientId::new(client_id),
@@ -45,22 +72,13 @@ impl GoogleDriveService {
             auth_url: AuthUrl::new(auth_url).unwrap(),
             token_url: TokenUrl::new(token_url).unwrap(),
             redirect_url: RedirectUrl::new(redirect_url).unwrap(),
+            // Drive only accepts chunks in multiples of 256 KiB
+            upload_chunk_size: upload_chunk_size.div_ceil(UPLOAD_CHUNK_ALIGNMENT).max(1)
+                * UPLOAD_CHUNK_ALIGNMENT,
         }
     }
 
-    fn create_oauth_client(
-        &self,
-    ) -> oauth2::Client<
-        StandardErrorResponse<BasicErrorResponseType>,
-        oauth2::StandardTokenResponse<oauth2::EmptyExtraTokenFields, oauth2::basic::BasicTokenType>,
-        oauth2::basic::BasicTokenType,
-        oauth2::StandardTokenIntrospectionResponse<
-            oauth2::EmptyExtraTokenFields,
-            oauth2::basic::BasicTokenType,
-        >,
-        oauth2::StandardRevocableToken,
-        StandardErrorResponse<oauth2::RevocationErrorResponseType>,
-    > {
+    fn create_oauth_client(&self) -> BasicClient {
         BasicClient::new(
             self.client_id.clone(),
             Some(self.client_secret.clone()),
@@ -72,10 +90,14 @@ impl GoogleDriveService {
 }
 
 impl GoogleDriveServiceTrait for GoogleDriveService {
-    async fn get_google_auth_url(&self) -> Result<(String, String), google_drive_service::Error> {
+    async fn get_google_auth_url(
+        &self,
+        force_consent: bool,
+    ) -> Result<AuthorizationUrl, google_drive_service::Error> {
         let client = self.create_oauth_client();
+        let (pkce_challenge, pkce_verifier) = PkceCodeChallenge::new_random_sha256();
 
-        let (auth_url, csrf_token) = client
+        let mut request = client
             .authorize_url(CsrfToken::new_random)
             .add_scope(Scope::new(
                 "https://www.googleapis.com/auth/drive".to_string(),
@@ -83,35 +105,74 @@ impl GoogleDriveServiceTrait for GoogleDriveService {
             .add_scope(Scope::new(
                 "https://www.googleapis.com/auth/userinfo.email".to_string(),
             )) // Request Google Drive scope
-            // .set_pkce_challenge(PkceCodeChallenge::new_random_sha256().0)
-            .url();
+            .add_extra_param("access_type", "offline")
+            .set_pkce_challenge(pkce_challenge);
+        // Google only sends a refresh token on the first consent, asking again is the only
+        // way to get a new one for a user who already authorized the app.
+        if force_consent {
+            request = request.add_extra_param("prompt", "consent");
+        }
+        let (auth_url, csrf_token) = request.url();
 
-        Ok((auth_url.to_string(), csrf_token.secret().clone()))
+        Ok(AuthorizationUrl {
+            url: auth_url.to_string(),
+            state: csrf_token.secret().clone(),
+            pkce_verifier: pkce_verifier.secret().clone(),
+        })
     }
 
     async fn handle_google_callback(
         &self,
         code: String,
-    ) -> Result<String, google_drive_service::Error> {
+        pkce_verifier: String,
+    ) -> Result<GoogleToken, google_drive_service::Error> {
         let client = self.create_oauth_client();
 
         let code = AuthorizationCode::new(code);
 
         let token_result = client
             .exchange_code(code)
-            // .set_pkce_verifier(PkceCodeChallenge::new_random_sha256())
+            .set_pkce_verifier(PkceCodeVerifier::new(pkce_verifier))
+            .request_async(async_http_client)
+            .await;
+
+        let token = match token_result {
+            Ok(token) => token,
+            Err(err) => {
+                eprintln!("Error: {:?}", err);
+                return Err(google_drive_service::Error::Unknown(err.to_string()));
+            }
+        };
+
+        Ok(to_google_token(&token))
+    }
+
+    async fn refresh_google_token(
+        &self,
+        refresh_token: String,
+    ) -> Result<GoogleToken, google_drive_service::Error> {
+        let client = self.create_oauth_client();
+
+        let token_result = client
+            .exchange_refresh_token(&RefreshToken::new(refresh_token))
             .request_async(async_http_client)
             .await;
 
         let token = match token_result {
             Ok(token) => token,
+            // The refresh token was revoked or has expired, the user has to log in again
+            Err(RequestTokenError::ServerResponse(err))
+                if *err.error() == BasicErrorResponseType::InvalidGrant =>
+            {
+                return Err(google_drive_service::Error::GoogleUnauthenticated);
+            }
             Err(err) => {
                 eprintln!("Error: {:?}", err);
                 return Err(google_drive_service::Error::Unknown(err.to_string()));
             }
         };
 
-        Ok(token.access_token().secret().clone())
+        Ok(to_google_token(&token))
     }
 
     async fn get_google_email(
@@ -146,266 +207,1727 @@ impl GoogleDriveServiceTrait for GoogleDriveService {
         Ok(user_info.email)
     }
 
-    async fn download_p_d_f(
+    async fn get_file(
         &self,
         access_token: String,
         file_id: &str,
-    ) -> Result<String, google_drive_service::Error> {
-        let hub = create_hub(access_token.clone()).await?;
+    ) -> Result<FileInfo, google_drive_service::Error> {
+        self.get_file_fields(access_token, file_id, &FileField::DEFAULT)
+            .await
+    }
+
+    async fn get_file_fields(
+        &self,
+        access_token: String,
+        file_id: &str,
+        fields: &[FileField],
+    ) -> Result<FileInfo, google_drive_service::Error> {
+        let hub = create_hub(access_token).await?;
 
-        let file_metadata = hub
+        let (_, file) = hub
             .files()
             .get(file_id)
-            .param("fields", "name,mimeType")
+            .supports_all_drives(true)
+            .param("fields", &file_fields(fields))
             .doit()
             .await
-            .map_err(|x| google_drive_service::Error::Unknown(x.to_string()))?;
+            .map_err(map_hub_error)?;
 
-        if file_metadata.1.mime_type != Some("application/pdf".to_string()) {
-            return Err(google_drive_service::Error::Unknown(
-                "File is not a PDF".to_string(),
-            ));
-        }
+        Ok(to_file_info(file))
+    }
 
-        let file_path = format!("downloaded_file_{}.pdf", file_id);
+    async fn download_thumbnail(
+        &self,
+        access_token: String,
+        thumbnail_link: &str,
+        width: u32,
+    ) -> Result<Image, google_drive_service::Error> {
+        let client = Client::new();
+        let response = client
+            .get(sized_thumbnail_link(thumbnail_link, width))
+            .bearer_auth(&access_token)
+            .send()
+            .await
+            .map_err(|e| {
+                google_drive_service::Error::Unknown(format!("Error sending request: {}", e))
+            })?;
+        let response = check_response_status(response).await?;
 
-        let mut output_file = File::create(&file_path).await.map_err(|e| {
-            google_drive_service::Error::Unknown(format!("Error creating file: {}", e))
+        let content_type = response
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|x| x.to_str().ok())
+            .unwrap_or("image/png")
+            .to_string();
+        let content = response.bytes().await.map_err(|e| {
+            google_drive_service::Error::Unknown(format!("Error reading thumbnail: {}", e))
         })?;
 
+        Ok(Image {
+            content_type,
+            content,
+        })
+    }
+
+    async fn get_about(
+        &self,
+        access_token: String,
+    ) -> Result<DriveAbout, google_drive_service::Error> {
+        let hub = create_hub(access_token).await?;
+
+        let (_, about) = hub
+            .about()
+            .get()
+            .param(
+                "fields",
+                "user(displayName,photoLink),storageQuota,maxUploadSize,importFormats,exportFormats",
+            )
+            .doit()
+            .await
+            .map_err(map_hub_error)?;
+
+        let to_bytes = |x: Option<i64>| x.and_then(|x| u64::try_from(x).ok());
+        let storage_quota = about.storage_quota.unwrap_or_default();
+        let user = about.user.unwrap_or_default();
+        Ok(DriveAbout {
+            display_name: user.display_name,
+            photo_link: user.photo_link,
+            storage_quota: StorageQuota {
+                limit: to_bytes(storage_quota.limit),
+                usage: to_bytes(storage_quota.usage).unwrap_or_default(),
+                usage_in_drive: to_bytes(storage_quota.usage_in_drive).unwrap_or_default(),
+                usage_in_drive_trash: to_bytes(storage_quota.usage_in_drive_trash)
+                    .unwrap_or_default(),
+            },
+            max_upload_size: to_bytes(about.max_upload_size),
+            import_formats: about.import_formats.unwrap_or_default(),
+            export_formats: about.export_formats.unwrap_or_default(),
+        })
+    }
+
+    async fn list_drives(
+        &self,
+        access_token: String,
+    ) -> Result<Vec<SharedDrive>, google_drive_service::Error> {
+        let hub = create_hub(access_token).await?;
+
+        let mut drives = Vec::new();
+        let mut page_token: Option<String> = None;
+        loop {
+            let mut request = hub
+                .drives()
+                .list()
+                .page_size(100)
+                .param("fields", "nextPageToken,drives(id,name,createdTime,hidden)");
+            if let Some(page_token) = &page_token {
+                request = request.page_token(page_token);
+            }
+            let (_, list) = request.doit().await.map_err(map_hub_error)?;
+
+            drives.extend(
+                list.drives
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|drive| SharedDrive {
+                        id: drive.id.unwrap_or_default(),
+                        name: drive.name.unwrap_or_default(),
+                        created_at: drive.created_time,
+                        hidden: drive.hidden.unwrap_or_default(),
+                    }),
+            );
+            match list.next_page_token {
+                Some(next_page_token) => page_token = Some(next_page_token),
+                None => return Ok(drives),
+            }
+        }
+    }
+
+    async fn download_file_content(
+        &self,
+        access_token: String,
+        file_id: &str,
+        range: Option<ByteRange>,
+    ) -> Result<ByteStream, google_drive_service::Error> {
         let client = Client::new();
-        let req = client
-            .get(&format!(
+        let mut req = client
+            .get(format!(
                 "https://www.googleapis.com/drive/v3/files/{}",
-                file_id
+                path_segment(file_id)
             ))
             .bearer_auth(&access_token)
-            .query(&[("alt", "media")]);
+            .query(&[("alt", "media"), ("supportsAllDrives", "true")]);
+        if let Some(range) = range {
+            req = req.header(
+                header::RANGE,
+                format!("bytes={}-{}", range.start, range.end),
+            );
+        }
 
-        let mut response = req.send().await.map_err(|e| {
+        let response = req.send().await.map_err(|e| {
             google_drive_service::Error::Unknown(format!("Error sending request: {}", e))
         })?;
+        let response = check_response_status(response).await?;
 
-        while let Some(chunk) = response.chunk().await.map_err(|e| {
+        let content = response.bytes_stream().map_err(|e| {
             google_drive_service::Error::Unknown(format!("Error reading chunk: {}", e))
-        })? {
-            output_file.write_all(&chunk).await.map_err(|e| {
-                google_drive_service::Error::Unknown(format!("Error writing to file: {}", e))
-            })?;
-        }
+        });
 
-        Ok(file_path)
+        Ok(Box::pin(content))
     }
 
-    async fn list_files(
+    async fn export_file_content(
         &self,
         access_token: String,
-        folder_id: &str,
-    ) -> Result<Vec<FileInfo>, google_drive_service::Error> {
-        let hub = create_hub(access_token).await?;
-        let result = hub
-            .files()
-            .list()
-            .q(&format!("'{}' in parents", folder_id))
-            .page_size(100)
-            .add_scope("https://www.googleapis.com/auth/drive.metadata.readonly")
-            .param("fields", "files(id, name, mimeType, createdTime)")
-            .doit()
-            .await;
+        file_id: &str,
+        mime_type: &str,
+    ) -> Result<ByteStream, google_drive_service::Error> {
+        let client = Client::new();
+        let response = client
+            .get(format!(
+                "https://www.googleapis.com/drive/v3/files/{}/export",
+                path_segment(file_id)
+            ))
+            .bearer_auth(&access_token)
+            .query(&[("mimeType", mime_type)])
+            .send()
+            .await
+            .map_err(|e| {
+                google_drive_service::Error::Unknown(format!("Error sending request: {}", e))
+            })?;
+        let response = check_response_status(response).await?;
 
-        let files = match result {
-            Ok((_resp, result)) => result,
-            Err(google_drive3::Error::BadRequest(json_value)) => {
-                if json_value.to_string().contains("UNAUTHENTICATED") {
-                    return Err(google_drive_service::Error::GoogleUnauthenticated);
-                } else {
-                    return Err(google_drive_service::Error::Unknown(json_value.to_string()));
-                }
-            }
-            Err(err) => {
-                eprintln!("Error: {:?}", err);
-                return Err(google_drive_service::Error::Unknown(err.to_string()));
-            }
-        };
+        let content = response.bytes_stream().map_err(|e| {
+            google_drive_service::Error::Unknown(format!("Error reading chunk: {}", e))
+        });
 
-        let file_ids = files
-            .files
-            .unwrap_or_default()
-            .into_iter()
-            // .filter(|file| file.id.is_some())
-            .map(|file| FileInfo {
-                id: file.id.unwrap(),
-                name: file.name.unwrap(),
-                file_type: file.mime_type.unwrap_or(Default::default()),
-                created_at: file.created_time,
-            })
-            .collect();
+        Ok(Box::pin(content))
+    }
+
+    async fn list_files(
+        &self,
+        access_token: String,
+        query: &FileQuery,
+    ) -> Result<FilesPage, google_drive_service::Error> {
+        list_page(access_token, list_request(query)).await
+    }
 
-        Ok(file_ids)
+    async fn search_files(
+        &self,
+        access_token: String,
+        query: &SearchQuery,
+    ) -> Result<FilesPage, google_drive_service::Error> {
+        list_page(access_token, search_request(query)).await
     }
 
-    async fn create_p_d_f(
+    async fn create_upload_session(
         &self,
         access_token: String,
         file_name: &str,
-        file_path: String,
+        mime_type: &str,
+        parent_id: Option<&str>,
     ) -> Result<String, google_drive_service::Error> {
-        let mut file = File::open(file_path).await.map_err(|e| {
-            google_drive_service::Error::Unknown(format!("Error opening file: {}", e))
-        })?;
-
-        let mut buffer = Vec::new();
-        file.read_to_end(&mut buffer).await.map_err(|e| {
-            google_drive_service::Error::Unknown(format!("Error reading file: {}", e))
-        })?;
-
         let client = Client::new();
 
-        let metadata = json!({
+        let mut metadata = json!({
             "name": file_name,
-            "mimeType": "application/pdf"
+            "mimeType": mime_type
         });
+        if let Some(parent_id) = parent_id {
+            metadata["parents"] = json!([parent_id]);
+        }
 
-        let boundary = "foo_bar_baz";
-        let mut body = Vec::new();
+        let req = client
+            .post("https://www.googleapis.com/upload/drive/v3/files")
+            .json(&metadata);
+        start_upload_session(req, access_token, mime_type).await
+    }
 
-        // Add metadata part
-        body.extend(format!("--{}\r\n", boundary).as_bytes());
-        body.extend(b"Content-Type: application/json; charset=UTF-8\r\n\r\n");
-        body.extend(metadata.to_string().as_bytes());
-        body.extend(b"\r\n");
+    async fn create_update_session(
+        &self,
+        access_token: String,
+        file_id: &str,
+        mime_type: &str,
+    ) -> Result<String, google_drive_service::Error> {
+        let client = Client::new();
 
-        // Add file part
-        body.extend(format!("--{}\r\n", boundary).as_bytes());
-        body.extend(b"Content-Type: application/pdf\r\n\r\n");
-        body.extend(&buffer);
-        body.extend(b"\r\n");
+        // Only the content changes, the file keeps its name and parents
+        let req = client
+            .patch(format!(
+                "https://www.googleapis.com/upload/drive/v3/files/{}",
+                path_segment(file_id)
+            ))
+            .json(&json!({}));
+        start_upload_session(req, access_token, mime_type).await
+    }
 
-        // End boundary
-        body.extend(format!("--{}--\r\n", boundary).as_bytes());
+    async fn upload_file_content(
+        &self,
+        session_uri: String,
+        mut content: ByteStream,
+    ) -> Result<FileInfo, google_drive_service::Error> {
+        let client = Client::new();
 
-        let req = client
-            .post("https://www.googleapis.com/upload/drive/v3/files?uploadType=multipart")
-            .bearer_auth(&access_token)
-            .header(
-                "Content-Type",
-                format!("multipart/related; boundary={}", boundary),
-            )
-            .header("Content-Length", body.len())
-            .body(body);
+        // Bytes Drive has confirmed so far; `buffer` holds everything after that offset
+        let mut offset: u64 = 0;
+        let mut buffer: Vec<u8> = Vec::with_capacity(self.upload_chunk_size);
+        let mut finished = false;
 
-        let response = req.send().await.map_err(|e| {
-            google_drive_service::Error::Unknown(format!("Error sending request: {}", e))
-        })?;
+        loop {
+            while !finished && buffer.len() < self.upload_chunk_size {
+                match content.next().await {
+                    Some(Ok(bytes)) => buffer.extend_from_slice(&bytes),
+                    Some(Err(err)) => {
+                        cancel_upload(&client, &session_uri).await;
+                        return Err(err);
+                    }
+                    None => finished = true,
+                }
+            }
 
-        let msg = response.text().await.map_err(|e| {
-            google_drive_service::Error::Unknown(format!("Error reading response: {}", e))
-        })?;
+            // Every chunk but the last must be a multiple of 256 KiB, which the chunk size is
+            let (chunk_len, total) = if finished {
+                (buffer.len(), Some(offset + buffer.len() as u64))
+            } else {
+                (self.upload_chunk_size, None)
+            };
 
-        Ok(msg)
+            match send_upload_chunk(&client, &session_uri, offset, &buffer[..chunk_len], total)
+                .await?
+            {
+                UploadProgress::Complete(msg) => {
+                    return serde_json::from_str::<File>(&msg)
+                        .map(to_file_info)
+                        .map_err(|err| {
+                            google_drive_service::Error::Unknown(format!(
+                                "Invalid upload response: {}",
+                                err
+                            ))
+                        })
+                }
+                UploadProgress::Incomplete(confirmed) => {
+                    let accepted = confirmed.saturating_sub(offset) as usize;
+                    buffer.drain(..accepted.min(buffer.len()));
+                    offset = confirmed.max(offset);
+                }
+            }
+        }
     }
-}
 
-async fn create_hub(
-    access_token: String,
-) -> Result<
-    DriveHub<hyper_rustls::HttpsConnector<hyper_util::client::legacy::connect::HttpConnector>>,
-    google_drive_service::Error,
-> {
-    let auth = AccessTokenAuthenticator::builder(access_token)
-        .build()
-        .await
-        .map_err(|e| google_drive_service::Error::Unknown(e.to_string()))?;
+    async fn trash_file(
+        &self,
+        access_token: String,
+        file_id: &str,
+    ) -> Result<FileInfo, google_drive_service::Error> {
+        set_trashed(access_token, file_id, true).await
+    }
 
-    let client = hyper_util::client::legacy::Client::builder(hyper_util::rt::TokioExecutor::new())
-        .build(
-            hyper_rustls::HttpsConnectorBuilder::new()
-                .with_native_roots()
-                .unwrap()
-                .https_or_http()
-                .enable_http1()
-                .build(),
-        );
+    async fn restore_file(
+        &self,
+        access_token: String,
+        file_id: &str,
+    ) -> Result<FileInfo, google_drive_service::Error> {
+        set_trashed(access_token, file_id, false).await
+    }
 
-    Ok(DriveHub::new(client, auth))
-}
+    async fn delete_file(
+        &self,
+        access_token: String,
+        file_id: &str,
+    ) -> Result<(), google_drive_service::Error> {
+        let hub = create_hub(access_token).await?;
 
-#[cfg(test)]
-mod tests {
-    use crate::application::ports::google_drive_service::GoogleDriveServiceTrait;
+        hub.files()
+            .delete(file_id)
+            .supports_all_drives(true)
+            .doit()
+            .await
+            .map_err(map_hub_error)?;
 
-    #[tokio::test]
-    async fn test_get_google_auth_url() {
-        let config = crate::adapters::config::Config::new();
+        Ok(())
+    }
 
-        let google_drive_service =
-            crate::adapters::driven::google_drive_service::GoogleDriveService::new(
-                config.google_client_id.clone(),
-                config.google_client_secret.clone(),
-                config.google_auth_url.clone(),
-                config.google_token_url.clone(),
-                config.google_redirect_url.clone(),
-            )
-            .await;
+    async fn rename_file(
+        &self,
+        access_token: String,
+        file_id: &str,
+        name: &str,
+    ) -> Result<FileInfo, google_drive_service::Error> {
+        let hub = create_hub(access_token).await?;
 
-        let (auth_url, csrf_token) = google_drive_service.get_google_auth_url().await.unwrap();
-        assert!(!auth_url.is_empty());
-        assert!(!csrf_token.is_empty());
+        let changes = File {
+            name: Some(name.to_string()),
+            ..Default::default()
+        };
+        let (_, file) = hub
+            .files()
+            .update(changes, file_id)
+            .supports_all_drives(true)
+            .param("fields", &file_fields(&FileField::DEFAULT))
+            .doit_without_upload()
+            .await
+            .map_err(map_hub_error)?;
 
-        println!("auth_url: {}", auth_url);
-        println!("csrf_token: {}", csrf_token);
+        Ok(to_file_info(file))
     }
 
-    #[tokio::test]
-    async fn test_handle_google_callback() {
-        let config = crate::adapters::config::Config::new();
-
-        let google_drive_service =
-            crate::adapters::driven::google_drive_service::GoogleDriveService::new(
-                config.google_client_id.clone(),
-                config.google_client_secret.clone(),
-                config.google_auth_url.clone(),
-                config.google_token_url.clone(),
-                config.google_redirect_url.clone(),
-            )
-            .await;
+    async fn set_app_properties(
+        &self,
+        access_token: String,
+        file_id: &str,
+        properties: &HashMap<String, String>,
+    ) -> Result<FileInfo, google_drive_service::Error> {
+        let hub = create_hub(access_token).await?;
 
-        let code = "".to_string();
-        let access_token = match google_drive_service.handle_google_callback(code).await {
-            Ok(access_token) => access_token,
-            Err(err) => {
-                println!("Error: {}", err);
-                panic!();
-            }
+        let changes = File {
+            app_properties: Some(properties.clone()),
+            ..Default::default()
         };
-        assert!(!access_token.is_empty());
+        let mut fields = FileField::DEFAULT.to_vec();
+        fields.push(FileField::AppProperties);
+        let (_, file) = hub
+            .files()
+            .update(changes, file_id)
+            .supports_all_drives(true)
+            .param("fields", &file_fields(&fields))
+            .doit_without_upload()
+            .await
+            .map_err(map_hub_error)?;
+
+        Ok(to_file_info(file))
     }
 
-    #[tokio::test]
-    async fn test_get_google_email() {
-        let config = crate::adapters::config::Config::new();
+    async fn move_file(
+        &self,
+        access_token: String,
+        file_id: &str,
+        folder_id: &str,
+    ) -> Result<FileInfo, google_drive_service::Error> {
+        let hub = create_hub(access_token).await?;
 
-        let google_drive_service =
-            crate::adapters::driven::google_drive_service::GoogleDriveService::new(
-                config.google_client_id.clone(),
-                config.google_client_secret.clone(),
-                config.google_auth_url.clone(),
-                config.google_token_url.clone(),
-                config.google_redirect_url.clone(),
-            )
-            .await;
+        let (_, current) = hub
+            .files()
+            .get(file_id)
+            .supports_all_drives(true)
+            .param("fields", "parents")
+            .doit()
+            .await
+            .map_err(map_hub_error)?;
+        let previous_parents = current.parents.unwrap_or_default().join(",");
 
-        let access_token = "".to_string();
+        let (_, file) = hub
+            .files()
+            .update(File::default(), file_id)
+            .supports_all_drives(true)
+            .add_parents(folder_id)
+            .remove_parents(&previous_parents)
+            .param("fields", &file_fields(&FileField::DEFAULT))
+            .doit_without_upload()
+            .await
+            .map_err(map_hub_error)?;
 
-        let email = match google_drive_service.get_google_email(access_token).await {
-            Ok(email) => email,
-            Err(err) => {
-                println!("Error: {}", err);
-                panic!();
-            }
-        };
-        assert!(!email.is_empty());
+        Ok(to_file_info(file))
+    }
 
-        println!("email: {}", email);
+    async fn copy_file(
+        &self,
+        access_token: String,
+        file_id: &str,
+        name: Option<&str>,
+        folder_id: Option<&str>,
+    ) -> Result<FileInfo, google_drive_service::Error> {
+        let hub = create_hub(access_token).await?;
+
+        let request = File {
+            name: name.map(|x| x.to_string()),
+            parents: folder_id.map(|x| vec![x.to_string()]),
+            ..Default::default()
+        };
+        let (_, file) = hub
+            .files()
+            .copy(request, file_id)
+            .supports_all_drives(true)
+            .param("fields", &file_fields(&FileField::DEFAULT))
+            .doit()
+            .await
+            .map_err(map_hub_error)?;
+
+        Ok(to_file_info(file))
+    }
+
+    async fn create_folder(
+        &self,
+        access_token: String,
+        name: &str,
+        parent_id: Option<&str>,
+    ) -> Result<FileInfo, google_drive_service::Error> {
+        let client = Client::new();
+
+        let mut metadata = json!({
+            "name": name,
+            "mimeType": FOLDER_MIME_TYPE
+        });
+        if let Some(parent_id) = parent_id {
+            metadata["parents"] = json!([parent_id]);
+        }
+
+        // The generated client only creates files along with their content
+        let response = client
+            .post("https://www.googleapis.com/drive/v3/files")
+            .bearer_auth(&access_token)
+            .query(&[
+                ("supportsAllDrives", "true".to_string()),
+                ("fields", file_fields(&FileField::DEFAULT)),
+            ])
+            .json(&metadata)
+            .send()
+            .await
+            .map_err(|e| {
+                google_drive_service::Error::Unknown(format!("Error sending request: {}", e))
+            })?;
+        let response = check_response_status(response).await?;
+
+        let file: File = response.json().await.map_err(|e| {
+            google_drive_service::Error::Unknown(format!("Error parsing response: {}", e))
+        })?;
+
+        Ok(to_file_info(file))
+    }
+
+    async fn list_permissions(
+        &self,
+        access_token: String,
+        file_id: &str,
+    ) -> Result<Vec<Permission>, google_drive_service::Error> {
+        let hub = create_hub(access_token).await?;
+
+        let mut permissions = Vec::new();
+        let mut page_token: Option<String> = None;
+        loop {
+            let mut request = hub
+                .permissions()
+                .list(file_id)
+                .supports_all_drives(true)
+                .param(
+                    "fields",
+                    &format!("nextPageToken,permissions({})", PERMISSION_FIELDS),
+                );
+            if let Some(page_token) = &page_token {
+                request = request.page_token(page_token);
+            }
+            let (_, list) = request.doit().await.map_err(map_hub_error)?;
+
+            permissions.extend(
+                list.permissions
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter_map(to_permission),
+            );
+            match list.next_page_token {
+                Some(next_page_token) => page_token = Some(next_page_token),
+                None => return Ok(permissions),
+            }
+        }
+    }
+
+    async fn create_permission(
+        &self,
+        access_token: String,
+        file_id: &str,
+        grant: &PermissionGrant,
+    ) -> Result<Permission, google_drive_service::Error> {
+        let hub = create_hub(access_token).await?;
+
+        let request = google_drive3::api::Permission {
+            type_: Some(permission_type_name(grant.permission_type).to_string()),
+            role: Some(permission_role_name(grant.role).to_string()),
+            email_address: grant.email_address.clone(),
+            domain: grant.domain.clone(),
+            expiration_time: grant.expires_at,
+            ..Default::default()
+        };
+        let mut call = hub
+            .permissions()
+            .create(request, file_id)
+            .supports_all_drives(true)
+            .param("fields", PERMISSION_FIELDS);
+        // Drive rejects the notification flags for domain and link permissions
+        if matches!(
+            grant.permission_type,
+            PermissionType::User | PermissionType::Group
+        ) {
+            call = call.send_notification_email(grant.send_notification);
+            if let Some(message) = grant.message.as_deref().filter(|_| grant.send_notification) {
+                call = call.email_message(message);
+            }
+        }
+        let (_, permission) = call.doit().await.map_err(map_hub_error)?;
+
+        to_permission(permission).ok_or_else(|| {
+            google_drive_service::Error::Unknown("Unexpected permission in response".to_string())
+        })
+    }
+
+    async fn update_permission(
+        &self,
+        access_token: String,
+        file_id: &str,
+        permission_id: &str,
+        update: &PermissionUpdate,
+    ) -> Result<Permission, google_drive_service::Error> {
+        let hub = create_hub(access_token).await?;
+
+        let request = google_drive3::api::Permission {
+            role: update.role.map(|x| permission_role_name(x).to_string()),
+            expiration_time: update.expires_at,
+            ..Default::default()
+        };
+        let (_, permission) = hub
+            .permissions()
+            .update(request, file_id, permission_id)
+            .supports_all_drives(true)
+            .remove_expiration(update.remove_expiration)
+            .param("fields", PERMISSION_FIELDS)
+            .doit()
+            .await
+            .map_err(map_hub_error)?;
+
+        to_permission(permission).ok_or_else(|| {
+            google_drive_service::Error::Unknown("Unexpected permission in response".to_string())
+        })
+    }
+
+    async fn delete_permission(
+        &self,
+        access_token: String,
+        file_id: &str,
+        permission_id: &str,
+    ) -> Result<(), google_drive_service::Error> {
+        let hub = create_hub(access_token).await?;
+
+        hub.permissions()
+            .delete(file_id, permission_id)
+            .supports_all_drives(true)
+            .doit()
+            .await
+            .map_err(map_hub_error)?;
+
+        Ok(())
+    }
+
+    async fn list_revisions(
+        &self,
+        access_token: String,
+        file_id: &str,
+    ) -> Result<Vec<Revision>, google_drive_service::Error> {
+        let hub = create_hub(access_token).await?;
+
+        let mut revisions = Vec::new();
+        let mut page_token: Option<String> = None;
+        loop {
+            let mut request = hub
+                .revisions()
+                .list(file_id)
+                .page_size(MAX_PAGE_SIZE as i32)
+                .param(
+                    "fields",
+                    &format!("nextPageToken,revisions({})", REVISION_FIELDS),
+                );
+            if let Some(page_token) = &page_token {
+                request = request.page_token(page_token);
+            }
+            let (_, list) = request.doit().await.map_err(map_hub_error)?;
+
+            revisions.extend(
+                list.revisions
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(to_revision),
+            );
+            match list.next_page_token {
+                Some(next_page_token) => page_token = Some(next_page_token),
+                None => return Ok(revisions),
+            }
+        }
+    }
+
+    async fn get_revision(
+        &self,
+        access_token: String,
+        file_id: &str,
+        revision_id: &str,
+    ) -> Result<Revision, google_drive_service::Error> {
+        let hub = create_hub(access_token).await?;
+
+        let (_, revision) = hub
+            .revisions()
+            .get(file_id, revision_id)
+            .param("fields", REVISION_FIELDS)
+            .doit()
+            .await
+            .map_err(map_hub_error)?;
+
+        Ok(to_revision(revision))
+    }
+
+    async fn download_revision_content(
+        &self,
+        access_token: String,
+        file_id: &str,
+        revision_id: &str,
+    ) -> Result<ByteStream, google_drive_service::Error> {
+        let client = Client::new();
+        let response = client
+            .get(format!(
+                "https://www.googleapis.com/drive/v3/files/{}/revisions/{}",
+                path_segment(file_id),
+                path_segment(revision_id)
+            ))
+            .bearer_auth(&access_token)
+            .query(&[("alt", "media")])
+            .send()
+            .await
+            .map_err(|e| {
+                google_drive_service::Error::Unknown(format!("Error sending request: {}", e))
+            })?;
+        let response = check_response_status(response).await?;
+
+        let content = response.bytes_stream().map_err(|e| {
+            google_drive_service::Error::Unknown(format!("Error reading chunk: {}", e))
+        });
+
+        Ok(Box::pin(content))
+    }
+
+    async fn update_revision(
+        &self,
+        access_token: String,
+        file_id: &str,
+        revision_id: &str,
+        keep_forever: bool,
+    ) -> Result<Revision, google_drive_service::Error> {
+        let hub = create_hub(access_token).await?;
+
+        let changes = google_drive3::api::Revision {
+            keep_forever: Some(keep_forever),
+            ..Default::default()
+        };
+        let (_, revision) = hub
+            .revisions()
+            .update(changes, file_id, revision_id)
+            .param("fields", REVISION_FIELDS)
+            .doit()
+            .await
+            .map_err(map_hub_error)?;
+
+        Ok(to_revision(revision))
+    }
+
+    async fn delete_revision(
+        &self,
+        access_token: String,
+        file_id: &str,
+        revision_id: &str,
+    ) -> Result<(), google_drive_service::Error> {
+        let hub = create_hub(access_token).await?;
+
+        hub.revisions()
+            .delete(file_id, revision_id)
+            .doit()
+            .await
+            .map_err(map_hub_error)?;
+
+        Ok(())
+    }
+
+    async fn get_start_page_token(
+        &self,
+        access_token: String,
+    ) -> Result<String, google_drive_service::Error> {
+        let hub = create_hub(access_token).await?;
+
+        let (_, token) = hub
+            .changes()
+            .get_start_page_token()
+            .supports_all_drives(true)
+            .doit()
+            .await
+            .map_err(map_hub_error)?;
+
+        token.start_page_token.ok_or_else(|| {
+            google_drive_service::Error::Unknown("Drive returned no start page token".to_string())
+        })
+    }
+
+    async fn list_changes(
+        &self,
+        access_token: String,
+        page_token: &str,
+    ) -> Result<ChangesPage, google_drive_service::Error> {
+        let hub = create_hub(access_token).await?;
+
+        let (_, changes) = hub
+            .changes()
+            .list(page_token)
+            .supports_all_drives(true)
+            .include_items_from_all_drives(true)
+            .page_size(MAX_PAGE_SIZE as i32)
+            .include_removed(true)
+            .param(
+                "fields",
+                &format!(
+                    "nextPageToken,newStartPageToken,changes(fileId,removed,time,file({}))",
+                    file_fields(&FileField::DEFAULT)
+                ),
+            )
+            .doit()
+            .await
+            .map_err(map_hub_error)?;
+
+        Ok(ChangesPage {
+            changes: changes
+                .changes
+                .unwrap_or_default()
+                .into_iter()
+                // Changes to shared drives themselves come without a file
+                .filter_map(|change| {
+                    Some(FileChange::new(
+                        change.file_id?,
+                        change.removed.unwrap_or_default(),
+                        change.time,
+                        change.file.map(to_file_info),
+                    ))
+                })
+                .collect(),
+            next_page_token: changes.next_page_token,
+            new_start_page_token: changes.new_start_page_token,
+        })
+    }
+
+    async fn watch_changes(
+        &self,
+        access_token: String,
+        page_token: &str,
+        channel: &ChannelRequest,
+    ) -> Result<WatchChannel, google_drive_service::Error> {
+        let hub = create_hub(access_token).await?;
+
+        let request = Channel {
+            id: Some(channel.id.clone()),
+            type_: Some("web_hook".to_string()),
+            address: Some(channel.address.clone()),
+            expiration: Some(channel.expires_at.timestamp_millis()),
+            ..Default::default()
+        };
+        let (_, watched) = hub
+            .changes()
+            .watch(request, page_token)
+            .supports_all_drives(true)
+            .include_items_from_all_drives(true)
+            .include_removed(true)
+            .doit()
+            .await
+            .map_err(map_hub_error)?;
+
+        Ok(WatchChannel {
+            id: channel.id.clone(),
+            resource_id: watched.resource_id.unwrap_or_default(),
+            // Drive can shorten the expiration it was asked for
+            expires_at: watched
+                .expiration
+                .and_then(|x| Utc.timestamp_millis_opt(x).single())
+                .unwrap_or(channel.expires_at),
+        })
+    }
+
+    async fn stop_channel(
+        &self,
+        access_token: String,
+        channel: &WatchChannel,
+    ) -> Result<(), google_drive_service::Error> {
+        let hub = create_hub(access_token).await?;
+
+        let request = Channel {
+            id: Some(channel.id.clone()),
+            resource_id: Some(channel.resource_id.clone()),
+            ..Default::default()
+        };
+        hub.channels()
+            .stop(request)
+            .doit()
+            .await
+            .map_err(map_hub_error)?;
+
+        Ok(())
+    }
+}
+
+const REVISION_FIELDS: &str = "id,modifiedTime,size,md5Checksum,mimeType,originalFilename,\
+    keepForever,lastModifyingUser(displayName,emailAddress)";
+
+fn to_revision(revision: google_drive3::api::Revision) -> Revision {
+    Revision {
+        id: revision.id.unwrap_or_default(),
+        modified_at: revision.modified_time,
+        size: revision.size.and_then(|x| u64::try_from(x).ok()),
+        md5_checksum: revision.md5_checksum,
+        file_type: revision.mime_type.unwrap_or_default(),
+        original_file_name: revision.original_filename,
+        author: revision.last_modifying_user.map(|user| FileOwner {
+            display_name: user.display_name,
+            email: user.email_address,
+        }),
+        keep_forever: revision.keep_forever.unwrap_or_default(),
+    }
+}
+
+const PERMISSION_FIELDS: &str = "id,type,role,emailAddress,domain,displayName,expirationTime";
+
+fn permission_type_name(permission_type: PermissionType) -> &'static str {
+    match permission_type {
+        PermissionType::User => "user",
+        PermissionType::Group => "group",
+        PermissionType::Domain => "domain",
+        PermissionType::Anyone => "anyone",
+    }
+}
+
+fn permission_role_name(role: PermissionRole) -> &'static str {
+    match role {
+        PermissionRole::Owner => "owner",
+        PermissionRole::Organizer => "organizer",
+        PermissionRole::FileOrganizer => "fileOrganizer",
+        PermissionRole::Writer => "writer",
+        PermissionRole::Commenter => "commenter",
+        PermissionRole::Reader => "reader",
+    }
+}
+
+/// `None` for permissions of a type or role this API doesn't know about.
+fn to_permission(permission: google_drive3::api::Permission) -> Option<Permission> {
+    let permission_type = match permission.type_.as_deref()? {
+        "user" => PermissionType::User,
+        "group" => PermissionType::Group,
+        "domain" => PermissionType::Domain,
+        "anyone" => PermissionType::Anyone,
+        _ => return None,
+    };
+    let role = match permission.role.as_deref()? {
+        "owner" => PermissionRole::Owner,
+        "organizer" => PermissionRole::Organizer,
+        "fileOrganizer" => PermissionRole::FileOrganizer,
+        "writer" => PermissionRole::Writer,
+        "commenter" => PermissionRole::Commenter,
+        "reader" => PermissionRole::Reader,
+        _ => return None,
+    };
+
+    Some(Permission {
+        id: permission.id?,
+        permission_type,
+        role,
+        email_address: permission.email_address,
+        domain: permission.domain,
+        display_name: permission.display_name,
+        expires_at: permission.expiration_time,
+    })
+}
+
+/// Drive field selector for the given `FileInfo` fields.
+fn file_fields(fields: &[FileField]) -> String {
+    fields
+        .iter()
+        .map(|field| match field {
+            FileField::Id => "id",
+            FileField::Name => "name",
+            FileField::FileType => "mimeType",
+            FileField::CreatedAt => "createdTime",
+            FileField::ModifiedAt => "modifiedTime",
+            FileField::Size => "size",
+            FileField::Md5Checksum => "md5Checksum",
+            FileField::Parents => "parents",
+            FileField::Owners => "owners(displayName,emailAddress)",
+            FileField::WebViewLink => "webViewLink",
+            FileField::ThumbnailLink => "thumbnailLink",
+            FileField::Trashed => "trashed",
+            FileField::Starred => "starred",
+            FileField::AppProperties => "appProperties",
+            FileField::DriveId => "driveId",
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+async fn set_trashed(
+    access_token: String,
+    file_id: &str,
+    trashed: bool,
+) -> Result<FileInfo, google_drive_service::Error> {
+    let hub = create_hub(access_token).await?;
+
+    let changes = File {
+        trashed: Some(trashed),
+        ..Default::default()
+    };
+    let (_, file) = hub
+        .files()
+        .update(changes, file_id)
+        .supports_all_drives(true)
+        .param("fields", &file_fields(&FileField::DEFAULT))
+        .doit_without_upload()
+        .await
+        .map_err(map_hub_error)?;
+
+    Ok(to_file_info(file))
+}
+
+fn to_file_info(file: File) -> FileInfo {
+    FileInfo {
+        id: file.id.unwrap_or_default(),
+        name: file.name.unwrap_or_default(),
+        file_type: file.mime_type.unwrap_or_default(),
+        created_at: file.created_time,
+        modified_at: file.modified_time,
+        size: file.size.and_then(|x| u64::try_from(x).ok()),
+        md5_checksum: file.md5_checksum,
+        parents: file.parents,
+        owners: file.owners.map(|owners| {
+            owners
+                .into_iter()
+                .map(|owner| FileOwner {
+                    display_name: owner.display_name,
+                    email: owner.email_address,
+                })
+                .collect()
+        }),
+        web_view_link: file.web_view_link,
+        thumbnail_link: file.thumbnail_link,
+        trashed: file.trashed,
+        starred: file.starred,
+        app_properties: file.app_properties,
+        drive_id: file.drive_id,
+    }
+}
+
+const UPLOAD_CHUNK_ALIGNMENT: usize = 256 * 1024;
+
+/// Attempts per chunk before a resumable upload gives up.
+const UPLOAD_MAX_RETRIES: u32 = 5;
+
+/// Opens a resumable upload session with a create or update request and returns its URI.
+/// The metadata of the uploaded file comes back with the default projection once the
+/// upload finishes.
+async fn start_upload_session(
+    req: reqwest::RequestBuilder,
+    access_token: String,
+    mime_type: &str,
+) -> Result<String, google_drive_service::Error> {
+    let req = req
+        .query(&[
+            ("uploadType", "resumable"),
+            ("supportsAllDrives", "true"),
+            ("fields", &file_fields(&FileField::DEFAULT)),
+        ])
+        .bearer_auth(&access_token)
+        .header("X-Upload-Content-Type", mime_type);
+
+    let response = req.send().await.map_err(|e| {
+        google_drive_service::Error::Unknown(format!("Error sending request: {}", e))
+    })?;
+    let response = check_response_status(response).await?;
+
+    response
+        .headers()
+        .get(header::LOCATION)
+        .and_then(|x| x.to_str().ok())
+        .map(|x| x.to_string())
+        .ok_or_else(|| {
+            google_drive_service::Error::Unknown("Upload session URI missing".to_string())
+        })
+}
+
+enum UploadProgress {
+    /// The upload finished, with Drive's response body
+    Complete(String),
+    /// The upload continues from this byte offset
+    Incomplete(u64),
+}
+
+/// Sends one chunk of a resumable upload. Transient failures are retried with exponential
+/// backoff, asking Drive how much it received before each retry.
+async fn send_upload_chunk(
+    client: &Client,
+    session_uri: &str,
+    offset: u64,
+    chunk: &[u8],
+    total: Option<u64>,
+) -> Result<UploadProgress, google_drive_service::Error> {
+    let mut attempt = 0;
+    let mut result = client
+        .put(session_uri)
+        .header(
+            header::CONTENT_RANGE,
+            content_range(offset, chunk.len(), total),
+        )
+        .body(chunk.to_vec())
+        .send()
+        .await;
+
+    loop {
+        let err = match result {
+            Ok(response) => match response.status().as_u16() {
+                200 | 201 => {
+                    let msg = response.text().await.map_err(|e| {
+                        google_drive_service::Error::Unknown(format!(
+                            "Error reading response: {}",
+                            e
+                        ))
+                    })?;
+                    return Ok(UploadProgress::Complete(msg));
+                }
+                308 => {
+                    let confirmed = response
+                        .headers()
+                        .get(header::RANGE)
+                        .and_then(|x| x.to_str().ok())
+                        .and_then(parse_upload_range)
+                        .unwrap_or(0);
+                    return Ok(UploadProgress::Incomplete(confirmed));
+                }
+                404 | 410 => {
+                    return Err(google_drive_service::Error::Unknown(
+                        "Upload session expired".to_string(),
+                    ))
+                }
+                status if status == 429 || status >= 500 => {
+                    format!("Google Drive responded with {}", status)
+                }
+                _ => return Err(response_error(response).await),
+            },
+            Err(err) => format!("Error sending chunk: {}", err),
+        };
+
+        attempt += 1;
+        if attempt > UPLOAD_MAX_RETRIES {
+            return Err(google_drive_service::Error::Unknown(err));
+        }
+        eprintln!("Upload chunk failed, retrying ({}): {}", attempt, err);
+        tokio::time::sleep(Duration::from_millis(500 * 2u64.pow(attempt - 1))).await;
+
+        // Ask for the upload status; the answer is handled like a chunk response
+        result = client
+            .put(session_uri)
+            .header(header::CONTENT_RANGE, content_range(0, 0, total))
+            .send()
+            .await;
+    }
+}
+
+/// Builds the `Content-Range` of a resumable upload request. An empty chunk asks for the
+/// upload status, or finishes the upload when the total size is known.
+fn content_range(offset: u64, len: usize, total: Option<u64>) -> String {
+    let total = total.map_or("*".to_string(), |x| x.to_string());
+    if len == 0 {
+        format!("bytes */{}", total)
+    } else {
+        format!("bytes {}-{}/{}", offset, offset + len as u64 - 1, total)
+    }
+}
+
+/// Parses the `Range: bytes=0-N` header of a 308 response into the next offset to send.
+fn parse_upload_range(range: &str) -> Option<u64> {
+    let (_, end) = range.strip_prefix("bytes=")?.split_once('-')?;
+    end.parse::<u64>().ok().map(|x| x + 1)
+}
+
+async fn cancel_upload(client: &Client, session_uri: &str) {
+    if let Err(err) = client.delete(session_uri).send().await {
+        eprintln!("Error cancelling upload: {:?}", err);
+    }
+}
+
+/// Characters left as they are in a URL path segment (RFC 3986 `unreserved`).
+const PATH_SEGMENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+/// Encodes an id for a URL path, so it can't point the request at another resource.
+fn path_segment(id: &str) -> String {
+    utf8_percent_encode(id, PATH_SEGMENT).to_string()
+}
+
+/// Quotes a value for a Drive `q` expression, escaping quotes and backslashes so user
+/// input can't change the query.
+fn quote_query_value(value: &str) -> String {
+    format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+/// Parameters of a `files.list` call, kept apart from the hub so they can be checked
+/// without calling Drive.
+#[derive(Debug, Default, PartialEq)]
+struct ListRequest {
+    q: String,
+    fields: String,
+    page_size: i32,
+    page_token: Option<String>,
+    order_by: Option<String>,
+    /// `drive` when the listing is limited to one shared drive
+    corpora: Option<&'static str>,
+    drive_id: Option<String>,
+}
+
+fn list_fields(fields: &[FileField]) -> String {
+    let fields: &[FileField] = if fields.is_empty() {
+        &FileField::DEFAULT
+    } else {
+        fields
+    };
+    format!("nextPageToken,files({})", file_fields(fields))
+}
+
+fn list_request(query: &FileQuery) -> ListRequest {
+    ListRequest {
+        q: build_query(query),
+        fields: list_fields(&query.fields),
+        page_size: query.page_size() as i32,
+        page_token: query.page_token.clone(),
+        order_by: (!query.order_by.is_empty()).then(|| build_order_by(&query.order_by)),
+        corpora: query.drive_id.as_ref().map(|_| "drive"),
+        drive_id: query.drive_id.clone(),
+    }
+}
+
+fn search_request(query: &SearchQuery) -> ListRequest {
+    ListRequest {
+        q: build_search_query(query),
+        fields: list_fields(&query.fields),
+        page_size: query.page_size() as i32,
+        page_token: query.page_token.clone(),
+        order_by: None,
+        corpora: query.drive_id.as_ref().map(|_| "drive"),
+        drive_id: query.drive_id.clone(),
+    }
+}
+
+async fn list_page(
+    access_token: String,
+    list: ListRequest,
+) -> Result<FilesPage, google_drive_service::Error> {
+    let hub = create_hub(access_token).await?;
+    let mut request = hub
+        .files()
+        .list()
+        .supports_all_drives(true)
+        .include_items_from_all_drives(true)
+        .q(&list.q)
+        .page_size(list.page_size)
+        .add_scope("https://www.googleapis.com/auth/drive.metadata.readonly")
+        .param("fields", &list.fields);
+    if let Some(order_by) = &list.order_by {
+        request = request.order_by(order_by);
+    }
+    if let Some(page_token) = &list.page_token {
+        request = request.page_token(page_token);
+    }
+    if let Some(corpora) = list.corpora {
+        request = request.corpora(corpora);
+    }
+    if let Some(drive_id) = &list.drive_id {
+        request = request.drive_id(drive_id);
+    }
+
+    let files = match request.doit().await {
+        Ok((_resp, result)) => result,
+        Err(err) => return Err(map_hub_error(err)),
+    };
+
+    Ok(FilesPage {
+        files: files
+            .files
+            .unwrap_or_default()
+            .into_iter()
+            .map(to_file_info)
+            .collect(),
+        next_page_token: files.next_page_token,
+    })
+}
+
+fn build_query(query: &FileQuery) -> String {
+    let mut clauses = vec![format!(
+        "{} in parents",
+        quote_query_value(&query.folder_id)
+    )];
+    if let Some(mime_type) = &query.mime_type {
+        clauses.push(format!("mimeType = {}", quote_query_value(mime_type)));
+    }
+    if let Some(name) = &query.name_contains {
+        clauses.push(format!("name contains {}", quote_query_value(name)));
+    }
+    let dates = [
+        ("createdTime >=", query.created_after),
+        ("createdTime <", query.created_before),
+        ("modifiedTime >=", query.modified_after),
+        ("modifiedTime <", query.modified_before),
+    ];
+    for (condition, date) in dates {
+        if let Some(date) = date {
+            clauses.push(format!(
+                "{} {}",
+                condition,
+                quote_query_value(&date.to_rfc3339_opts(SecondsFormat::Secs, true))
+            ));
+        }
+    }
+
+    clauses.join(" and ")
+}
+
+fn build_search_query(query: &SearchQuery) -> String {
+    let mut clauses = vec![format!("trashed = {}", query.trashed)];
+    if let Some(text) = &query.full_text {
+        clauses.push(format!("fullText contains {}", quote_query_value(text)));
+    }
+    if let Some(name) = &query.name_contains {
+        clauses.push(format!("name contains {}", quote_query_value(name)));
+    }
+    if let Some(mime_type) = &query.mime_type {
+        clauses.push(format!("mimeType = {}", quote_query_value(mime_type)));
+    }
+    if let Some(owner) = &query.owner {
+        clauses.push(format!("{} in owners", quote_query_value(owner)));
+    }
+    if let Some(starred) = query.starred {
+        clauses.push(format!("starred = {}", starred));
+    }
+    let dates = [
+        ("modifiedTime >=", query.modified_after),
+        ("modifiedTime <", query.modified_before),
+    ];
+    for (condition, date) in dates {
+        if let Some(date) = date {
+            clauses.push(format!(
+                "{} {}",
+                condition,
+                quote_query_value(&date.to_rfc3339_opts(SecondsFormat::Secs, true))
+            ));
+        }
+    }
+    if !query.folder_ids.is_empty() {
+        let parents: Vec<String> = query
+            .folder_ids
+            .iter()
+            .map(|x| format!("{} in parents", quote_query_value(x)))
+            .collect();
+        clauses.push(format!("({})", parents.join(" or ")));
+    }
+
+    clauses.join(" and ")
+}
+
+fn build_order_by(order_by: &[SortKey]) -> String {
+    order_by
+        .iter()
+        .map(|key| {
+            let field = match key.field {
+                SortField::Name => "name_natural",
+                SortField::CreatedAt => "createdTime",
+                SortField::ModifiedAt => "modifiedTime",
+                SortField::Size => "quotaBytesUsed",
+                SortField::Folder => "folder",
+                SortField::Starred => "starred",
+            };
+            if key.descending {
+                format!("{} desc", field)
+            } else {
+                field.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn to_google_token(token: &BasicTokenResponse) -> GoogleToken {
+    GoogleToken::new(
+        token.access_token().secret().clone(),
+        token.refresh_token().map(|x| x.secret().clone()),
+        token
+            .expires_in()
+            .and_then(|x| chrono::Duration::from_std(x).ok())
+            .map(|x| Utc::now() + x),
+    )
+}
+
+fn map_hub_error(err: google_drive3::Error) -> google_drive_service::Error {
+    match err {
+        google_drive3::Error::BadRequest(json_value) => {
+            if json_value.to_string().contains("UNAUTHENTICATED") {
+                google_drive_service::Error::GoogleUnauthenticated
+            } else {
+                let status = json_value["error"]["code"].as_u64().unwrap_or_default();
+                drive_error(status as u16, &json_value)
+            }
+        }
+        err => {
+            eprintln!("Error: {:?}", err);
+            google_drive_service::Error::Unknown(err.to_string())
+        }
+    }
+}
+
+/// Thumbnail links end with a size parameter such as `=s220`, replaced here with the
+/// width wanted. Links without one get it appended.
+fn sized_thumbnail_link(thumbnail_link: &str, width: u32) -> String {
+    let base = match thumbnail_link.rsplit_once('=') {
+        Some((base, size))
+            if size.starts_with('s') && size[1..].bytes().all(|x| x.is_ascii_digit()) =>
+        {
+            base
+        }
+        _ => thumbnail_link,
+    };
+    format!("{}=w{}", base, width)
+}
+
+/// Maps an error response from Drive, given its status and JSON body.
+fn drive_error(status: u16, body: &serde_json::Value) -> google_drive_service::Error {
+    let message = body["error"]["message"]
+        .as_str()
+        .or(body.as_str())
+        .unwrap_or_default()
+        .to_string();
+    let reason = body["error"]["errors"][0]["reason"]
+        .as_str()
+        .unwrap_or_default();
+
+    match status {
+        401 => google_drive_service::Error::GoogleUnauthenticated,
+        400 => google_drive_service::Error::InvalidRequest(message),
+        403 if reason == "storageQuotaExceeded" => {
+            google_drive_service::Error::QuotaExceeded(message)
+        }
+        // Drive also answers 403 when rate limited, which isn't on the user
+        403 if !reason.ends_with("RateLimitExceeded") => {
+            google_drive_service::Error::PermissionDenied(message)
+        }
+        404 => google_drive_service::Error::NotFound(message),
+        _ => google_drive_service::Error::Unknown(format!(
+            "Google Drive responded with {}: {}",
+            status, body
+        )),
+    }
+}
+
+async fn check_response_status(
+    response: reqwest::Response,
+) -> Result<reqwest::Response, google_drive_service::Error> {
+    if response.status().is_success() {
+        Ok(response)
+    } else {
+        Err(response_error(response).await)
+    }
+}
+
+async fn response_error(response: reqwest::Response) -> google_drive_service::Error {
+    let status = response.status();
+    let body = response.text().await.unwrap_or_default();
+    let body = serde_json::from_str(&body).unwrap_or(serde_json::Value::String(body));
+    drive_error(status.as_u16(), &body)
+}
+
+async fn create_hub(
+    access_token: String,
+) -> Result<
+    DriveHub<hyper_rustls::HttpsConnector<hyper_util::client::legacy::connect::HttpConnector>>,
+    google_drive_service::Error,
+> {
+    let auth = AccessTokenAuthenticator::builder(access_token)
+        .build()
+        .await
+        .map_err(|e| google_drive_service::Error::Unknown(e.to_string()))?;
+
+    let client = hyper_util::client::legacy::Client::builder(hyper_util::rt::TokioExecutor::new())
+        .build(
+            hyper_rustls::HttpsConnectorBuilder::new()
+                .with_native_roots()
+                .unwrap()
+                .https_or_http()
+                .enable_http1()
+                .build(),
+        );
+
+    Ok(DriveHub::new(client, auth))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::application::ports::google_drive_service::GoogleDriveServiceTrait;
+
+    #[tokio::test]
+    async fn test_get_google_auth_url() {
+        let config = crate::adapters::config::Config::new();
+
+        let google_drive_service =
+            crate::adapters::driven::google_drive_service::GoogleDriveService::new(
+                config.google_client_id.clone(),
+                config.google_client_secret.clone(),
+                config.google_auth_url.clone(),
+                config.google_token_url.clone(),
+                config.google_redirect_url.clone(),
+                config.upload_chunk_size,
+            )
+            .await;
+
+        let auth_url = google_drive_service
+            .get_google_auth_url(false)
+            .await
+            .unwrap();
+        assert!(!auth_url.url.is_empty());
+        assert!(!auth_url.state.is_empty());
+        assert!(!auth_url.pkce_verifier.is_empty());
+
+        println!("auth_url: {}", auth_url.url);
+        println!("csrf_token: {}", auth_url.state);
+    }
+
+    #[tokio::test]
+    async fn test_handle_google_callback() {
+        let config = crate::adapters::config::Config::new();
+
+        let google_drive_service =
+            crate::adapters::driven::google_drive_service::GoogleDriveService::new(
+                config.google_client_id.clone(),
+                config.google_client_secret.clone(),
+                config.google_auth_url.clone(),
+                config.google_token_url.clone(),
+                config.google_redirect_url.clone(),
+                config.upload_chunk_size,
+            )
+            .await;
+
+        let code = "".to_string();
+        let pkce_verifier = "".to_string();
+        let token = match google_drive_service
+            .handle_google_callback(code, pkce_verifier)
+            .await
+        {
+            Ok(token) => token,
+            Err(err) => {
+                println!("Error: {}", err);
+                panic!();
+            }
+        };
+        assert!(!token.access_token.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_get_google_email() {
+        let config = crate::adapters::config::Config::new();
+
+        let google_drive_service =
+            crate::adapters::driven::google_drive_service::GoogleDriveService::new(
+                config.google_client_id.clone(),
+                config.google_client_secret.clone(),
+                config.google_auth_url.clone(),
+                config.google_token_url.clone(),
+                config.google_redirect_url.clone(),
+                config.upload_chunk_size,
+            )
+            .await;
+
+        let access_token = "".to_string();
+
+        let email = match google_drive_service.get_google_email(access_token).await {
+            Ok(email) => email,
+            Err(err) => {
+                println!("Error: {}", err);
+                panic!();
+            }
+        };
+        assert!(!email.is_empty());
+
+        println!("email: {}", email);
+    }
+
+    #[test]
+    fn test_path_segment() {
+        use super::path_segment;
+
+        assert_eq!(path_segment("1AbC-d_e.f~"), "1AbC-d_e.f~");
+        assert_eq!(path_segment("../about?x=1#y"), "..%2Fabout%3Fx%3D1%23y");
+    }
+
+    #[test]
+    fn test_content_range() {
+        use super::content_range;
+
+        assert_eq!(content_range(0, 262144, None), "bytes 0-262143/*");
+        assert_eq!(
+            content_range(262144, 10, Some(262154)),
+            "bytes 262144-262153/262154"
+        );
+        assert_eq!(content_range(0, 0, Some(524288)), "bytes */524288");
+        assert_eq!(content_range(0, 0, None), "bytes */*");
+    }
+
+    #[test]
+    fn test_sized_thumbnail_link() {
+        use super::sized_thumbnail_link;
+
+        assert_eq!(
+            sized_thumbnail_link(
+                "https://lh3.googleusercontent.com/drive-storage/abc=s220",
+                480
+            ),
+            "https://lh3.googleusercontent.com/drive-storage/abc=w480"
+        );
+        assert_eq!(
+            sized_thumbnail_link("https://lh3.googleusercontent.com/abc", 480),
+            "https://lh3.googleusercontent.com/abc=w480"
+        );
+    }
+
+    #[test]
+    fn test_parse_upload_range() {
+        use super::parse_upload_range;
+
+        assert_eq!(parse_upload_range("bytes=0-262143"), Some(262144));
+        assert_eq!(parse_upload_range("0-262143"), None);
+        assert_eq!(parse_upload_range("bytes=0-"), None);
+    }
+
+    #[test]
+    fn test_build_query_escapes_values() {
+        use super::build_query;
+        use crate::domain::value_objects::file_query::FileQuery;
+        use chrono::{TimeZone, Utc};
+
+        let mut query = FileQuery {
+            folder_id: "abc' or '1' = '1".to_string(),
+            ..Default::default()
+        };
+        query.name_contains = Some("it's a \\ test".to_string());
+        query.mime_type = Some("application/pdf".to_string());
+        query.modified_after = Some(Utc.with_ymd_and_hms(2024, 1, 2, 3, 4, 5).unwrap());
+
+        assert_eq!(
+            build_query(&query),
+            "'abc\\' or \\'1\\' = \\'1' in parents \
+             and mimeType = 'application/pdf' \
+             and name contains 'it\\'s a \\\\ test' \
+             and modifiedTime >= '2024-01-02T03:04:05Z'"
+        );
+    }
+
+    #[test]
+    fn test_build_search_query() {
+        use super::build_search_query;
+        use crate::domain::value_objects::search_query::SearchQuery;
+
+        let query = SearchQuery {
+            full_text: Some("contrato' or name contains '".to_string()),
+            owner: Some("client@example.com".to_string()),
+            starred: Some(true),
+            folder_ids: vec!["a".to_string(), "b".to_string()],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            build_search_query(&query),
+            "trashed = false \
+             and fullText contains 'contrato\\' or name contains \\'' \
+             and 'client@example.com' in owners \
+             and starred = true \
+             and ('a' in parents or 'b' in parents)"
+        );
+    }
+
+    #[test]
+    fn test_list_request_in_shared_drive() {
+        use super::{list_request, search_request};
+        use crate::domain::value_objects::{file_query::FileQuery, search_query::SearchQuery};
+
+        let query = FileQuery {
+            folder_id: "drive-1".to_string(),
+            drive_id: Some("drive-1".to_string()),
+            ..Default::default()
+        };
+        let request = list_request(&query);
+        assert_eq!(request.q, "'drive-1' in parents");
+        assert_eq!(request.corpora, Some("drive"));
+        assert_eq!(request.drive_id.as_deref(), Some("drive-1"));
+
+        let request = search_request(&SearchQuery {
+            drive_id: Some("drive-1".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(request.corpora, Some("drive"));
+        assert_eq!(request.drive_id.as_deref(), Some("drive-1"));
+
+        let request = list_request(&FileQuery {
+            folder_id: "root".to_string(),
+            ..Default::default()
+        });
+        assert_eq!(request.corpora, None);
+        assert_eq!(request.drive_id, None);
+        assert_eq!(request.order_by, None);
+    }
+
+    #[test]
+    fn test_build_order_by() {
+        use super::build_order_by;
+        use crate::domain::value_objects::file_query::SortKey;
+
+        let order_by = SortKey::parse_list("folder,modified_at desc").unwrap();
+        assert_eq!(build_order_by(&order_by), "folder,modifiedTime desc");
+    }
+
+    #[test]
+    fn test_map_hub_error() {
+        use super::map_hub_error;
+        use crate::application::ports::google_drive_service::Error;
+
+        let err = map_hub_error(google_drive3::Error::BadRequest(serde_json::json!({
+            "error": { "code": 404, "message": "File not found: abc." }
+        })));
+        assert!(matches!(err, Error::NotFound(message) if message == "File not found: abc."));
+
+        let err = map_hub_error(google_drive3::Error::BadRequest(serde_json::json!({
+            "error": { "code": 403, "message": "Forbidden" }
+        })));
+        assert!(matches!(err, Error::PermissionDenied(_)));
+
+        let err = map_hub_error(google_drive3::Error::BadRequest(serde_json::json!({
+            "error": {
+                "code": 403,
+                "message": "Rate limit",
+                "errors": [{ "reason": "userRateLimitExceeded" }]
+            }
+        })));
+        assert!(matches!(err, Error::Unknown(_)));
+    }
+
+    #[test]
+    fn test_file_fields() {
+        use super::file_fields;
+        use crate::domain::value_objects::file_info::FileField;
+
+        assert_eq!(
+            file_fields(&[FileField::Id, FileField::FileType, FileField::Owners]),
+            "id,mimeType,owners(displayName,emailAddress)"
+        );
+    }
+
+    #[test]
+    fn test_to_file_info_partial_response() {
+        use super::to_file_info;
+
+        let file = to_file_info(google_drive3::api::File {
+            id: Some("abc".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(file.id, "abc");
+        assert_eq!(file.name, "");
+        assert!(file.size.is_none());
     }
 }
@@ -1,2 +1,13 @@
+pub mod auth_request_repository;
+pub mod change_cursor_repository;
+pub mod file_parents_repository;
+pub mod google_drive_service;
+pub mod pdf_editor;
+pub mod pdf_reader;
+pub mod pdf_renderer;
+pub mod share_link_repository;
+pub mod thumbnail_repository;
+pub mod upload_request_repository;
 pub mod user_repository;
-pub mod google_drive_service;
\ No newline at end of file
+pub mod webhook_repository;
+pub mod webhook_sender;
@@ -0,0 +1,125 @@
+use std::{
+    env,
+    path::{Path, PathBuf},
+    process::Stdio,
+    time::Duration,
+};
+
+use bytes::Bytes;
+use tokio::{fs, process::Command};
+use uuid::Uuid;
+
+use crate::{
+    application::ports::pdf_editor::{self, PdfEditorTrait, PdfPart},
+    domain::value_objects::page_range::PageRanges,
+};
+
+/// Combines PDF pages with `qpdf`, which has to be installed where the API runs. It needs
+/// the documents as files, so they are written to a temporary folder removed afterwards.
+#[derive(Clone)]
+pub struct PdfEditor {
+    program: String,
+    timeout: Duration,
+}
+
+impl PdfEditor {
+    /// `program` is the path to `qpdf`, a run taking longer than `timeout` is killed.
+    pub fn new(program: String, timeout: Duration) -> Self {
+        Self { program, timeout }
+    }
+
+    async fn run(&self, dir: &Path, parts: &[PdfPart]) -> Result<Bytes, pdf_editor::Error> {
+        let mut command = Command::new(&self.program);
+        command.args(["--empty", "--pages"]);
+        for (i, part) in parts.iter().enumerate() {
+            let path = dir.join(format!("{}.pdf", i));
+            fs::write(&path, &part.content)
+                .await
+                .map_err(|err| pdf_editor::Error::Unavailable(err.to_string()))?;
+            command.arg(&path);
+            if let Some(pages) = &part.pages {
+                command.arg(qpdf_ranges(pages));
+            }
+        }
+        let output_path = dir.join("output.pdf");
+        command.arg("--").arg(&output_path);
+
+        let output = command
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .output();
+        let output = match tokio::time::timeout(self.timeout, output).await {
+            Ok(Ok(output)) => output,
+            Ok(Err(err)) => {
+                return Err(pdf_editor::Error::Unavailable(format!(
+                    "Error starting {}: {}",
+                    self.program, err
+                )))
+            }
+            Err(_) => {
+                return Err(pdf_editor::Error::Unavailable(format!(
+                    "Editing took more than {} seconds",
+                    self.timeout.as_secs()
+                )))
+            }
+        };
+
+        // 3 means it succeeded with warnings, which damaged documents often raise
+        if !matches!(output.status.code(), Some(0) | Some(3)) {
+            return Err(pdf_editor::Error::InvalidDocument(
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            ));
+        }
+        fs::read(&output_path)
+            .await
+            .map(Bytes::from)
+            .map_err(|err| pdf_editor::Error::Unavailable(err.to_string()))
+    }
+}
+
+impl PdfEditorTrait for PdfEditor {
+    async fn combine(&self, parts: &[PdfPart]) -> Result<Bytes, pdf_editor::Error> {
+        let dir = TempDir(env::temp_dir().join(format!("pdf-{}", Uuid::new_v4())));
+        fs::create_dir(&dir.0)
+            .await
+            .map_err(|err| pdf_editor::Error::Unavailable(err.to_string()))?;
+        self.run(&dir.0, parts).await
+    }
+}
+
+/// Folder removed when dropped, even when the request is dropped halfway.
+struct TempDir(PathBuf);
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        if let Err(err) = std::fs::remove_dir_all(&self.0) {
+            eprintln!("Error removing {}: {}", self.0.display(), err);
+        }
+    }
+}
+
+/// Ranges in qpdf's syntax, where `z` is the last page.
+fn qpdf_ranges(pages: &PageRanges) -> String {
+    let ranges: Vec<String> = pages
+        .0
+        .iter()
+        .map(|x| match x.end {
+            Some(end) => format!("{}-{}", x.start, end),
+            None => format!("{}-z", x.start),
+        })
+        .collect();
+    ranges.join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_qpdf_ranges() {
+        let pages = PageRanges::parse("1-3,5,8-").unwrap();
+        assert_eq!(qpdf_ranges(&pages), "1-3,5-5,8-z");
+    }
+}
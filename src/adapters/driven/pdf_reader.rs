@@ -0,0 +1,162 @@
+use std::time::Duration;
+
+use bytes::Bytes;
+use chrono::{DateTime, NaiveDateTime, Utc};
+
+use crate::{
+    adapters::driven::pdf_renderer::run_piped,
+    application::ports::pdf_reader::{self, PdfReaderTrait},
+    domain::value_objects::pdf_document::PdfInfo,
+};
+
+/// Reads PDF files with poppler's `pdfinfo` and `pdftotext`, which have to be installed
+/// where the API runs. Like `PdfRenderer`, the document goes through their stdin.
+#[derive(Clone)]
+pub struct PdfReader {
+    info_program: String,
+    text_program: String,
+    timeout: Duration,
+}
+
+impl PdfReader {
+    /// Paths to `pdfinfo` and `pdftotext`, a run taking longer than `timeout` is killed.
+    pub fn new(info_program: String, text_program: String, timeout: Duration) -> Self {
+        Self {
+            info_program,
+            text_program,
+            timeout,
+        }
+    }
+}
+
+impl PdfReaderTrait for PdfReader {
+    async fn info(&self, content: Bytes) -> Result<PdfInfo, pdf_reader::Error> {
+        let args = ["-isodates", "-enc", "UTF-8", "-"];
+        let stdout = run(&self.info_program, &args, content, self.timeout).await?;
+        Ok(parse_info(&stdout))
+    }
+
+    async fn text(&self, content: Bytes) -> Result<Vec<String>, pdf_reader::Error> {
+        let args = ["-enc", "UTF-8", "-", "-"];
+        let stdout = run(&self.text_program, &args, content, self.timeout).await?;
+
+        // Every page ends with a form feed, the last one included
+        let mut pages: Vec<String> = stdout
+            .split('\u{c}')
+            .map(|x| x.trim_end().to_string())
+            .collect();
+        if pages.len() > 1 && pages.last().is_some_and(|x| x.is_empty()) {
+            pages.pop();
+        }
+        Ok(pages)
+    }
+}
+
+async fn run(
+    program: &str,
+    args: &[&str],
+    content: Bytes,
+    timeout: Duration,
+) -> Result<String, pdf_reader::Error> {
+    let output = run_piped(program, args, content, timeout)
+        .await
+        .map_err(pdf_reader::Error::Unavailable)?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if needs_password(&stderr) {
+            return Err(pdf_reader::Error::Encrypted);
+        }
+        return Err(pdf_reader::Error::InvalidDocument(
+            stderr.trim().to_string(),
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Whether poppler failed because the document has a user password, which it reports as
+/// an incorrect one since none was given.
+fn needs_password(stderr: &str) -> bool {
+    stderr.contains("Incorrect password")
+}
+
+/// Reads the `Key: value` lines `pdfinfo` prints.
+fn parse_info(output: &str) -> PdfInfo {
+    let mut info = PdfInfo::default();
+    for line in output.lines() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        let text = || Some(value.to_string()).filter(|x| !x.is_empty());
+        match key {
+            "Title" => info.title = text(),
+            "Author" => info.author = text(),
+            "Producer" => info.producer = text(),
+            "CreationDate" => info.created_at = parse_iso_date(value),
+            "Pages" => info.page_count = value.parse().unwrap_or_default(),
+            "Encrypted" => info.encrypted = value.starts_with("yes"),
+            _ => {}
+        }
+    }
+    info
+}
+
+/// Dates as `pdfinfo -isodates` prints them. The offset can come without minutes, such
+/// as `+02`, or be left out, in which case the date is taken as UTC.
+fn parse_iso_date(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .or_else(|_| DateTime::parse_from_rfc3339(&format!("{}:00", value)))
+        .map(|x| x.with_timezone(&Utc))
+        .ok()
+        .or_else(|| {
+            NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S")
+                .ok()
+                .map(|x| x.and_utc())
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_info() {
+        let output = "Title:           Contrato: anexo\n\
+            Author:          \n\
+            Producer:        LibreOffice 7.6\n\
+            CreationDate:    2024-06-01T12:30:45+02\n\
+            Pages:           12\n\
+            Encrypted:       yes (print:yes copy:no change:no addNotes:no)\n";
+        let info = parse_info(output);
+        assert_eq!(info.title.as_deref(), Some("Contrato: anexo"));
+        assert_eq!(info.author, None);
+        assert_eq!(info.producer.as_deref(), Some("LibreOffice 7.6"));
+        assert_eq!(
+            info.created_at,
+            DateTime::parse_from_rfc3339("2024-06-01T10:30:45Z")
+                .ok()
+                .map(|x| x.with_timezone(&Utc))
+        );
+        assert_eq!(info.page_count, 12);
+        assert!(info.encrypted);
+    }
+
+    #[test]
+    fn test_needs_password() {
+        assert!(needs_password("Command Line Error: Incorrect password\n"));
+        assert!(!needs_password(
+            "Syntax Error: Couldn't find trailer dictionary\n"
+        ));
+    }
+
+    #[test]
+    fn test_parse_iso_date() {
+        let expected = DateTime::parse_from_rfc3339("2024-06-01T12:30:45Z")
+            .ok()
+            .map(|x| x.with_timezone(&Utc));
+        assert_eq!(parse_iso_date("2024-06-01T12:30:45Z"), expected);
+        assert_eq!(parse_iso_date("2024-06-01T14:30:45+02:00"), expected);
+        assert_eq!(parse_iso_date("2024-06-01T12:30:45"), expected);
+        assert_eq!(parse_iso_date("none"), None);
+    }
+}
@@ -0,0 +1,95 @@
+use std::{process::Output, process::Stdio, time::Duration};
+
+use bytes::Bytes;
+use tokio::{io::AsyncWriteExt, process::Command};
+
+use crate::application::ports::pdf_renderer::{self, PdfRendererTrait};
+
+/// Renders PDF pages with poppler's `pdftoppm`, which has to be installed where the API
+/// runs. The document goes through its stdin and the image comes back on its stdout, so
+/// nothing is written to disk.
+#[derive(Clone)]
+pub struct PdfRenderer {
+    program: String,
+    timeout: Duration,
+}
+
+impl PdfRenderer {
+    /// `program` is the path to `pdftoppm`, a render taking longer than `timeout` is killed.
+    pub fn new(program: String, timeout: Duration) -> Self {
+        Self { program, timeout }
+    }
+}
+
+impl PdfRendererTrait for PdfRenderer {
+    async fn render_first_page(
+        &self,
+        content: Bytes,
+        width: u32,
+    ) -> Result<Bytes, pdf_renderer::Error> {
+        let width = width.to_string();
+        let args = [
+            "-png",
+            "-f",
+            "1",
+            "-l",
+            "1",
+            "-singlefile",
+            "-scale-to-x",
+            &width,
+            "-scale-to-y",
+            "-1",
+            "-",
+        ];
+        let output = run_piped(&self.program, &args, content, self.timeout)
+            .await
+            .map_err(pdf_renderer::Error::Unavailable)?;
+
+        if !output.status.success() || output.stdout.is_empty() {
+            return Err(pdf_renderer::Error::InvalidDocument(
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            ));
+        }
+        Ok(Bytes::from(output.stdout))
+    }
+}
+
+/// Runs one of poppler's tools with the document on its stdin, and collects what it writes.
+/// Fails with a message when it can't start or takes longer than `timeout`.
+pub(super) async fn run_piped(
+    program: &str,
+    args: &[&str],
+    content: Bytes,
+    timeout: Duration,
+) -> Result<Output, String> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|err| format!("Error starting {}: {}", program, err))?;
+
+    // Written from its own task, the tool may start answering before it has read the
+    // whole document
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let writer = tokio::spawn(async move {
+        // A tool that stops reading early fails with its own message
+        let _ = stdin.write_all(&content).await;
+    });
+
+    let output = match tokio::time::timeout(timeout, child.wait_with_output()).await {
+        Ok(Ok(output)) => output,
+        Ok(Err(err)) => return Err(err.to_string()),
+        Err(_) => {
+            return Err(format!(
+                "{} took more than {} seconds",
+                program,
+                timeout.as_secs()
+            ))
+        }
+    };
+    writer.abort();
+    Ok(output)
+}
@@ -0,0 +1,192 @@
+use std::time::Duration;
+
+use chrono::{DateTime, TimeZone, Utc};
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{self, doc},
+    options::{IndexOptions, ReturnDocument},
+    Client, Collection, IndexModel,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    application::ports::share_link_repository::{self, ShareLinkRepositoryTrait},
+    domain::{entities::share_link::ShareLink, value_objects::id::Id},
+};
+
+/// Stored shape of a `ShareLink`. Dates are kept as BSON dates so the download checks can
+/// compare them and Mongo can drop expired links through a TTL index.
+#[derive(Serialize, Deserialize)]
+struct ShareLinkDocument {
+    #[serde(rename = "_id")]
+    id: Id,
+    user_id: Id,
+    file_id: String,
+    created_at: bson::DateTime,
+    expires_at: bson::DateTime,
+    max_downloads: Option<i64>,
+    download_count: i64,
+    revoked_at: Option<bson::DateTime>,
+}
+
+impl From<ShareLink> for ShareLinkDocument {
+    fn from(share_link: ShareLink) -> Self {
+        Self {
+            id: share_link.id,
+            user_id: share_link.user_id,
+            file_id: share_link.file_id,
+            created_at: to_bson_date(share_link.created_at),
+            expires_at: to_bson_date(share_link.expires_at),
+            max_downloads: share_link.max_downloads.map(i64::from),
+            download_count: i64::from(share_link.download_count),
+            revoked_at: share_link.revoked_at.map(to_bson_date),
+        }
+    }
+}
+
+impl From<ShareLinkDocument> for ShareLink {
+    fn from(document: ShareLinkDocument) -> Self {
+        Self {
+            id: document.id,
+            user_id: document.user_id,
+            file_id: document.file_id,
+            created_at: from_bson_date(document.created_at),
+            expires_at: from_bson_date(document.expires_at),
+            max_downloads: document.max_downloads.map(|x| x.try_into().unwrap_or(0)),
+            download_count: document.download_count.try_into().unwrap_or(u32::MAX),
+            revoked_at: document.revoked_at.map(from_bson_date),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct ShareLinkRepository {
+    collection: Collection<ShareLinkDocument>,
+}
+
+impl ShareLinkRepository {
+    pub async fn new(db_url: &str, db_name: &str, collection_name: &str) -> Self {
+        let client = Client::with_uri_str(db_url)
+            .await
+            .expect("Failed to initialize client");
+        let db = client.database(db_name);
+        let collection = db.collection(collection_name);
+
+        let indexes = [
+            IndexModel::builder()
+                .keys(doc! { "expires_at": 1 })
+                .options(
+                    IndexOptions::builder()
+                        .expire_after(Duration::from_secs(0))
+                        .build(),
+                )
+                .build(),
+            IndexModel::builder().keys(doc! { "user_id": 1 }).build(),
+        ];
+        if let Err(err) = collection.create_indexes(indexes).await {
+            // Expiry is still checked on every download, the indexes only clean up and
+            // speed up listing
+            eprintln!("Error creating share link indexes: {:?}", err);
+        }
+
+        Self { collection }
+    }
+}
+
+impl ShareLinkRepositoryTrait for ShareLinkRepository {
+    async fn create(
+        &self,
+        share_link: ShareLink,
+    ) -> Result<ShareLink, share_link_repository::Error> {
+        let document = ShareLinkDocument::from(share_link.clone());
+        match self.collection.insert_one(&document).await {
+            Ok(_) => Ok(share_link),
+            Err(err) => Err(share_link_repository::Error::Unknown(err.to_string())),
+        }
+    }
+
+    async fn find_by_user(
+        &self,
+        user_id: Id,
+    ) -> Result<Vec<ShareLink>, share_link_repository::Error> {
+        let filter = doc! { "user_id": user_id.to_string() };
+        let documents: Vec<ShareLinkDocument> = self
+            .collection
+            .find(filter)
+            .sort(doc! { "created_at": -1 })
+            .await
+            .map_err(|err| share_link_repository::Error::Unknown(err.to_string()))?
+            .try_collect()
+            .await
+            .map_err(|err| share_link_repository::Error::Unknown(err.to_string()))?;
+
+        Ok(documents.into_iter().map(ShareLink::from).collect())
+    }
+
+    async fn revoke(&self, id: Id, user_id: Id) -> Result<ShareLink, share_link_repository::Error> {
+        let filter = doc! { "_id": id.to_string(), "user_id": user_id.to_string() };
+        let update = vec![doc! {
+            "$set": { "revoked_at": { "$ifNull": ["$revoked_at", to_bson_date(Utc::now())] } }
+        }];
+        match self
+            .collection
+            .find_one_and_update(filter, update)
+            .return_document(ReturnDocument::After)
+            .await
+        {
+            Ok(Some(document)) => Ok(document.into()),
+            Ok(None) => Err(share_link_repository::Error::NotFound),
+            Err(err) => Err(share_link_repository::Error::Unknown(err.to_string())),
+        }
+    }
+
+    async fn register_download(&self, id: Id) -> Result<ShareLink, share_link_repository::Error> {
+        // Checked and counted in one update so concurrent downloads can't go over the limit
+        let filter = doc! {
+            "_id": id.to_string(),
+            "revoked_at": null,
+            "expires_at": { "$gt": to_bson_date(Utc::now()) },
+            "$or": [
+                { "max_downloads": null },
+                { "$expr": { "$lt": ["$download_count", "$max_downloads"] } },
+            ],
+        };
+        let update = doc! { "$inc": { "download_count": 1 } };
+        match self
+            .collection
+            .find_one_and_update(filter, update)
+            .return_document(ReturnDocument::After)
+            .await
+        {
+            Ok(Some(document)) => return Ok(document.into()),
+            Ok(None) => (),
+            Err(err) => return Err(share_link_repository::Error::Unknown(err.to_string())),
+        }
+
+        let filter = doc! { "_id": id.to_string() };
+        match self.collection.find_one(filter).await {
+            Ok(Some(_)) => Err(share_link_repository::Error::Unavailable),
+            Ok(None) => Err(share_link_repository::Error::NotFound),
+            Err(err) => Err(share_link_repository::Error::Unknown(err.to_string())),
+        }
+    }
+
+    async fn release_download(&self, id: Id) -> Result<(), share_link_repository::Error> {
+        let filter = doc! { "_id": id.to_string(), "download_count": { "$gt": 0 } };
+        let update = doc! { "$inc": { "download_count": -1 } };
+        match self.collection.update_one(filter, update).await {
+            Ok(_) => Ok(()),
+            Err(err) => Err(share_link_repository::Error::Unknown(err.to_string())),
+        }
+    }
+}
+
+fn to_bson_date(date: DateTime<Utc>) -> bson::DateTime {
+    bson::DateTime::from_millis(date.timestamp_millis())
+}
+
+fn from_bson_date(date: bson::DateTime) -> DateTime<Utc> {
+    Utc.timestamp_millis_opt(date.timestamp_millis())
+        .single()
+        .unwrap_or_default()
+}
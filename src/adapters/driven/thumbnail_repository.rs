@@ -0,0 +1,133 @@
+use std::time::Duration;
+
+use bytes::Bytes;
+use chrono::{DateTime, TimeZone, Utc};
+use mongodb::{
+    bson::{self, doc, spec::BinarySubtype, Binary},
+    options::IndexOptions,
+    Client, Collection, IndexModel,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    application::ports::thumbnail_repository::{self, ThumbnailRepositoryTrait},
+    domain::entities::thumbnail::Thumbnail,
+};
+
+/// Cached thumbnails are dropped after this long. Those of files that changed are
+/// never asked for again, this is what cleans them up.
+const THUMBNAIL_TTL: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+/// Stored shape of a `Thumbnail`, with the image as BSON binary.
+#[derive(Serialize, Deserialize)]
+struct ThumbnailDocument {
+    file_id: String,
+    md5_checksum: String,
+    width: u32,
+    content_type: String,
+    content: Binary,
+    created_at: bson::DateTime,
+}
+
+impl From<&Thumbnail> for ThumbnailDocument {
+    fn from(thumbnail: &Thumbnail) -> Self {
+        Self {
+            file_id: thumbnail.file_id.clone(),
+            md5_checksum: thumbnail.md5_checksum.clone(),
+            width: thumbnail.width,
+            content_type: thumbnail.content_type.clone(),
+            content: Binary {
+                subtype: BinarySubtype::Generic,
+                bytes: thumbnail.content.to_vec(),
+            },
+            created_at: to_bson_date(thumbnail.created_at),
+        }
+    }
+}
+
+impl From<ThumbnailDocument> for Thumbnail {
+    fn from(document: ThumbnailDocument) -> Self {
+        Self {
+            file_id: document.file_id,
+            md5_checksum: document.md5_checksum,
+            width: document.width,
+            content_type: document.content_type,
+            content: Bytes::from(document.content.bytes),
+            created_at: from_bson_date(document.created_at),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct ThumbnailRepository {
+    collection: Collection<ThumbnailDocument>,
+}
+
+impl ThumbnailRepository {
+    pub async fn new(db_url: &str, db_name: &str, collection_name: &str) -> Self {
+        let client = Client::with_uri_str(db_url)
+            .await
+            .expect("Failed to initialize client");
+        let db = client.database(db_name);
+        let collection = db.collection(collection_name);
+
+        let indexes = [
+            IndexModel::builder()
+                .keys(doc! { "file_id": 1, "md5_checksum": 1, "width": 1 })
+                .options(IndexOptions::builder().unique(true).build())
+                .build(),
+            IndexModel::builder()
+                .keys(doc! { "created_at": 1 })
+                .options(IndexOptions::builder().expire_after(THUMBNAIL_TTL).build())
+                .build(),
+        ];
+        if let Err(err) = collection.create_indexes(indexes).await {
+            eprintln!("Error creating thumbnail indexes: {:?}", err);
+        }
+
+        Self { collection }
+    }
+}
+
+impl ThumbnailRepositoryTrait for ThumbnailRepository {
+    async fn find(
+        &self,
+        file_id: &str,
+        md5_checksum: &str,
+        width: u32,
+    ) -> Result<Thumbnail, thumbnail_repository::Error> {
+        let filter = doc! { "file_id": file_id, "md5_checksum": md5_checksum, "width": width };
+        match self.collection.find_one(filter).await {
+            Ok(Some(document)) => Ok(document.into()),
+            Ok(None) => Err(thumbnail_repository::Error::NotFound),
+            Err(err) => Err(thumbnail_repository::Error::Unknown(err.to_string())),
+        }
+    }
+
+    async fn save(&self, thumbnail: &Thumbnail) -> Result<(), thumbnail_repository::Error> {
+        let filter = doc! {
+            "file_id": &thumbnail.file_id,
+            "md5_checksum": &thumbnail.md5_checksum,
+            "width": thumbnail.width,
+        };
+        match self
+            .collection
+            .replace_one(filter, ThumbnailDocument::from(thumbnail))
+            .upsert(true)
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(err) => Err(thumbnail_repository::Error::Unknown(err.to_string())),
+        }
+    }
+}
+
+fn to_bson_date(date: DateTime<Utc>) -> bson::DateTime {
+    bson::DateTime::from_millis(date.timestamp_millis())
+}
+
+fn from_bson_date(date: bson::DateTime) -> DateTime<Utc> {
+    Utc.timestamp_millis_opt(date.timestamp_millis())
+        .single()
+        .unwrap_or_default()
+}
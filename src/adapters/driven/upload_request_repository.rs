@@ -0,0 +1,250 @@
+use std::time::Duration;
+
+use chrono::{DateTime, TimeZone, Utc};
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{self, doc},
+    options::{IndexOptions, ReturnDocument},
+    Client, Collection, IndexModel,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    application::ports::upload_request_repository::{self, UploadRequestRepositoryTrait},
+    domain::{
+        entities::upload_request::{UploadRecord, UploadRequest},
+        value_objects::id::Id,
+    },
+};
+
+/// Stored shape of an `UploadRequest`, the uploads are embedded in it.
+#[derive(Serialize, Deserialize)]
+struct UploadRequestDocument {
+    #[serde(rename = "_id")]
+    id: Id,
+    user_id: Id,
+    folder_id: String,
+    created_at: bson::DateTime,
+    expires_at: bson::DateTime,
+    max_files: Option<i64>,
+    file_count: i64,
+    allowed_types: Vec<String>,
+    max_size: Option<i64>,
+    uploads: Vec<UploadRecordDocument>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct UploadRecordDocument {
+    file_id: String,
+    file_name: String,
+    mime_type: String,
+    size: i64,
+    uploader_name: Option<String>,
+    uploader_email: Option<String>,
+    uploaded_at: bson::DateTime,
+}
+
+impl From<UploadRequest> for UploadRequestDocument {
+    fn from(upload_request: UploadRequest) -> Self {
+        Self {
+            id: upload_request.id,
+            user_id: upload_request.user_id,
+            folder_id: upload_request.folder_id,
+            created_at: to_bson_date(upload_request.created_at),
+            expires_at: to_bson_date(upload_request.expires_at),
+            max_files: upload_request.max_files.map(i64::from),
+            file_count: i64::from(upload_request.file_count),
+            allowed_types: upload_request.allowed_types,
+            max_size: upload_request
+                .max_size
+                .map(|x| x.try_into().unwrap_or(i64::MAX)),
+            uploads: upload_request
+                .uploads
+                .into_iter()
+                .map(UploadRecordDocument::from)
+                .collect(),
+        }
+    }
+}
+
+impl From<UploadRequestDocument> for UploadRequest {
+    fn from(document: UploadRequestDocument) -> Self {
+        Self {
+            id: document.id,
+            user_id: document.user_id,
+            folder_id: document.folder_id,
+            created_at: from_bson_date(document.created_at),
+            expires_at: from_bson_date(document.expires_at),
+            max_files: document.max_files.map(|x| x.try_into().unwrap_or(0)),
+            file_count: document.file_count.try_into().unwrap_or(u32::MAX),
+            allowed_types: document.allowed_types,
+            max_size: document.max_size.map(|x| x.try_into().unwrap_or(0)),
+            uploads: document
+                .uploads
+                .into_iter()
+                .map(UploadRecord::from)
+                .collect(),
+        }
+    }
+}
+
+impl From<UploadRecord> for UploadRecordDocument {
+    fn from(upload: UploadRecord) -> Self {
+        Self {
+            file_id: upload.file_id,
+            file_name: upload.file_name,
+            mime_type: upload.mime_type,
+            size: upload.size.try_into().unwrap_or(i64::MAX),
+            uploader_name: upload.uploader_name,
+            uploader_email: upload.uploader_email,
+            uploaded_at: to_bson_date(upload.uploaded_at),
+        }
+    }
+}
+
+impl From<UploadRecordDocument> for UploadRecord {
+    fn from(document: UploadRecordDocument) -> Self {
+        Self {
+            file_id: document.file_id,
+            file_name: document.file_name,
+            mime_type: document.mime_type,
+            size: document.size.try_into().unwrap_or(0),
+            uploader_name: document.uploader_name,
+            uploader_email: document.uploader_email,
+            uploaded_at: from_bson_date(document.uploaded_at),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct UploadRequestRepository {
+    collection: Collection<UploadRequestDocument>,
+}
+
+impl UploadRequestRepository {
+    pub async fn new(db_url: &str, db_name: &str, collection_name: &str) -> Self {
+        let client = Client::with_uri_str(db_url)
+            .await
+            .expect("Failed to initialize client");
+        let db = client.database(db_name);
+        let collection = db.collection(collection_name);
+
+        let indexes = [
+            IndexModel::builder()
+                .keys(doc! { "expires_at": 1 })
+                .options(
+                    IndexOptions::builder()
+                        .expire_after(Duration::from_secs(0))
+                        .build(),
+                )
+                .build(),
+            IndexModel::builder().keys(doc! { "user_id": 1 }).build(),
+        ];
+        if let Err(err) = collection.create_indexes(indexes).await {
+            eprintln!("Error creating upload request indexes: {:?}", err);
+        }
+
+        Self { collection }
+    }
+}
+
+impl UploadRequestRepositoryTrait for UploadRequestRepository {
+    async fn create(
+        &self,
+        upload_request: UploadRequest,
+    ) -> Result<UploadRequest, upload_request_repository::Error> {
+        let document = UploadRequestDocument::from(upload_request.clone());
+        match self.collection.insert_one(&document).await {
+            Ok(_) => Ok(upload_request),
+            Err(err) => Err(upload_request_repository::Error::Unknown(err.to_string())),
+        }
+    }
+
+    async fn find_by_user(
+        &self,
+        user_id: Id,
+    ) -> Result<Vec<UploadRequest>, upload_request_repository::Error> {
+        let filter = doc! { "user_id": user_id.to_string() };
+        let documents: Vec<UploadRequestDocument> = self
+            .collection
+            .find(filter)
+            .sort(doc! { "created_at": -1 })
+            .await
+            .map_err(|err| upload_request_repository::Error::Unknown(err.to_string()))?
+            .try_collect()
+            .await
+            .map_err(|err| upload_request_repository::Error::Unknown(err.to_string()))?;
+
+        Ok(documents.into_iter().map(UploadRequest::from).collect())
+    }
+
+    async fn reserve_upload(
+        &self,
+        id: Id,
+    ) -> Result<UploadRequest, upload_request_repository::Error> {
+        // Checked and counted in one update so concurrent uploads can't go over the limit
+        let filter = doc! {
+            "_id": id.to_string(),
+            "expires_at": { "$gt": to_bson_date(Utc::now()) },
+            "$or": [
+                { "max_files": null },
+                { "$expr": { "$lt": ["$file_count", "$max_files"] } },
+            ],
+        };
+        let update = doc! { "$inc": { "file_count": 1 } };
+        match self
+            .collection
+            .find_one_and_update(filter, update)
+            .return_document(ReturnDocument::After)
+            .await
+        {
+            Ok(Some(document)) => return Ok(document.into()),
+            Ok(None) => (),
+            Err(err) => return Err(upload_request_repository::Error::Unknown(err.to_string())),
+        }
+
+        let filter = doc! { "_id": id.to_string() };
+        match self.collection.find_one(filter).await {
+            Ok(Some(_)) => Err(upload_request_repository::Error::Unavailable),
+            Ok(None) => Err(upload_request_repository::Error::NotFound),
+            Err(err) => Err(upload_request_repository::Error::Unknown(err.to_string())),
+        }
+    }
+
+    async fn release_upload(&self, id: Id) -> Result<(), upload_request_repository::Error> {
+        let filter = doc! { "_id": id.to_string(), "file_count": { "$gt": 0 } };
+        let update = doc! { "$inc": { "file_count": -1 } };
+        match self.collection.update_one(filter, update).await {
+            Ok(_) => Ok(()),
+            Err(err) => Err(upload_request_repository::Error::Unknown(err.to_string())),
+        }
+    }
+
+    async fn record_upload(
+        &self,
+        id: Id,
+        upload: UploadRecord,
+    ) -> Result<(), upload_request_repository::Error> {
+        let upload = bson::to_bson(&UploadRecordDocument::from(upload))
+            .map_err(|err| upload_request_repository::Error::Unknown(err.to_string()))?;
+        let filter = doc! { "_id": id.to_string() };
+        let update = doc! { "$push": { "uploads": upload } };
+        match self.collection.update_one(filter, update).await {
+            Ok(result) if result.matched_count == 0 => {
+                Err(upload_request_repository::Error::NotFound)
+            }
+            Ok(_) => Ok(()),
+            Err(err) => Err(upload_request_repository::Error::Unknown(err.to_string())),
+        }
+    }
+}
+
+fn to_bson_date(date: DateTime<Utc>) -> bson::DateTime {
+    bson::DateTime::from_millis(date.timestamp_millis())
+}
+
+fn from_bson_date(date: bson::DateTime) -> DateTime<Utc> {
+    Utc.timestamp_millis_opt(date.timestamp_millis())
+        .single()
+        .unwrap_or_default()
+}
@@ -1,4 +1,8 @@
-use mongodb::{bson::doc, Client, Collection};
+use mongodb::{
+    bson::doc,
+    error::{Error as MongoError, ErrorKind},
+    Client, Collection,
+};
 
 use crate::{
     application::ports::user_repository::{self, UserRepositoryTrait},
@@ -11,7 +15,7 @@ pub struct UserRepository {
 }
 
 impl UserRepository {
-    pub async fn new(db_url: &String, db_name: &String, collection_name: &String) -> Self {
+    pub async fn new(db_url: &str, db_name: &str, collection_name: &str) -> Self {
         let client = Client::with_uri_str(db_url)
             .await
             .expect("Failed to initialize client");
@@ -28,7 +32,7 @@ impl UserRepositoryTrait for UserRepository {
         match self.collection.find_one(filter).await {
             Ok(Some(user)) => Ok(user),
             Ok(None) => Err(user_repository::Error::NotFound),
-            Err(err) => Err(user_repository::Error::Unknown(err.to_string())),
+            Err(err) => Err(map_error(err)),
         }
     }
 
@@ -37,14 +41,14 @@ impl UserRepositoryTrait for UserRepository {
         match self.collection.find_one(filter).await {
             Ok(Some(user)) => Ok(user),
             Ok(None) => Err(user_repository::Error::NotFound),
-            Err(err) => Err(user_repository::Error::Unknown(err.to_string())),
+            Err(err) => Err(map_error(err)),
         }
     }
 
     async fn create(&self, user: User) -> Result<User, user_repository::Error> {
         match self.collection.insert_one(&user).await {
             Ok(_) => Ok(user),
-            Err(err) => Err(user_repository::Error::Unknown(err.to_string())),
+            Err(err) => Err(map_error(err)),
         }
     }
 
@@ -52,8 +56,17 @@ impl UserRepositoryTrait for UserRepository {
         let filter = doc! { "_id": user.id.to_string() };
         match self.collection.replace_one(filter, &user).await {
             Ok(_) => Ok(user),
-            Err(err) => Err(user_repository::Error::Unknown(err.to_string())),
+            Err(err) => Err(map_error(err)),
+        }
+    }
+}
+
+fn map_error(err: MongoError) -> user_repository::Error {
+    match *err.kind {
+        ErrorKind::Io(_) | ErrorKind::ServerSelection { .. } => {
+            user_repository::Error::ConnectionError(err.to_string())
         }
+        _ => user_repository::Error::Unknown(err.to_string()),
     }
 }
 
@@ -63,6 +76,7 @@ mod tests {
     use crate::application::ports::user_repository::UserRepositoryTrait;
     use crate::domain::entities::user::User;
     use crate::domain::value_objects::email::Email;
+    use crate::domain::value_objects::google_token::GoogleToken;
     use mongodb::bson::doc;
 
     #[tokio::test]
@@ -70,10 +84,12 @@ mod tests {
         let config = crate::adapters::config::Config::new();
         let email_test = Email::new("name@some.com".to_string()).expect("Failed to create email");
 
-        let user_repository =
-            UserRepository::new(&config.db_url, &config.db_name, &"users".to_string()).await;
+        let user_repository = UserRepository::new(&config.db_url, &config.db_name, "users").await;
 
-        let user = User::new(email_test.clone(), "".to_string());
+        let user = User::new(
+            email_test.clone(),
+            GoogleToken::new("".to_string(), None, None),
+        );
 
         let result = user_repository.create(user.clone()).await;
         assert!(result.is_ok());
@@ -85,7 +101,7 @@ mod tests {
             .await
             .expect("Failed to initialize client");
         let db = client.database(&config.db_name);
-        let collection = db.collection::<User>(&"users".to_string());
+        let collection = db.collection::<User>("users");
 
         let filter = doc! { "_id": user.id.to_string() };
         let result = collection.find_one(filter.clone()).await;
@@ -0,0 +1,116 @@
+use chrono::{DateTime, TimeZone, Utc};
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{self, doc},
+    Client, Collection, IndexModel,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    application::ports::webhook_repository::{self, WebhookRepositoryTrait},
+    domain::{entities::webhook::Webhook, value_objects::id::Id},
+};
+
+/// Stored shape of a `Webhook`, the secret included.
+#[derive(Serialize, Deserialize)]
+struct WebhookDocument {
+    #[serde(rename = "_id")]
+    id: Id,
+    user_id: Id,
+    url: String,
+    folder_ids: Vec<String>,
+    secret: String,
+    created_at: bson::DateTime,
+}
+
+impl From<Webhook> for WebhookDocument {
+    fn from(webhook: Webhook) -> Self {
+        Self {
+            id: webhook.id,
+            user_id: webhook.user_id,
+            url: webhook.url,
+            folder_ids: webhook.folder_ids,
+            secret: webhook.secret,
+            created_at: to_bson_date(webhook.created_at),
+        }
+    }
+}
+
+impl From<WebhookDocument> for Webhook {
+    fn from(document: WebhookDocument) -> Self {
+        Self {
+            id: document.id,
+            user_id: document.user_id,
+            url: document.url,
+            folder_ids: document.folder_ids,
+            secret: document.secret,
+            created_at: from_bson_date(document.created_at),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct WebhookRepository {
+    collection: Collection<WebhookDocument>,
+}
+
+impl WebhookRepository {
+    pub async fn new(db_url: &str, db_name: &str, collection_name: &str) -> Self {
+        let client = Client::with_uri_str(db_url)
+            .await
+            .expect("Failed to initialize client");
+        let db = client.database(db_name);
+        let collection = db.collection(collection_name);
+
+        let index = IndexModel::builder().keys(doc! { "user_id": 1 }).build();
+        if let Err(err) = collection.create_index(index).await {
+            eprintln!("Error creating webhook indexes: {:?}", err);
+        }
+
+        Self { collection }
+    }
+}
+
+impl WebhookRepositoryTrait for WebhookRepository {
+    async fn create(&self, webhook: Webhook) -> Result<Webhook, webhook_repository::Error> {
+        let document = WebhookDocument::from(webhook.clone());
+        match self.collection.insert_one(&document).await {
+            Ok(_) => Ok(webhook),
+            Err(err) => Err(webhook_repository::Error::Unknown(err.to_string())),
+        }
+    }
+
+    async fn find_by_user(&self, user_id: Id) -> Result<Vec<Webhook>, webhook_repository::Error> {
+        let filter = doc! { "user_id": user_id.to_string() };
+        let documents: Vec<WebhookDocument> = self
+            .collection
+            .find(filter)
+            .sort(doc! { "created_at": -1 })
+            .await
+            .map_err(|err| webhook_repository::Error::Unknown(err.to_string()))?
+            .try_collect()
+            .await
+            .map_err(|err| webhook_repository::Error::Unknown(err.to_string()))?;
+
+        Ok(documents.into_iter().map(Webhook::from).collect())
+    }
+
+    async fn delete(&self, id: Id, user_id: Id) -> Result<Webhook, webhook_repository::Error> {
+        let filter = doc! { "_id": id.to_string(), "user_id": user_id.to_string() };
+        match self.collection.find_one_and_delete(filter).await {
+            Ok(Some(document)) => Ok(document.into()),
+            Ok(None) => Err(webhook_repository::Error::NotFound),
+            Err(err) => Err(webhook_repository::Error::Unknown(err.to_string())),
+        }
+    }
+}
+
+fn to_bson_date(date: DateTime<Utc>) -> bson::DateTime {
+    bson::DateTime::from_millis(date.timestamp_millis())
+}
+
+fn from_bson_date(date: bson::DateTime) -> DateTime<Utc> {
+    Utc.timestamp_millis_opt(date.timestamp_millis())
+        .single()
+        .unwrap_or_default()
+}
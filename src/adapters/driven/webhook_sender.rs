@@ -0,0 +1,259 @@
+use std::{
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    sync::Arc,
+    time::Duration,
+};
+
+use reqwest::{
+    dns::{Addrs, Name, Resolve, Resolving},
+    header, redirect, Client,
+};
+use url::{Host, Url};
+
+use crate::application::ports::webhook_sender::{self, Delivery, WebhookSenderTrait};
+
+/// Longest a receiver gets to answer a single attempt.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Clone)]
+pub struct WebhookSender {
+    client: Client,
+    max_attempts: u32,
+    retry_delay: Duration,
+}
+
+impl WebhookSender {
+    /// `retry_delay` is the wait after the first failed attempt, doubled after each one.
+    pub fn new(max_attempts: u32, retry_delay: Duration) -> Self {
+        Self {
+            // A redirect or a DNS answer could otherwise lead to an address the URL check
+            // refused
+            client: Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .redirect(redirect::Policy::none())
+                .dns_resolver(Arc::new(PublicResolver))
+                .build()
+                .expect("Failed to initialize client"),
+            max_attempts: max_attempts.max(1),
+            retry_delay,
+        }
+    }
+}
+
+impl WebhookSenderTrait for WebhookSender {
+    async fn check_url(&self, url: &str) -> Result<(), webhook_sender::Error> {
+        let url = parse_url(url)?;
+        let domain = match url.host() {
+            Some(Host::Domain(domain)) => domain,
+            // IP addresses were checked along with the URL
+            _ => return Ok(()),
+        };
+
+        let port = url.port_or_known_default().unwrap_or(443);
+        let addrs: Vec<SocketAddr> = tokio::net::lookup_host((domain, port))
+            .await
+            .map_err(|err| {
+                webhook_sender::Error::ForbiddenUrl(format!("cannot resolve {}: {}", domain, err))
+            })?
+            .collect();
+        check_addrs(domain, &addrs).map_err(webhook_sender::Error::ForbiddenUrl)
+    }
+
+    async fn send(&self, delivery: &Delivery) -> Result<(), webhook_sender::Error> {
+        parse_url(&delivery.url)?;
+
+        let mut attempt = 1;
+        loop {
+            let result = self
+                .client
+                .post(&delivery.url)
+                .header(header::CONTENT_TYPE, "application/json")
+                .header("X-Webhook-Timestamp", delivery.timestamp)
+                .header(
+                    "X-Webhook-Signature",
+                    format!("sha256={}", delivery.signature),
+                )
+                .body(delivery.body.clone())
+                .send()
+                .await;
+
+            let err = match result {
+                Ok(response) if response.status().is_success() => return Ok(()),
+                // Other client errors won't go away by sending the same request again
+                Ok(response)
+                    if response.status().is_client_error()
+                        && response.status().as_u16() != 408
+                        && response.status().as_u16() != 429 =>
+                {
+                    return Err(webhook_sender::Error::Rejected(response.status().as_u16()))
+                }
+                Ok(response) => webhook_sender::Error::Rejected(response.status().as_u16()),
+                Err(err) => webhook_sender::Error::Unreachable(err.to_string()),
+            };
+
+            if attempt >= self.max_attempts {
+                return Err(err);
+            }
+            eprintln!(
+                "Webhook delivery to {} failed, retrying ({}): {}",
+                delivery.url, attempt, err
+            );
+            tokio::time::sleep(
+                self.retry_delay
+                    .saturating_mul(2u32.saturating_pow(attempt - 1)),
+            )
+            .await;
+            attempt += 1;
+        }
+    }
+}
+
+/// Resolves hosts like the system does, failing for those with a non-public address.
+struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let host = name.as_str().to_string();
+        Box::pin(async move {
+            let addrs: Vec<SocketAddr> =
+                tokio::net::lookup_host((host.as_str(), 0)).await?.collect();
+            check_addrs(&host, &addrs)?;
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// Parses a webhook URL, which has to be HTTPS. A host given as an IP address has to be
+/// public; names are checked once resolved.
+fn parse_url(url: &str) -> Result<Url, webhook_sender::Error> {
+    let url =
+        Url::parse(url).map_err(|err| webhook_sender::Error::ForbiddenUrl(err.to_string()))?;
+    if url.scheme() != "https" {
+        return Err(webhook_sender::Error::ForbiddenUrl(
+            "the URL must start with https://".to_string(),
+        ));
+    }
+
+    let ip = match url.host() {
+        Some(Host::Ipv4(ip)) => IpAddr::V4(ip),
+        Some(Host::Ipv6(ip)) => IpAddr::V6(ip),
+        Some(Host::Domain(_)) => return Ok(url),
+        None => {
+            return Err(webhook_sender::Error::ForbiddenUrl(
+                "the URL has no host".to_string(),
+            ))
+        }
+    };
+    if !is_public(ip) {
+        return Err(webhook_sender::Error::ForbiddenUrl(format!(
+            "{} is not a public address",
+            ip
+        )));
+    }
+    Ok(url)
+}
+
+fn check_addrs(host: &str, addrs: &[SocketAddr]) -> Result<(), String> {
+    if addrs.is_empty() {
+        return Err(format!("{} has no address", host));
+    }
+    match addrs.iter().find(|x| !is_public(x.ip())) {
+        Some(addr) => Err(format!(
+            "{} resolves to {}, which is not public",
+            host,
+            addr.ip()
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Whether the address can be reached from the public internet: loopback, private,
+/// link-local, shared, unspecified, broadcast and multicast addresses are not.
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_v4(ip),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public_v4(ip),
+            None => is_public_v6(ip),
+        },
+    }
+}
+
+fn is_public_v4(ip: Ipv4Addr) -> bool {
+    let [a, b, ..] = ip.octets();
+    // 100.64.0.0/10 is used for carrier-grade NAT, 0.0.0.0/8 means "this network"
+    let shared = a == 100 && (b & 0xc0) == 64;
+    !(ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_unspecified()
+        || ip.is_broadcast()
+        || ip.is_multicast()
+        || ip.is_documentation()
+        || shared
+        || a == 0)
+}
+
+fn is_public_v6(ip: Ipv6Addr) -> bool {
+    let first = ip.segments()[0];
+    // fc00::/7 are unique local addresses, fe80::/10 link-local ones
+    let unique_local = (first & 0xfe00) == 0xfc00;
+    let link_local = (first & 0xffc0) == 0xfe80;
+    !(ip.is_loopback() || ip.is_unspecified() || ip.is_multicast() || unique_local || link_local)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_public() {
+        for ip in ["8.8.8.8", "142.250.80.46", "2001:4860:4860::8888"] {
+            assert!(is_public(ip.parse().unwrap()), "{}", ip);
+        }
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "255.255.255.255",
+            "::1",
+            "::",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+            "::ffff:169.254.169.254",
+        ] {
+            assert!(!is_public(ip.parse().unwrap()), "{}", ip);
+        }
+    }
+
+    #[test]
+    fn test_parse_url() {
+        assert!(parse_url("https://example.com/hook").is_ok());
+        assert!(parse_url("https://8.8.8.8/hook").is_ok());
+        assert!(parse_url("http://example.com/hook").is_err());
+        assert!(parse_url("ftp://example.com/hook").is_err());
+        assert!(parse_url("https://127.0.0.1/hook").is_err());
+        assert!(parse_url("https://169.254.169.254/latest/meta-data").is_err());
+        assert!(parse_url("https://[::1]:8443/hook").is_err());
+        assert!(parse_url("https://0x7f000001/hook").is_err());
+        assert!(parse_url("not a url").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_check_url() {
+        let sender = WebhookSender::new(1, Duration::ZERO);
+        assert!(matches!(
+            sender.check_url("https://localhost/hook").await,
+            Err(webhook_sender::Error::ForbiddenUrl(_))
+        ));
+        assert!(matches!(
+            sender.check_url("https://10.0.0.1/hook").await,
+            Err(webhook_sender::Error::ForbiddenUrl(_))
+        ));
+    }
+}
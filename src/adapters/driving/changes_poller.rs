@@ -0,0 +1,100 @@
+use std::time::Duration;
+
+use futures::{stream, StreamExt};
+use tokio::time::MissedTickBehavior;
+
+use crate::{
+    adapters::driving::http::state::AppState,
+    application::{ports::change_cursor_repository::ChangeCursorRepositoryTrait, usecases},
+    domain::value_objects::id::Id,
+};
+
+/// Longest Drive keeps a push notification channel open.
+const CHANNEL_TTL: chrono::Duration = chrono::Duration::days(7);
+/// Channels are replaced this long before they expire, so there is always one open.
+const CHANNEL_RENEW_BEFORE: chrono::Duration = chrono::Duration::hours(6);
+
+/// Reads the change log of every user with webhooks on a schedule, for as long as the
+/// server runs. Push notifications only make syncs happen sooner, polling still catches
+/// what Drive couldn't deliver.
+pub async fn run(state: AppState) {
+    let mut interval = tokio::time::interval(Duration::from_secs(
+        state.config.changes_poll_interval_secs.max(1),
+    ));
+    interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    loop {
+        interval.tick().await;
+        poll(&state).await;
+    }
+}
+
+async fn poll(state: &AppState) {
+    let cursors = match state.change_cursor_repository.find_all().await {
+        Ok(cursors) => cursors,
+        Err(err) => {
+            eprintln!("Error reading change cursors: {}", err);
+            return;
+        }
+    };
+
+    let syncs: Vec<_> = cursors
+        .iter()
+        .map(|cursor| watch_and_sync(state, cursor.user_id))
+        .collect();
+    stream::iter(syncs)
+        .buffer_unordered(state.config.changes_concurrency.max(1))
+        .collect::<Vec<_>>()
+        .await;
+}
+
+async fn watch_and_sync(state: &AppState, user_id: Id) {
+    if let Some(address) = &state.config.drive_notifications_url {
+        let payload = usecases::watch_changes::Payload {
+            user_id,
+            address: address.clone(),
+            ttl: CHANNEL_TTL,
+            renew_before: CHANNEL_RENEW_BEFORE,
+        };
+        if let Err(err) = usecases::watch_changes::execute(
+            &state.user_repository,
+            &state.change_cursor_repository,
+            &state.google_drive_service,
+            payload,
+        )
+        .await
+        {
+            eprintln!("Error watching changes of user {}: {}", user_id, err);
+        }
+    }
+
+    sync(state, user_id).await;
+}
+
+/// Sends the user's new changes to their webhooks, logging what went wrong.
+pub async fn sync(state: &AppState, user_id: Id) {
+    let payload = usecases::sync_changes::Payload {
+        user_id,
+        concurrency: state.config.changes_concurrency,
+    };
+    match usecases::sync_changes::execute(
+        &state.user_repository,
+        &state.webhook_repository,
+        &state.change_cursor_repository,
+        &state.file_parents_repository,
+        &state.google_drive_service,
+        &state.webhook_sender,
+        payload,
+    )
+    .await
+    {
+        Ok(summary) if summary.failed > 0 => eprintln!(
+            "{} of {} webhook calls failed for user {}, {} changes were not sent to them",
+            summary.failed,
+            summary.failed + summary.delivered,
+            user_id,
+            summary.changes
+        ),
+        Ok(_) => (),
+        Err(err) => eprintln!("Error syncing changes of user {}: {}", user_id, err),
+    }
+}
@@ -1,32 +1,79 @@
+use std::sync::{Arc, Mutex};
+
 use axum::{
     body::Body,
     extract::{Query, State},
-    http::{header, StatusCode},
+    http::{header, response, HeaderMap, HeaderName, StatusCode},
     response::{IntoResponse, Redirect, Response},
-    Extension,
+    Extension, Json,
 };
 use axum_extra::extract::Multipart;
-use serde::Deserialize;
-use tokio::{fs::File, io::AsyncWriteExt};
-use tokio_util::io::ReaderStream;
+use bytes::{Bytes, BytesMut};
+use chrono::{DateTime, Duration, Utc};
+use futures::{channel::mpsc, future, stream, SinkExt, StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use super::{state::AppState, utils::responses::JsonResponse};
+use super::{
+    state::AppState,
+    utils::{
+        headers::{
+            content_disposition, entity_tag, http_date, parse_http_date, parse_if_none_match,
+            parse_range,
+        },
+        responses::JsonResponse,
+        zip::{Crc32, ZipWriter},
+    },
+};
 use crate::{
-    application::usecases,
-    domain::value_objects::{file_info::FileInfo, id::Id},
+    adapters::driving::changes_poller,
+    application::{
+        ports::google_drive_service,
+        usecases::{self, download_archive::Archive, download_file::Download, pdf_content::PDF},
+    },
+    domain::{
+        entities::{
+            share_link::ShareLink,
+            upload_request::{UploadRecord, UploadRequest},
+            webhook::Webhook,
+        },
+        value_objects::{
+            conflict_policy::ConflictPolicy,
+            download_conditions::DownloadConditions,
+            drive_about::DriveAbout,
+            email::Email,
+            file_info::{FileField, FileInfo},
+            file_query::{FileQuery, SortKey},
+            file_type::FileTypePolicy,
+            files_page::FilesPage,
+            folder_tree::FolderTree,
+            id::Id,
+            page_range::PageRanges,
+            pdf_document::PdfDocument,
+            permission::{
+                Permission, PermissionGrant, PermissionRole, PermissionType, PermissionUpdate,
+            },
+            revision::Revision,
+            search_query::SearchQuery,
+            shared_drive::SharedDrive,
+        },
+    },
 };
 
 pub async fn handler_get_google_auth_url(
     State(state): State<AppState>,
 ) -> Result<Redirect, JsonResponse<String>> {
-    match usecases::get_google_auth_url::execute(&state.google_drive_service).await {
-        Ok(url) => Ok(Redirect::permanent(&url)),
-        Err(_) => {
-            return Err(JsonResponse::new_int_ser_err(
-                "Internal Server Error".to_string(),
-            ));
-        }
+    match usecases::get_google_auth_url::execute(
+        &state.auth_request_repository,
+        &state.google_drive_service,
+        Duration::seconds(state.config.oauth_state_ttl_secs),
+        false,
+    )
+    .await
+    {
+        // Every login gets its own state, so the redirect must not be cached
+        Ok(url) => Ok(Redirect::temporary(&url)),
+        Err(err) => Err(JsonResponse::new_int_ser_err(err.to_string())),
     }
 }
 
@@ -34,51 +81,148 @@ pub async fn handler_get_google_auth_url(
 pub struct GoogleCallbackQuery {
     state: String,
     code: String,
-    scope: String,
-    authuser: String,
-    prompt: String,
 }
 
 pub async fn handler_handle_google_callback(
     State(state): State<AppState>,
     Query(params): Query<GoogleCallbackQuery>,
-) -> JsonResponse<String> {
+) -> Response {
     let payload = usecases::handle_google_callback::Payload {
-        code: params.code.to_string(),
+        code: params.code,
+        state: params.state,
     };
 
     match usecases::handle_google_callback::execute(
         &state.user_repository,
+        &state.auth_request_repository,
         &state.google_drive_service,
         &state.config.secret,
         payload,
     )
     .await
     {
-        Ok(auth_token) => JsonResponse::<String>::new_ok(auth_token),
-        Err(_) => {
-            return JsonResponse::new_int_ser_err("Internal Server Error".to_string());
+        Ok(auth_token) => JsonResponse::<String>::new_ok(auth_token).into_response(),
+        Err(err @ usecases::handle_google_callback::Error::InvalidState(_)) => {
+            JsonResponse::<String>::new_bad_req_err(err.to_string()).into_response()
+        }
+        // Send the user back through the consent screen so Google issues a refresh token
+        Err(usecases::handle_google_callback::Error::ConsentRequired) => {
+            match usecases::get_google_auth_url::execute(
+                &state.auth_request_repository,
+                &state.google_drive_service,
+                Duration::seconds(state.config.oauth_state_ttl_secs),
+                true,
+            )
+            .await
+            {
+                Ok(url) => Redirect::temporary(&url).into_response(),
+                Err(err) => {
+                    JsonResponse::<String>::new_int_ser_err(err.to_string()).into_response()
+                }
+            }
+        }
+        Err(_) => JsonResponse::<String>::new_int_ser_err("Internal Server Error".to_string())
+            .into_response(),
+    }
+}
+
+/// The user as stored here, merged with their Drive account. Tokens are left out.
+#[derive(Serialize)]
+pub struct AccountResponse {
+    id: Id,
+    email: Email,
+    created_at: DateTime<Utc>,
+    #[serde(flatten)]
+    about: DriveAbout,
+}
+
+pub async fn handler_get_me(
+    Extension(user_id): Extension<Uuid>,
+    State(state): State<AppState>,
+) -> JsonResponse<AccountResponse> {
+    let user_id = if let Ok(user_id) = Id::try_from(user_id) {
+        user_id
+    } else {
+        return JsonResponse::new_int_ser_err("Internal Server Error".to_string());
+    };
+    let payload = usecases::get_account::Payload { user_id };
+    match usecases::get_account::execute(
+        &state.user_repository,
+        &state.google_drive_service,
+        payload,
+    )
+    .await
+    {
+        Ok(account) => JsonResponse::new_ok(AccountResponse {
+            id: account.user.id,
+            email: account.user.email,
+            created_at: account.user.created_at,
+            about: account.about,
+        }),
+        Err(err @ usecases::get_account::Error::NotFound(_)) => {
+            JsonResponse::new_not_found_err(err.to_string())
+        }
+        Err(err @ usecases::get_account::Error::GoogleUnauthenticated) => {
+            JsonResponse::new_unauthorized_err(err.to_string())
         }
+        Err(err) => JsonResponse::new_int_ser_err(err.to_string()),
     }
 }
 
 #[derive(Deserialize)]
 pub struct ListFilesQuery {
-    folder_id: String,
+    /// Defaults to the root of the shared drive when there is a `drive_id`
+    folder_id: Option<String>,
+    drive_id: Option<String>,
+    page_size: Option<u32>,
+    page_token: Option<String>,
+    /// Comma separated, e.g. `folder,modified_at desc`
+    order_by: Option<String>,
+    mime_type: Option<String>,
+    name_contains: Option<String>,
+    created_after: Option<DateTime<Utc>>,
+    created_before: Option<DateTime<Utc>>,
+    modified_after: Option<DateTime<Utc>>,
+    modified_before: Option<DateTime<Utc>>,
+    fields: Option<String>,
 }
 
 pub async fn handler_get_list_files(
     Extension(user_id): Extension<Uuid>,
     State(state): State<AppState>,
     Query(params): Query<ListFilesQuery>,
-) -> JsonResponse<Vec<FileInfo>> {
+) -> JsonResponse<FilesPage> {
     let user_id = if let Ok(user_id) = Id::try_from(user_id) {
         user_id
     } else {
         return JsonResponse::new_int_ser_err("Internal Server Error".to_string());
     };
+    let order_by = match params.order_by.as_deref().map(SortKey::parse_list) {
+        Some(Ok(order_by)) => order_by,
+        Some(Err(err)) => return JsonResponse::new_bad_req_err(err),
+        None => Vec::new(),
+    };
+    let fields = match params.fields.as_deref().map(FileField::parse_list) {
+        Some(Ok(fields)) => fields,
+        Some(Err(err)) => return JsonResponse::new_bad_req_err(err),
+        None => Vec::new(),
+    };
     let payload = usecases::list_files::Payload {
-        path: params.folder_id.to_string(),
+        folder_id: params.folder_id,
+        query: FileQuery {
+            drive_id: params.drive_id,
+            mime_type: params.mime_type,
+            name_contains: params.name_contains,
+            created_after: params.created_after,
+            created_before: params.created_before,
+            modified_after: params.modified_after,
+            modified_before: params.modified_before,
+            order_by,
+            page_size: params.page_size,
+            page_token: params.page_token,
+            fields,
+            ..Default::default()
+        },
         user_id,
     };
     match usecases::list_files::execute(
@@ -88,22 +232,130 @@ pub async fn handler_get_list_files(
     )
     .await
     {
-        Ok(file_ids) => JsonResponse::<Vec<FileInfo>>::new_ok(file_ids),
-        Err(err) => {
-            return JsonResponse::new_int_ser_err(err.to_string());
+        Ok(files_page) => JsonResponse::<FilesPage>::new_ok(files_page),
+        Err(err @ usecases::list_files::Error::InvalidRequest(_)) => {
+            JsonResponse::new_bad_req_err(err.to_string())
+        }
+        Err(err @ usecases::list_files::Error::GoogleUnauthenticated) => {
+            JsonResponse::new_unauthorized_err(err.to_string())
+        }
+        Err(err) => JsonResponse::new_int_ser_err(err.to_string()),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct SearchFilesQuery {
+    /// Matched against the name, description and content of the files
+    q: Option<String>,
+    name_contains: Option<String>,
+    mime_type: Option<String>,
+    owner: Option<String>,
+    starred: Option<bool>,
+    #[serde(default)]
+    trashed: bool,
+    modified_after: Option<DateTime<Utc>>,
+    modified_before: Option<DateTime<Utc>>,
+    folder_id: Option<String>,
+    drive_id: Option<String>,
+    page_size: Option<u32>,
+    page_token: Option<String>,
+    fields: Option<String>,
+}
+
+pub async fn handler_search_files(
+    Extension(user_id): Extension<Uuid>,
+    State(state): State<AppState>,
+    Query(params): Query<SearchFilesQuery>,
+) -> JsonResponse<FilesPage> {
+    let user_id = if let Ok(user_id) = Id::try_from(user_id) {
+        user_id
+    } else {
+        return JsonResponse::new_int_ser_err("Internal Server Error".to_string());
+    };
+    let fields = match params.fields.as_deref().map(FileField::parse_list) {
+        Some(Ok(fields)) => fields,
+        Some(Err(err)) => return JsonResponse::new_bad_req_err(err),
+        None => Vec::new(),
+    };
+    let payload = usecases::search_files::Payload {
+        query: SearchQuery {
+            full_text: params.q,
+            name_contains: params.name_contains,
+            mime_type: params.mime_type,
+            owner: params.owner,
+            starred: params.starred,
+            trashed: params.trashed,
+            modified_after: params.modified_after,
+            modified_before: params.modified_before,
+            folder_ids: Vec::new(),
+            drive_id: params.drive_id,
+            page_size: params.page_size,
+            page_token: params.page_token,
+            fields,
+        },
+        folder_id: params.folder_id,
+        user_id,
+        max_folders: state.config.search_max_folders,
+        concurrency: state.config.folder_tree_concurrency,
+    };
+    match usecases::search_files::execute(
+        &state.user_repository,
+        &state.google_drive_service,
+        payload,
+    )
+    .await
+    {
+        Ok(files_page) => JsonResponse::<FilesPage>::new_ok(files_page),
+        Err(err @ usecases::search_files::Error::InvalidQuery(_)) => {
+            JsonResponse::new_bad_req_err(err.to_string())
         }
+        Err(err @ usecases::search_files::Error::NotAFolder(_)) => {
+            JsonResponse::new_bad_req_err(err.to_string())
+        }
+        Err(err @ usecases::search_files::Error::NotFound(_)) => {
+            JsonResponse::new_not_found_err(err.to_string())
+        }
+        Err(err @ usecases::search_files::Error::GoogleUnauthenticated) => {
+            JsonResponse::new_unauthorized_err(err.to_string())
+        }
+        Err(err) => JsonResponse::new_int_ser_err(err.to_string()),
     }
 }
 
 #[derive(Deserialize)]
-pub struct DownloadPDFQuery {
+pub struct DownloadFileQuery {
     file_id: String,
+    drive_id: Option<String>,
+    format: Option<String>,
+}
+
+pub async fn handler_download_file(
+    Extension(user_id): Extension<Uuid>,
+    State(state): State<AppState>,
+    Query(params): Query<DownloadFileQuery>,
+    headers: HeaderMap,
+) -> Result<Response, (StatusCode, String)> {
+    let allowed_types = state.config.allowed_file_types.clone();
+    download_file(user_id, state, params, headers, allowed_types).await
 }
 
+/// Same as `handler_download_file`, limited to PDF files.
 pub async fn handler_download_pdf(
     Extension(user_id): Extension<Uuid>,
     State(state): State<AppState>,
-    Query(params): Query<DownloadPDFQuery>,
+    Query(params): Query<DownloadFileQuery>,
+    headers: HeaderMap,
+) -> Result<Response, (StatusCode, String)> {
+    let allowed_types = state.config.allowed_file_types.only(PDF);
+    download_file(user_id, state, params, headers, allowed_types).await
+}
+
+async fn download_file(
+    user_id: Uuid,
+    state: AppState,
+    params: DownloadFileQuery,
+    headers: HeaderMap,
+    allowed_types: FileTypePolicy,
 ) -> Result<Response, (StatusCode, String)> {
     let user_id = if let Ok(user_id) = Id::try_from(user_id) {
         user_id
@@ -113,51 +365,146 @@ pub async fn handler_download_pdf(
             "Internal Server Error".to_string(),
         ));
     };
-    let payload = usecases::download_pdf::Payload {
+
+    let header_str = |name: HeaderName| headers.get(name).and_then(|x| x.to_str().ok());
+    let payload = usecases::download_file::Payload {
         file_id: params.file_id,
+        drive_id: params.drive_id,
         user_id,
+        ranges: header_str(header::RANGE).and_then(parse_range),
+        conditions: DownloadConditions {
+            if_none_match: header_str(header::IF_NONE_MATCH).map(parse_if_none_match),
+            if_modified_since: header_str(header::IF_MODIFIED_SINCE).and_then(parse_http_date),
+        },
+        allowed_types,
+        format: params.format,
     };
-    let file_path = match usecases::download_pdf::execute(
+    match usecases::download_file::execute(
         &state.user_repository,
         &state.google_drive_service,
         payload,
     )
     .await
     {
-        Ok(file_path) => file_path,
-        Err(err) => {
-            return Err((StatusCode::INTERNAL_SERVER_ERROR, err.to_string()));
+        Ok(download) => download_response(download),
+        Err(err) => Err(download_error(err)),
+    }
+}
+
+fn download_error(err: usecases::download_file::Error) -> (StatusCode, String) {
+    match err {
+        err @ usecases::download_file::Error::NotFound(_) => {
+            (StatusCode::NOT_FOUND, err.to_string())
+        }
+        err @ usecases::download_file::Error::UnsupportedFileType(_) => {
+            (StatusCode::UNSUPPORTED_MEDIA_TYPE, err.to_string())
+        }
+        err @ usecases::download_file::Error::GoogleUnauthenticated => {
+            (StatusCode::UNAUTHORIZED, err.to_string())
+        }
+        err => (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+    }
+}
+
+fn download_response(download: Download) -> Result<Response, (StatusCode, String)> {
+    let response = match download {
+        Download::NotModified(file) => validator_headers(Response::builder(), &file)
+            .status(StatusCode::NOT_MODIFIED)
+            .body(Body::empty()),
+        Download::RangeNotSatisfiable(file) => validator_headers(Response::builder(), &file)
+            .status(StatusCode::RANGE_NOT_SATISFIABLE)
+            .header(
+                header::CONTENT_RANGE,
+                format!("bytes */{}", file.size.unwrap_or_default()),
+            )
+            .body(Body::empty()),
+        Download::Full(download) => {
+            let mut response = validator_headers(Response::builder(), &download.file)
+                .header(header::CONTENT_TYPE, download.file.file_type.as_str())
+                .header(
+                    header::CONTENT_DISPOSITION,
+                    content_disposition("inline", &download.file.name),
+                );
+            if let Some(size) = download.file.size {
+                response = response.header(header::CONTENT_LENGTH, size);
+            }
+            response.body(Body::from_stream(download.content))
+        }
+        Download::Partial(file, mut parts) if parts.len() == 1 => {
+            let (range, content) = parts.remove(0);
+            validator_headers(Response::builder(), &file)
+                .status(StatusCode::PARTIAL_CONTENT)
+                .header(header::CONTENT_TYPE, file.file_type.as_str())
+                .header(
+                    header::CONTENT_DISPOSITION,
+                    content_disposition("inline", &file.name),
+                )
+                .header(
+                    header::CONTENT_RANGE,
+                    format!(
+                        "bytes {}-{}/{}",
+                        range.start,
+                        range.end,
+                        file.size.unwrap_or_default()
+                    ),
+                )
+                .header(header::CONTENT_LENGTH, range.len())
+                .body(Body::from_stream(content))
+        }
+        Download::Partial(file, parts) => {
+            let boundary = Uuid::new_v4().simple().to_string();
+            let size = file.size.unwrap_or_default();
+
+            // Each part is its headers followed by the range content, see RFC 9110 14.6
+            let mut content_length = 0;
+            let mut body_parts = Vec::with_capacity(parts.len() + 1);
+            for (range, content) in parts {
+                let part_headers = format!(
+                    "\r\n--{}\r\nContent-Type: {}\r\nContent-Range: bytes {}-{}/{}\r\n\r\n",
+                    boundary, file.file_type, range.start, range.end, size
+                );
+                content_length += part_headers.len() as u64 + range.len();
+                body_parts.push(
+                    stream::once(future::ready(Ok(Bytes::from(part_headers))))
+                        .chain(content)
+                        .boxed(),
+                );
+            }
+            let closing = format!("\r\n--{}--\r\n", boundary);
+            content_length += closing.len() as u64;
+            body_parts.push(stream::once(future::ready(Ok(Bytes::from(closing)))).boxed());
+
+            validator_headers(Response::builder(), &file)
+                .status(StatusCode::PARTIAL_CONTENT)
+                .header(
+                    header::CONTENT_TYPE,
+                    format!("multipart/byteranges; boundary={}", boundary),
+                )
+                .header(header::CONTENT_LENGTH, content_length)
+                .body(Body::from_stream(stream::iter(body_parts).flatten()))
         }
     };
 
-    let file = File::open(&file_path).await.map_err(|err| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            format!("Error opening PDF file: {}", err),
-        )
-    })?;
+    response.map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))
+}
 
-    let stream = ReaderStream::new(file);
-    let body = Body::from_stream(stream);
+/// Chunks of a ZIP archive waiting to be sent to the client.
+const ARCHIVE_BUFFER: usize = 8;
 
-    Ok((
-        [
-            (header::CONTENT_TYPE, "application/pdf"),
-            (
-                header::CONTENT_DISPOSITION,
-                "inline; filename=\"example.pdf\"",
-            ),
-        ],
-        body,
-    )
-        .into_response())
+#[derive(Deserialize)]
+pub struct DownloadArchiveQuery {
+    folder_id: Option<String>,
+    /// The whole shared drive is archived when there is no folder nor files
+    drive_id: Option<String>,
+    /// Comma separated ids, used when there is no folder
+    file_ids: Option<String>,
 }
 
-pub async fn handler_upload_pdf(
+pub async fn handler_download_archive(
     Extension(user_id): Extension<Uuid>,
     State(state): State<AppState>,
-    mut multipart: Multipart,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
+    Query(params): Query<DownloadArchiveQuery>,
+) -> Result<Response, (StatusCode, String)> {
     let user_id = if let Ok(user_id) = Id::try_from(user_id) {
         user_id
     } else {
@@ -167,73 +514,2014 @@ pub async fn handler_upload_pdf(
         ));
     };
 
-    if let Some(mut field) = multipart.next_field().await.map_err(|err| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            format!("Error processing file: {}", err),
-        )
-    })? {
-        let content_type = field.content_type().map(|ct| ct.to_string());
-        if content_type != Some("application/pdf".to_string()) {
-            return Err((
-                StatusCode::BAD_REQUEST,
-                "Only PDF files are allowed!".to_string(),
-            ));
+    let payload = usecases::download_archive::Payload {
+        folder_id: params.folder_id,
+        drive_id: params.drive_id,
+        file_ids: params
+            .file_ids
+            .map(|x| {
+                x.split(',')
+                    .map(str::trim)
+                    .filter(|x| !x.is_empty())
+                    .map(|x| x.to_string())
+                    .collect()
+            })
+            .unwrap_or_default(),
+        user_id,
+        allowed_types: state.config.allowed_file_types.clone(),
+        max_depth: state.config.folder_tree_max_depth,
+        concurrency: state.config.folder_tree_concurrency,
+    };
+    let archive = match usecases::download_archive::execute(
+        &state.user_repository,
+        &state.google_drive_service,
+        payload,
+    )
+    .await
+    {
+        Ok(archive) => archive,
+        Err(err @ usecases::download_archive::Error::NotFound(_)) => {
+            return Err((StatusCode::NOT_FOUND, err.to_string()));
         }
+        Err(err @ usecases::download_archive::Error::InvalidRequest(_)) => {
+            return Err((StatusCode::BAD_REQUEST, err.to_string()));
+        }
+        Err(err @ usecases::download_archive::Error::GoogleUnauthenticated) => {
+            return Err((StatusCode::UNAUTHORIZED, err.to_string()));
+        }
+        Err(err) => {
+            return Err((StatusCode::INTERNAL_SERVER_ERROR, err.to_string()));
+        }
+    };
 
-        let file_name = field
-            .file_name()
-            .map(|name| name.to_string())
-            .ok_or_else(|| {
-                (
-                    StatusCode::BAD_REQUEST,
-                    "File name not provided!".to_string(),
-                )
-            })?;
+    let content_disposition = content_disposition("attachment", &archive.name);
+    let skipped = archive.skipped;
+    let (sender, receiver) = mpsc::channel(ARCHIVE_BUFFER);
+    tokio::spawn(write_archive(state, archive, sender));
 
-        let file_path = format!("{}", file_name);
-        let mut file = File::create(&file_path).await.map_err(|err| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Failed to create file: {}", err),
-            )
-        })?;
+    Response::builder()
+        .header(header::CONTENT_TYPE, "application/zip")
+        .header(header::CONTENT_DISPOSITION, content_disposition)
+        .header("x-skipped-files", skipped)
+        .body(Body::from_stream(receiver))
+        .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))
+}
+
+/// Sends the archive through `sender`, downloading each file when its turn comes so only
+/// a few chunks are held at a time. Stops when the client goes away; a file that fails
+/// midway ends the response early, since the status was sent already.
+async fn write_archive(
+    state: AppState,
+    archive: Archive,
+    mut sender: mpsc::Sender<Result<Bytes, String>>,
+) {
+    let mut user = archive.user;
+    let mut zip = ZipWriter::new();
+    for entry in archive.entries {
+        let header = zip.start_entry(&entry.path, entry.file.modified_at, entry.file.size);
+        if sender.send(Ok(header)).await.is_err() {
+            return;
+        }
 
-        while let Some(chunk) = field.chunk().await.map_err(|err| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Error while reading file: {}", err),
+        let mut crc = Crc32::new();
+        let mut size = 0;
+        if !entry.file.is_folder() {
+            let content = usecases::download_archive::open_entry(
+                &state.user_repository,
+                &state.google_drive_service,
+                &mut user,
+                &entry,
             )
-        })? {
-            file.write_all(&chunk).await.map_err(|err| {
-                (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    format!("Error while writing file: {}", err),
-                )
-            })?;
+            .await
+            .map_err(|err| err.to_string());
+            let mut content = match content {
+                Ok(content) => content.map_err(|err| err.to_string()),
+                Err(err) => {
+                    eprintln!("Error archiving {}: {}", entry.path, err);
+                    let _ = sender.send(Err(err)).await;
+                    return;
+                }
+            };
+            while let Some(chunk) = content.next().await {
+                if let Err(err) = &chunk {
+                    eprintln!("Error archiving {}: {}", entry.path, err);
+                }
+                let failed = chunk.is_err();
+                if let Ok(chunk) = &chunk {
+                    crc.update(chunk);
+                    size += chunk.len() as u64;
+                }
+                if sender.send(chunk).await.is_err() || failed {
+                    return;
+                }
+            }
         }
 
-        let payload = usecases::upload_pdf::Payload {
-            file_name,
-            user_id,
-            file_path,
-        };
+        let descriptor = zip.finish_entry(crc.value(), size);
+        if let Err(err) = &descriptor {
+            eprintln!("Error archiving {}: {}", entry.path, err);
+        }
+        let failed = descriptor.is_err();
+        if sender.send(descriptor).await.is_err() || failed {
+            return;
+        }
+    }
+    let _ = sender.send(Ok(zip.finish())).await;
+}
 
-        let msg = match usecases::upload_pdf::execute(
-            &state.user_repository,
-            &state.google_drive_service,
-            payload,
-        )
-        .await
-        {
-            Ok(msg) => msg,
+/// Headers that let the client cache the file and make conditional or range requests.
+fn validator_headers(mut response: response::Builder, file: &FileInfo) -> response::Builder {
+    // Ranges need the size, which exported files don't have
+    if file.size.is_some() {
+        response = response.header(header::ACCEPT_RANGES, "bytes");
+    }
+    if let Some(tag) = file.entity_tag() {
+        response = response.header(header::ETAG, entity_tag(&tag));
+    }
+    if let Some(modified_at) = file.modified_at {
+        response = response.header(header::LAST_MODIFIED, http_date(modified_at));
+    }
+    response
+}
+
+#[derive(Deserialize)]
+pub struct UploadFileQuery {
+    /// Root of the shared drive, or of the user's Drive, when not given
+    folder_id: Option<String>,
+    drive_id: Option<String>,
+    #[serde(default)]
+    on_conflict: ConflictPolicy,
+    /// Stores the page count and title of uploaded PDFs as Drive app properties
+    #[serde(default)]
+    pdf_properties: bool,
+}
+
+pub async fn handler_upload_file(
+    Extension(user_id): Extension<Uuid>,
+    State(state): State<AppState>,
+    Query(params): Query<UploadFileQuery>,
+    multipart: Multipart,
+) -> JsonResponse<FileInfo> {
+    let allowed_types = state.config.allowed_file_types.clone();
+    upload_file(user_id, state, params, multipart, allowed_types).await
+}
+
+/// Same as `handler_upload_file`, limited to PDF files.
+pub async fn handler_upload_pdf(
+    Extension(user_id): Extension<Uuid>,
+    State(state): State<AppState>,
+    Query(params): Query<UploadFileQuery>,
+    multipart: Multipart,
+) -> JsonResponse<FileInfo> {
+    let allowed_types = state.config.allowed_file_types.only(PDF);
+    upload_file(user_id, state, params, multipart, allowed_types).await
+}
+
+async fn upload_file(
+    user_id: Uuid,
+    state: AppState,
+    params: UploadFileQuery,
+    mut multipart: Multipart,
+    allowed_types: FileTypePolicy,
+) -> JsonResponse<FileInfo> {
+    let user_id = if let Ok(user_id) = Id::try_from(user_id) {
+        user_id
+    } else {
+        return JsonResponse::new_int_ser_err("Internal Server Error".to_string());
+    };
+
+    let field = match multipart.next_field().await {
+        Ok(Some(field)) => field,
+        Ok(None) => return JsonResponse::new_bad_req_err("No file uploaded".to_string()),
+        Err(err) => {
+            return JsonResponse::new_bad_req_err(format!("Error processing file: {}", err))
+        }
+    };
+    let file_name = match field.file_name() {
+        Some(file_name) => file_name.to_string(),
+        None => return JsonResponse::new_bad_req_err("File name not provided!".to_string()),
+    };
+
+    // The field is forwarded to Drive as it arrives, without staging it on disk. For PDF
+    // properties a copy is kept as it goes by, as long as it is small enough to be read
+    let keep = params.pdf_properties && is_pdf(&allowed_types, &file_name);
+    let kept = Arc::new(Mutex::new(keep.then(BytesMut::new)));
+    let max_kept_size = state.config.pdf_max_size;
+    let content = field
+        .map_err(|err| {
+            google_drive_service::Error::Unknown(format!("Error while reading file: {}", err))
+        })
+        .inspect_ok({
+            let kept = kept.clone();
+            move |chunk| {
+                let mut kept = kept.lock().unwrap();
+                if kept
+                    .as_ref()
+                    .is_some_and(|x| (x.len() + chunk.len()) as u64 > max_kept_size)
+                {
+                    *kept = None;
+                }
+                if let Some(kept) = kept.as_mut() {
+                    kept.extend_from_slice(chunk);
+                }
+            }
+        });
+
+    let payload = usecases::upload_file::Payload {
+        file_name,
+        user_id,
+        content: Box::pin(content),
+        allowed_types,
+        parent_id: params.folder_id,
+        drive_id: params.drive_id,
+        conflict_policy: params.on_conflict,
+        space: None,
+    };
+    match usecases::upload_file::execute(
+        &state.user_repository,
+        &state.google_drive_service,
+        payload,
+    )
+    .await
+    {
+        Ok(file) => {
+            let content = kept.lock().unwrap().take().map(BytesMut::freeze);
+            JsonResponse::new_ok(with_pdf_properties(&state, user_id, file, content).await)
+        }
+        Err(err) => upload_error(err),
+    }
+}
+
+/// Whether the file would be uploaded as a PDF, going by its name.
+fn is_pdf(allowed_types: &FileTypePolicy, file_name: &str) -> bool {
+    allowed_types
+        .find_by_file_name(file_name)
+        .is_some_and(|x| x.mime_type == PDF)
+}
+
+/// Stores the page count and title of an uploaded PDF on its Drive file, when its content
+/// was kept for it. The upload went through already, so a failure is only logged.
+async fn with_pdf_properties(
+    state: &AppState,
+    user_id: Id,
+    file: FileInfo,
+    content: Option<Bytes>,
+) -> FileInfo {
+    let Some(content) = content.filter(|_| file.file_type == PDF) else {
+        return file;
+    };
+    let payload = usecases::store_pdf_properties::Payload {
+        file_id: file.id.clone(),
+        user_id,
+        content,
+    };
+    match usecases::store_pdf_properties::execute(
+        &state.user_repository,
+        &state.google_drive_service,
+        &state.pdf_reader,
+        payload,
+    )
+    .await
+    {
+        Ok(file) => file,
+        Err(err) => {
+            eprintln!("Error storing the PDF properties of {}: {}", file.id, err);
+            file
+        }
+    }
+}
+
+/// Result of one file of a batch upload.
+#[derive(Serialize)]
+pub struct BatchUploadResult {
+    file_name: String,
+    /// Status the file would have gotten uploaded on its own
+    status: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    file: Option<FileInfo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl BatchUploadResult {
+    fn new(file_name: String, result: Result<FileInfo, usecases::upload_file::Error>) -> Self {
+        match result {
+            Ok(file) => Self {
+                file_name,
+                status: StatusCode::OK.as_u16(),
+                file: Some(file),
+                error: None,
+            },
+            Err(err) => {
+                let response = upload_error::<FileInfo>(err);
+                Self {
+                    file_name,
+                    status: response.status.as_u16(),
+                    file: None,
+                    error: response.error.map(|x| x.details),
+                }
+            }
+        }
+    }
+}
+
+/// Uploads every file of the request, see `usecases::upload_files`. Answers `200` with a
+/// result per file even when some of them failed.
+pub async fn handler_upload_files(
+    Extension(user_id): Extension<Uuid>,
+    State(state): State<AppState>,
+    Query(params): Query<UploadFileQuery>,
+    mut multipart: Multipart,
+) -> JsonResponse<Vec<BatchUploadResult>> {
+    let user_id = if let Ok(user_id) = Id::try_from(user_id) {
+        user_id
+    } else {
+        return JsonResponse::new_int_ser_err("Internal Server Error".to_string());
+    };
+
+    // Fields can only be read one after the other, so each file is buffered before the
+    // uploads start. The route body limit bounds the memory this takes
+    let mut files = Vec::new();
+    let mut kept = Vec::new();
+    loop {
+        let field = match multipart.next_field().await {
+            Ok(Some(field)) => field,
+            Ok(None) => break,
+            Err(err) => {
+                return JsonResponse::new_err(err.status(), "Invalid upload", err.body_text())
+            }
+        };
+        let file_name = match field.file_name() {
+            Some(file_name) => file_name.to_string(),
+            None => continue,
+        };
+        if files.len() == state.config.batch_upload_max_files {
+            return JsonResponse::new_bad_req_err(format!(
+                "A batch can have at most {} files",
+                state.config.batch_upload_max_files
+            ));
+        }
+        let content = match field.bytes().await {
+            Ok(content) => content,
             Err(err) => {
-                return Err((StatusCode::INTERNAL_SERVER_ERROR, err.to_string()));
+                return JsonResponse::new_err(err.status(), "Invalid upload", err.body_text())
             }
         };
+        let keep = params.pdf_properties && is_pdf(&state.config.allowed_file_types, &file_name);
+        kept.push(keep.then(|| content.clone()));
+        files.push(usecases::upload_files::FileUpload {
+            file_name,
+            content: Box::pin(stream::once(future::ready(Ok(content)))),
+        });
+    }
+    if files.is_empty() {
+        return JsonResponse::new_bad_req_err("No file uploaded".to_string());
+    }
+
+    let file_names: Vec<String> = files.iter().map(|x| x.file_name.clone()).collect();
+    let payload = usecases::upload_files::Payload {
+        files,
+        user_id,
+        allowed_types: state.config.allowed_file_types.clone(),
+        parent_id: params.folder_id,
+        drive_id: params.drive_id,
+        conflict_policy: params.on_conflict,
+        concurrency: state.config.batch_upload_concurrency,
+    };
+    let results = usecases::upload_files::execute(
+        &state.user_repository,
+        &state.google_drive_service,
+        payload,
+    )
+    .await;
+    let results: Vec<_> = results
+        .into_iter()
+        .zip(kept)
+        .map(|(result, content)| {
+            let state = &state;
+            async move {
+                match result {
+                    Ok(file) => Ok(with_pdf_properties(state, user_id, file, content).await),
+                    Err(err) => Err(err),
+                }
+            }
+        })
+        .collect();
+    let results: Vec<_> = stream::iter(results)
+        .buffered(state.config.batch_upload_concurrency.max(1))
+        .collect()
+        .await;
+
+    JsonResponse::new_ok(
+        file_names
+            .into_iter()
+            .zip(results)
+            .map(|(file_name, result)| BatchUploadResult::new(file_name, result))
+            .collect(),
+    )
+}
+
+fn upload_error<T>(err: usecases::upload_file::Error) -> JsonResponse<T> {
+    match err {
+        err @ usecases::upload_file::Error::NotFound(_) => {
+            JsonResponse::new_not_found_err(err.to_string())
+        }
+        err @ usecases::upload_file::Error::UnsupportedFileType(_) => JsonResponse::new_err(
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            "Unsupported Media Type",
+            err.to_string(),
+        ),
+        err @ usecases::upload_file::Error::TooLarge(_) => JsonResponse::new_err(
+            StatusCode::PAYLOAD_TOO_LARGE,
+            "Payload Too Large",
+            err.to_string(),
+        ),
+        err @ usecases::upload_file::Error::Conflict(_) => {
+            JsonResponse::new_conflict_err(err.to_string())
+        }
+        err @ usecases::upload_file::Error::QuotaExceeded(_) => JsonResponse::new_err(
+            StatusCode::INSUFFICIENT_STORAGE,
+            "Insufficient Storage",
+            err.to_string(),
+        ),
+        err @ usecases::upload_file::Error::GoogleUnauthenticated => {
+            JsonResponse::new_unauthorized_err(err.to_string())
+        }
+        err => JsonResponse::new_int_ser_err(err.to_string()),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct MergePdfSource {
+    file_id: String,
+    /// Such as `1-3,5,8-`, every page when not given
+    pages: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct MergePdfsRequest {
+    files: Vec<MergePdfSource>,
+    name: String,
+    /// Folder of the first file when not given
+    folder_id: Option<String>,
+    #[serde(default)]
+    on_conflict: ConflictPolicy,
+}
 
-        return Ok((StatusCode::OK, msg));
+pub async fn handler_merge_pdfs(
+    Extension(user_id): Extension<Uuid>,
+    State(state): State<AppState>,
+    Json(request): Json<MergePdfsRequest>,
+) -> JsonResponse<FileInfo> {
+    let user_id = if let Ok(user_id) = Id::try_from(user_id) {
+        user_id
+    } else {
+        return JsonResponse::new_int_ser_err("Internal Server Error".to_string());
+    };
+    let mut sources = Vec::with_capacity(request.files.len());
+    for file in request.files {
+        let pages = match file.pages.as_deref().map(PageRanges::parse).transpose() {
+            Ok(pages) => pages,
+            Err(err) => return JsonResponse::new_bad_req_err(err),
+        };
+        sources.push(usecases::merge_pdfs::MergeSource {
+            file_id: file.file_id,
+            pages,
+        });
     }
 
-    Err((StatusCode::BAD_REQUEST, "No file uploaded".to_string()))
+    let payload = usecases::merge_pdfs::Payload {
+        sources,
+        file_name: request.name,
+        user_id,
+        folder_id: request.folder_id,
+        conflict_policy: request.on_conflict,
+        allowed_types: state.config.allowed_file_types.only(PDF),
+        max_files: state.config.pdf_max_files,
+        max_size: state.config.pdf_max_size,
+    };
+    match usecases::merge_pdfs::execute(
+        &state.user_repository,
+        &state.google_drive_service,
+        &state.pdf_editor,
+        payload,
+    )
+    .await
+    {
+        Ok(file) => JsonResponse::new_ok(file),
+        Err(err @ usecases::merge_pdfs::Error::NotFound(_)) => {
+            JsonResponse::new_not_found_err(err.to_string())
+        }
+        Err(err @ usecases::merge_pdfs::Error::InvalidRequest(_)) => {
+            JsonResponse::new_bad_req_err(err.to_string())
+        }
+        Err(err @ usecases::merge_pdfs::Error::TooLarge(_)) => JsonResponse::new_err(
+            StatusCode::PAYLOAD_TOO_LARGE,
+            "Payload Too Large",
+            err.to_string(),
+        ),
+        Err(err @ usecases::merge_pdfs::Error::GoogleUnauthenticated) => {
+            JsonResponse::new_unauthorized_err(err.to_string())
+        }
+        Err(usecases::merge_pdfs::Error::Upload(err)) => upload_error(err),
+        Err(err) => JsonResponse::new_int_ser_err(err.to_string()),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct SplitPdfPart {
+    pages: String,
+    /// Named after the file and the pages when not given
+    name: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct SplitPdfRequest {
+    file_id: String,
+    parts: Vec<SplitPdfPart>,
+    /// Folder of the file when not given
+    folder_id: Option<String>,
+    #[serde(default)]
+    on_conflict: ConflictPolicy,
+}
+
+/// Writes each part of the PDF to a new file, see `usecases::split_pdf`. Once the parts
+/// are made, answers `200` with a result per file like `upload-batch` does.
+pub async fn handler_split_pdf(
+    Extension(user_id): Extension<Uuid>,
+    State(state): State<AppState>,
+    Json(request): Json<SplitPdfRequest>,
+) -> JsonResponse<Vec<BatchUploadResult>> {
+    let user_id = if let Ok(user_id) = Id::try_from(user_id) {
+        user_id
+    } else {
+        return JsonResponse::new_int_ser_err("Internal Server Error".to_string());
+    };
+    let mut parts = Vec::with_capacity(request.parts.len());
+    for part in request.parts {
+        let pages = match PageRanges::parse(&part.pages) {
+            Ok(pages) => pages,
+            Err(err) => return JsonResponse::new_bad_req_err(err),
+        };
+        parts.push(usecases::split_pdf::SplitPart {
+            pages,
+            file_name: part.name,
+        });
+    }
+
+    let payload = usecases::split_pdf::Payload {
+        file_id: request.file_id,
+        parts,
+        user_id,
+        folder_id: request.folder_id,
+        conflict_policy: request.on_conflict,
+        allowed_types: state.config.allowed_file_types.only(PDF),
+        max_files: state.config.pdf_max_files,
+        max_size: state.config.pdf_max_size,
+        concurrency: state.config.batch_upload_concurrency,
+    };
+    match usecases::split_pdf::execute(
+        &state.user_repository,
+        &state.google_drive_service,
+        &state.pdf_editor,
+        payload,
+    )
+    .await
+    {
+        Ok(results) => JsonResponse::new_ok(
+            results
+                .into_iter()
+                .map(|(file_name, result)| BatchUploadResult::new(file_name, result))
+                .collect(),
+        ),
+        Err(err @ usecases::split_pdf::Error::NotFound(_)) => {
+            JsonResponse::new_not_found_err(err.to_string())
+        }
+        Err(err @ usecases::split_pdf::Error::InvalidRequest(_)) => {
+            JsonResponse::new_bad_req_err(err.to_string())
+        }
+        Err(err @ usecases::split_pdf::Error::TooLarge(_)) => JsonResponse::new_err(
+            StatusCode::PAYLOAD_TOO_LARGE,
+            "Payload Too Large",
+            err.to_string(),
+        ),
+        Err(err @ usecases::split_pdf::Error::GoogleUnauthenticated) => {
+            JsonResponse::new_unauthorized_err(err.to_string())
+        }
+        Err(err) => JsonResponse::new_int_ser_err(err.to_string()),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ExtractPdfQuery {
+    file_id: String,
+}
+
+pub async fn handler_extract_pdf(
+    Extension(user_id): Extension<Uuid>,
+    State(state): State<AppState>,
+    Query(params): Query<ExtractPdfQuery>,
+) -> JsonResponse<PdfDocument> {
+    let user_id = if let Ok(user_id) = Id::try_from(user_id) {
+        user_id
+    } else {
+        return JsonResponse::new_int_ser_err("Internal Server Error".to_string());
+    };
+    let payload = usecases::read_pdf::Payload {
+        file_id: params.file_id,
+        user_id,
+        max_size: state.config.pdf_max_size,
+    };
+    match usecases::read_pdf::execute(
+        &state.user_repository,
+        &state.google_drive_service,
+        &state.pdf_reader,
+        payload,
+    )
+    .await
+    {
+        Ok(document) => JsonResponse::new_ok(document),
+        Err(err @ usecases::read_pdf::Error::NotFound(_)) => {
+            JsonResponse::new_not_found_err(err.to_string())
+        }
+        Err(err @ usecases::read_pdf::Error::InvalidRequest(_)) => {
+            JsonResponse::new_bad_req_err(err.to_string())
+        }
+        Err(err @ usecases::read_pdf::Error::TooLarge(_)) => JsonResponse::new_err(
+            StatusCode::PAYLOAD_TOO_LARGE,
+            "Payload Too Large",
+            err.to_string(),
+        ),
+        Err(err @ usecases::read_pdf::Error::GoogleUnauthenticated) => {
+            JsonResponse::new_unauthorized_err(err.to_string())
+        }
+        Err(err) => JsonResponse::new_int_ser_err(err.to_string()),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct TrashFileRequest {
+    file_id: String,
+    drive_id: Option<String>,
+}
+
+pub async fn handler_trash_file(
+    Extension(user_id): Extension<Uuid>,
+    State(state): State<AppState>,
+    Json(request): Json<TrashFileRequest>,
+) -> JsonResponse<FileInfo> {
+    let user_id = if let Ok(user_id) = Id::try_from(user_id) {
+        user_id
+    } else {
+        return JsonResponse::new_int_ser_err("Internal Server Error".to_string());
+    };
+    let payload = usecases::trash_file::Payload {
+        file_id: request.file_id,
+        drive_id: request.drive_id,
+        user_id,
+    };
+    match usecases::trash_file::execute(
+        &state.user_repository,
+        &state.google_drive_service,
+        payload,
+    )
+    .await
+    {
+        Ok(file) => JsonResponse::<FileInfo>::new_ok(file),
+        Err(err @ usecases::trash_file::Error::NotFound(_)) => {
+            JsonResponse::new_not_found_err(err.to_string())
+        }
+        Err(err @ usecases::trash_file::Error::GoogleUnauthenticated) => {
+            JsonResponse::new_unauthorized_err(err.to_string())
+        }
+        Err(err) => JsonResponse::new_int_ser_err(err.to_string()),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct RestoreFileRequest {
+    file_id: String,
+    drive_id: Option<String>,
+}
+
+pub async fn handler_restore_file(
+    Extension(user_id): Extension<Uuid>,
+    State(state): State<AppState>,
+    Json(request): Json<RestoreFileRequest>,
+) -> JsonResponse<FileInfo> {
+    let user_id = if let Ok(user_id) = Id::try_from(user_id) {
+        user_id
+    } else {
+        return JsonResponse::new_int_ser_err("Internal Server Error".to_string());
+    };
+    let payload = usecases::restore_file::Payload {
+        file_id: request.file_id,
+        drive_id: request.drive_id,
+        user_id,
+    };
+    match usecases::restore_file::execute(
+        &state.user_repository,
+        &state.google_drive_service,
+        payload,
+    )
+    .await
+    {
+        Ok(file) => JsonResponse::<FileInfo>::new_ok(file),
+        Err(err @ usecases::restore_file::Error::NotFound(_)) => {
+            JsonResponse::new_not_found_err(err.to_string())
+        }
+        Err(err @ usecases::restore_file::Error::GoogleUnauthenticated) => {
+            JsonResponse::new_unauthorized_err(err.to_string())
+        }
+        Err(err) => JsonResponse::new_int_ser_err(err.to_string()),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct DeleteFileRequest {
+    file_id: String,
+    drive_id: Option<String>,
+}
+
+pub async fn handler_delete_file(
+    Extension(user_id): Extension<Uuid>,
+    State(state): State<AppState>,
+    Json(request): Json<DeleteFileRequest>,
+) -> JsonResponse<FileInfo> {
+    let user_id = if let Ok(user_id) = Id::try_from(user_id) {
+        user_id
+    } else {
+        return JsonResponse::new_int_ser_err("Internal Server Error".to_string());
+    };
+    let payload = usecases::delete_file::Payload {
+        file_id: request.file_id,
+        drive_id: request.drive_id,
+        user_id,
+    };
+    match usecases::delete_file::execute(
+        &state.user_repository,
+        &state.google_drive_service,
+        payload,
+    )
+    .await
+    {
+        Ok(file) => JsonResponse::<FileInfo>::new_ok(file),
+        Err(err @ usecases::delete_file::Error::NotFound(_)) => {
+            JsonResponse::new_not_found_err(err.to_string())
+        }
+        Err(err @ usecases::delete_file::Error::GoogleUnauthenticated) => {
+            JsonResponse::new_unauthorized_err(err.to_string())
+        }
+        Err(err) => JsonResponse::new_int_ser_err(err.to_string()),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct RenameFileRequest {
+    file_id: String,
+    drive_id: Option<String>,
+    name: String,
+}
+
+pub async fn handler_rename_file(
+    Extension(user_id): Extension<Uuid>,
+    State(state): State<AppState>,
+    Json(request): Json<RenameFileRequest>,
+) -> JsonResponse<FileInfo> {
+    let user_id = if let Ok(user_id) = Id::try_from(user_id) {
+        user_id
+    } else {
+        return JsonResponse::new_int_ser_err("Internal Server Error".to_string());
+    };
+    let payload = usecases::rename_file::Payload {
+        file_id: request.file_id,
+        drive_id: request.drive_id,
+        name: request.name,
+        user_id,
+    };
+    match usecases::rename_file::execute(
+        &state.user_repository,
+        &state.google_drive_service,
+        payload,
+    )
+    .await
+    {
+        Ok(file) => JsonResponse::<FileInfo>::new_ok(file),
+        Err(err @ usecases::rename_file::Error::InvalidName(_)) => {
+            JsonResponse::new_bad_req_err(err.to_string())
+        }
+        Err(err @ usecases::rename_file::Error::NotFound(_)) => {
+            JsonResponse::new_not_found_err(err.to_string())
+        }
+        Err(err @ usecases::rename_file::Error::GoogleUnauthenticated) => {
+            JsonResponse::new_unauthorized_err(err.to_string())
+        }
+        Err(err) => JsonResponse::new_int_ser_err(err.to_string()),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct MoveFileRequest {
+    file_id: String,
+    drive_id: Option<String>,
+    folder_id: String,
+}
+
+pub async fn handler_move_file(
+    Extension(user_id): Extension<Uuid>,
+    State(state): State<AppState>,
+    Json(request): Json<MoveFileRequest>,
+) -> JsonResponse<FileInfo> {
+    let user_id = if let Ok(user_id) = Id::try_from(user_id) {
+        user_id
+    } else {
+        return JsonResponse::new_int_ser_err("Internal Server Error".to_string());
+    };
+    let payload = usecases::move_file::Payload {
+        file_id: request.file_id,
+        drive_id: request.drive_id,
+        folder_id: request.folder_id,
+        user_id,
+    };
+    match usecases::move_file::execute(&state.user_repository, &state.google_drive_service, payload)
+        .await
+    {
+        Ok(file) => JsonResponse::<FileInfo>::new_ok(file),
+        Err(err @ usecases::move_file::Error::NotFound(_)) => {
+            JsonResponse::new_not_found_err(err.to_string())
+        }
+        Err(err @ usecases::move_file::Error::GoogleUnauthenticated) => {
+            JsonResponse::new_unauthorized_err(err.to_string())
+        }
+        Err(err) => JsonResponse::new_int_ser_err(err.to_string()),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct CopyFileRequest {
+    file_id: String,
+    name: Option<String>,
+    folder_id: Option<String>,
+    /// Copies to the root of this shared drive when there is no folder
+    drive_id: Option<String>,
+}
+
+pub async fn handler_copy_file(
+    Extension(user_id): Extension<Uuid>,
+    State(state): State<AppState>,
+    Json(request): Json<CopyFileRequest>,
+) -> JsonResponse<FileInfo> {
+    let user_id = if let Ok(user_id) = Id::try_from(user_id) {
+        user_id
+    } else {
+        return JsonResponse::new_int_ser_err("Internal Server Error".to_string());
+    };
+    let payload = usecases::copy_file::Payload {
+        file_id: request.file_id,
+        name: request.name,
+        folder_id: request.folder_id,
+        drive_id: request.drive_id,
+        user_id,
+    };
+    match usecases::copy_file::execute(&state.user_repository, &state.google_drive_service, payload)
+        .await
+    {
+        Ok(file) => JsonResponse::<FileInfo>::new_ok(file),
+        Err(err @ usecases::copy_file::Error::InvalidName(_)) => {
+            JsonResponse::new_bad_req_err(err.to_string())
+        }
+        Err(err @ usecases::copy_file::Error::NotFound(_)) => {
+            JsonResponse::new_not_found_err(err.to_string())
+        }
+        Err(err @ usecases::copy_file::Error::GoogleUnauthenticated) => {
+            JsonResponse::new_unauthorized_err(err.to_string())
+        }
+        Err(err) => JsonResponse::new_int_ser_err(err.to_string()),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct CreateFolderRequest {
+    name: String,
+    parent_id: Option<String>,
+    /// Creates the folder at the root of this shared drive when there is no parent
+    drive_id: Option<String>,
+}
+
+pub async fn handler_create_folder(
+    Extension(user_id): Extension<Uuid>,
+    State(state): State<AppState>,
+    Json(request): Json<CreateFolderRequest>,
+) -> JsonResponse<FileInfo> {
+    let user_id = if let Ok(user_id) = Id::try_from(user_id) {
+        user_id
+    } else {
+        return JsonResponse::new_int_ser_err("Internal Server Error".to_string());
+    };
+    let payload = usecases::create_folder::Payload {
+        name: request.name,
+        parent_id: request.parent_id,
+        drive_id: request.drive_id,
+        user_id,
+    };
+    match usecases::create_folder::execute(
+        &state.user_repository,
+        &state.google_drive_service,
+        payload,
+    )
+    .await
+    {
+        Ok(folder) => JsonResponse::<FileInfo>::new_ok(folder),
+        Err(err @ usecases::create_folder::Error::InvalidName(_)) => {
+            JsonResponse::new_bad_req_err(err.to_string())
+        }
+        Err(err @ usecases::create_folder::Error::NotFound(_)) => {
+            JsonResponse::new_not_found_err(err.to_string())
+        }
+        Err(err @ usecases::create_folder::Error::GoogleUnauthenticated) => {
+            JsonResponse::new_unauthorized_err(err.to_string())
+        }
+        Err(err) => JsonResponse::new_int_ser_err(err.to_string()),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct FolderTreeQuery {
+    /// Defaults to the root of the shared drive when there is a `drive_id`
+    folder_id: Option<String>,
+    drive_id: Option<String>,
+    depth: Option<u32>,
+}
+
+pub async fn handler_get_folder_tree(
+    Extension(user_id): Extension<Uuid>,
+    State(state): State<AppState>,
+    Query(params): Query<FolderTreeQuery>,
+) -> JsonResponse<FolderTree> {
+    let user_id = if let Ok(user_id) = Id::try_from(user_id) {
+        user_id
+    } else {
+        return JsonResponse::new_int_ser_err("Internal Server Error".to_string());
+    };
+    let max_depth = state.config.folder_tree_max_depth;
+    let payload = usecases::folder_tree::Payload {
+        folder_id: params.folder_id,
+        drive_id: params.drive_id,
+        max_depth: params.depth.unwrap_or(max_depth).clamp(1, max_depth.max(1)),
+        concurrency: state.config.folder_tree_concurrency,
+        user_id,
+    };
+    match usecases::folder_tree::execute(
+        &state.user_repository,
+        &state.google_drive_service,
+        payload,
+    )
+    .await
+    {
+        Ok(tree) => JsonResponse::<FolderTree>::new_ok(tree),
+        Err(err @ usecases::folder_tree::Error::NotAFolder(_)) => {
+            JsonResponse::new_bad_req_err(err.to_string())
+        }
+        Err(err @ usecases::folder_tree::Error::InvalidRequest(_)) => {
+            JsonResponse::new_bad_req_err(err.to_string())
+        }
+        Err(err @ usecases::folder_tree::Error::NotFound(_)) => {
+            JsonResponse::new_not_found_err(err.to_string())
+        }
+        Err(err @ usecases::folder_tree::Error::GoogleUnauthenticated) => {
+            JsonResponse::new_unauthorized_err(err.to_string())
+        }
+        Err(err) => JsonResponse::new_int_ser_err(err.to_string()),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ListPermissionsQuery {
+    file_id: String,
+    drive_id: Option<String>,
+}
+
+pub async fn handler_get_permissions(
+    Extension(user_id): Extension<Uuid>,
+    State(state): State<AppState>,
+    Query(params): Query<ListPermissionsQuery>,
+) -> JsonResponse<Vec<Permission>> {
+    let user_id = if let Ok(user_id) = Id::try_from(user_id) {
+        user_id
+    } else {
+        return JsonResponse::new_int_ser_err("Internal Server Error".to_string());
+    };
+    let payload = usecases::list_permissions::Payload {
+        file_id: params.file_id,
+        drive_id: params.drive_id,
+        user_id,
+    };
+    match usecases::list_permissions::execute(
+        &state.user_repository,
+        &state.google_drive_service,
+        payload,
+    )
+    .await
+    {
+        Ok(permissions) => JsonResponse::<Vec<Permission>>::new_ok(permissions),
+        Err(err @ usecases::list_permissions::Error::InvalidPermission(_)) => {
+            JsonResponse::new_bad_req_err(err.to_string())
+        }
+        Err(err @ usecases::list_permissions::Error::Forbidden(_)) => {
+            JsonResponse::new_forbidden_err(err.to_string())
+        }
+        Err(err @ usecases::list_permissions::Error::NotFound(_)) => {
+            JsonResponse::new_not_found_err(err.to_string())
+        }
+        Err(err @ usecases::list_permissions::Error::GoogleUnauthenticated) => {
+            JsonResponse::new_unauthorized_err(err.to_string())
+        }
+        Err(err) => JsonResponse::new_int_ser_err(err.to_string()),
+    }
+}
+
+fn default_send_notification() -> bool {
+    true
+}
+
+#[derive(Deserialize)]
+pub struct GrantPermissionRequest {
+    file_id: String,
+    drive_id: Option<String>,
+    permission_type: PermissionType,
+    role: PermissionRole,
+    email_address: Option<String>,
+    domain: Option<String>,
+    expires_at: Option<DateTime<Utc>>,
+    #[serde(default = "default_send_notification")]
+    send_notification: bool,
+    message: Option<String>,
+}
+
+pub async fn handler_grant_permission(
+    Extension(user_id): Extension<Uuid>,
+    State(state): State<AppState>,
+    Json(request): Json<GrantPermissionRequest>,
+) -> JsonResponse<Permission> {
+    let user_id = if let Ok(user_id) = Id::try_from(user_id) {
+        user_id
+    } else {
+        return JsonResponse::new_int_ser_err("Internal Server Error".to_string());
+    };
+    let payload = usecases::grant_permission::Payload {
+        file_id: request.file_id,
+        drive_id: request.drive_id,
+        grant: PermissionGrant {
+            permission_type: request.permission_type,
+            role: request.role,
+            email_address: request.email_address,
+            domain: request.domain,
+            expires_at: request.expires_at,
+            send_notification: request.send_notification,
+            message: request.message,
+        },
+        user_id,
+    };
+    match usecases::grant_permission::execute(
+        &state.user_repository,
+        &state.google_drive_service,
+        payload,
+    )
+    .await
+    {
+        Ok(permission) => JsonResponse::<Permission>::new_ok(permission),
+        Err(err @ usecases::grant_permission::Error::InvalidPermission(_)) => {
+            JsonResponse::new_bad_req_err(err.to_string())
+        }
+        Err(err @ usecases::grant_permission::Error::Forbidden(_)) => {
+            JsonResponse::new_forbidden_err(err.to_string())
+        }
+        Err(err @ usecases::grant_permission::Error::NotFound(_)) => {
+            JsonResponse::new_not_found_err(err.to_string())
+        }
+        Err(err @ usecases::grant_permission::Error::GoogleUnauthenticated) => {
+            JsonResponse::new_unauthorized_err(err.to_string())
+        }
+        Err(err) => JsonResponse::new_int_ser_err(err.to_string()),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct UpdatePermissionRequest {
+    file_id: String,
+    drive_id: Option<String>,
+    permission_id: String,
+    role: Option<PermissionRole>,
+    expires_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    remove_expiration: bool,
+}
+
+pub async fn handler_update_permission(
+    Extension(user_id): Extension<Uuid>,
+    State(state): State<AppState>,
+    Json(request): Json<UpdatePermissionRequest>,
+) -> JsonResponse<Permission> {
+    let user_id = if let Ok(user_id) = Id::try_from(user_id) {
+        user_id
+    } else {
+        return JsonResponse::new_int_ser_err("Internal Server Error".to_string());
+    };
+    let payload = usecases::update_permission::Payload {
+        file_id: request.file_id,
+        drive_id: request.drive_id,
+        permission_id: request.permission_id,
+        update: PermissionUpdate {
+            role: request.role,
+            expires_at: request.expires_at,
+            remove_expiration: request.remove_expiration,
+        },
+        user_id,
+    };
+    match usecases::update_permission::execute(
+        &state.user_repository,
+        &state.google_drive_service,
+        payload,
+    )
+    .await
+    {
+        Ok(permission) => JsonResponse::<Permission>::new_ok(permission),
+        Err(err @ usecases::update_permission::Error::InvalidPermission(_)) => {
+            JsonResponse::new_bad_req_err(err.to_string())
+        }
+        Err(err @ usecases::update_permission::Error::Forbidden(_)) => {
+            JsonResponse::new_forbidden_err(err.to_string())
+        }
+        Err(err @ usecases::update_permission::Error::NotFound(_)) => {
+            JsonResponse::new_not_found_err(err.to_string())
+        }
+        Err(err @ usecases::update_permission::Error::GoogleUnauthenticated) => {
+            JsonResponse::new_unauthorized_err(err.to_string())
+        }
+        Err(err) => JsonResponse::new_int_ser_err(err.to_string()),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct RevokePermissionRequest {
+    file_id: String,
+    drive_id: Option<String>,
+    permission_id: String,
+}
+
+pub async fn handler_revoke_permission(
+    Extension(user_id): Extension<Uuid>,
+    State(state): State<AppState>,
+    Json(request): Json<RevokePermissionRequest>,
+) -> JsonResponse<String> {
+    let user_id = if let Ok(user_id) = Id::try_from(user_id) {
+        user_id
+    } else {
+        return JsonResponse::new_int_ser_err("Internal Server Error".to_string());
+    };
+    let payload = usecases::revoke_permission::Payload {
+        file_id: request.file_id,
+        drive_id: request.drive_id,
+        permission_id: request.permission_id.clone(),
+        user_id,
+    };
+    match usecases::revoke_permission::execute(
+        &state.user_repository,
+        &state.google_drive_service,
+        payload,
+    )
+    .await
+    {
+        Ok(()) => JsonResponse::<String>::new_ok(request.permission_id),
+        Err(err @ usecases::revoke_permission::Error::InvalidPermission(_)) => {
+            JsonResponse::new_bad_req_err(err.to_string())
+        }
+        Err(err @ usecases::revoke_permission::Error::Forbidden(_)) => {
+            JsonResponse::new_forbidden_err(err.to_string())
+        }
+        Err(err @ usecases::revoke_permission::Error::NotFound(_)) => {
+            JsonResponse::new_not_found_err(err.to_string())
+        }
+        Err(err @ usecases::revoke_permission::Error::GoogleUnauthenticated) => {
+            JsonResponse::new_unauthorized_err(err.to_string())
+        }
+        Err(err) => JsonResponse::new_int_ser_err(err.to_string()),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct RevisionsQuery {
+    file_id: String,
+    drive_id: Option<String>,
+}
+
+pub async fn handler_get_revisions(
+    Extension(user_id): Extension<Uuid>,
+    State(state): State<AppState>,
+    Query(params): Query<RevisionsQuery>,
+) -> JsonResponse<Vec<Revision>> {
+    let user_id = if let Ok(user_id) = Id::try_from(user_id) {
+        user_id
+    } else {
+        return JsonResponse::new_int_ser_err("Internal Server Error".to_string());
+    };
+    let payload = usecases::list_revisions::Payload {
+        file_id: params.file_id,
+        drive_id: params.drive_id,
+        user_id,
+    };
+    match usecases::list_revisions::execute(
+        &state.user_repository,
+        &state.google_drive_service,
+        payload,
+    )
+    .await
+    {
+        Ok(revisions) => JsonResponse::new_ok(revisions),
+        Err(err @ usecases::list_revisions::Error::NotFound(_)) => {
+            JsonResponse::new_not_found_err(err.to_string())
+        }
+        Err(err @ usecases::list_revisions::Error::GoogleUnauthenticated) => {
+            JsonResponse::new_unauthorized_err(err.to_string())
+        }
+        Err(err) => JsonResponse::new_int_ser_err(err.to_string()),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ThumbnailQuery {
+    file_id: String,
+    width: Option<u32>,
+}
+
+pub async fn handler_get_thumbnail(
+    Extension(user_id): Extension<Uuid>,
+    State(state): State<AppState>,
+    Query(params): Query<ThumbnailQuery>,
+) -> Result<Response, (StatusCode, String)> {
+    let user_id = if let Ok(user_id) = Id::try_from(user_id) {
+        user_id
+    } else {
+        return Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Internal Server Error".to_string(),
+        ));
+    };
+    let payload = usecases::get_thumbnail::Payload {
+        file_id: params.file_id,
+        user_id,
+        width: params.width,
+        max_pdf_size: state.config.thumbnail_max_pdf_size,
+    };
+    let thumbnail = match usecases::get_thumbnail::execute(
+        &state.user_repository,
+        &state.thumbnail_repository,
+        &state.google_drive_service,
+        &state.pdf_renderer,
+        payload,
+    )
+    .await
+    {
+        Ok(thumbnail) => thumbnail,
+        Err(
+            err @ (usecases::get_thumbnail::Error::NotFound(_)
+            | usecases::get_thumbnail::Error::NoPreview(_)),
+        ) => return Err((StatusCode::NOT_FOUND, err.to_string())),
+        Err(err @ usecases::get_thumbnail::Error::GoogleUnauthenticated) => {
+            return Err((StatusCode::UNAUTHORIZED, err.to_string()))
+        }
+        Err(err) => return Err((StatusCode::INTERNAL_SERVER_ERROR, err.to_string())),
+    };
+
+    Response::builder()
+        .header(header::CONTENT_TYPE, thumbnail.content_type)
+        .header(header::CONTENT_LENGTH, thumbnail.content.len())
+        // The file can change under the same id, so browsers keep it only for a while
+        .header(header::CACHE_CONTROL, "private, max-age=300")
+        .body(Body::from(thumbnail.content))
+        .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))
+}
+
+#[derive(Deserialize)]
+pub struct DownloadRevisionQuery {
+    file_id: String,
+    drive_id: Option<String>,
+    revision_id: String,
+}
+
+pub async fn handler_download_revision(
+    Extension(user_id): Extension<Uuid>,
+    State(state): State<AppState>,
+    Query(params): Query<DownloadRevisionQuery>,
+) -> Result<Response, (StatusCode, String)> {
+    let user_id = if let Ok(user_id) = Id::try_from(user_id) {
+        user_id
+    } else {
+        return Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Internal Server Error".to_string(),
+        ));
+    };
+    let payload = usecases::download_revision::Payload {
+        file_id: params.file_id,
+        drive_id: params.drive_id,
+        revision_id: params.revision_id,
+        user_id,
+        allowed_types: state.config.allowed_file_types.clone(),
+    };
+    match usecases::download_revision::execute(
+        &state.user_repository,
+        &state.google_drive_service,
+        payload,
+    )
+    .await
+    {
+        Ok(download) => download_response(Download::Full(download)),
+        Err(err @ usecases::download_revision::Error::NotFound(_)) => {
+            Err((StatusCode::NOT_FOUND, err.to_string()))
+        }
+        Err(err @ usecases::download_revision::Error::UnsupportedFileType(_)) => {
+            Err((StatusCode::UNSUPPORTED_MEDIA_TYPE, err.to_string()))
+        }
+        Err(err @ usecases::download_revision::Error::GoogleUnauthenticated) => {
+            Err((StatusCode::UNAUTHORIZED, err.to_string()))
+        }
+        Err(err) => Err((StatusCode::INTERNAL_SERVER_ERROR, err.to_string())),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct KeepRevisionRequest {
+    file_id: String,
+    drive_id: Option<String>,
+    revision_id: String,
+    /// Defaults to pinning the revision, `false` unpins it
+    keep_forever: Option<bool>,
+}
+
+pub async fn handler_keep_revision(
+    Extension(user_id): Extension<Uuid>,
+    State(state): State<AppState>,
+    Json(request): Json<KeepRevisionRequest>,
+) -> JsonResponse<Revision> {
+    let user_id = if let Ok(user_id) = Id::try_from(user_id) {
+        user_id
+    } else {
+        return JsonResponse::new_int_ser_err("Internal Server Error".to_string());
+    };
+    let payload = usecases::keep_revision::Payload {
+        file_id: request.file_id,
+        drive_id: request.drive_id,
+        revision_id: request.revision_id,
+        keep_forever: request.keep_forever.unwrap_or(true),
+        user_id,
+    };
+    match usecases::keep_revision::execute(
+        &state.user_repository,
+        &state.google_drive_service,
+        payload,
+    )
+    .await
+    {
+        Ok(revision) => JsonResponse::new_ok(revision),
+        Err(err @ usecases::keep_revision::Error::InvalidRequest(_)) => {
+            JsonResponse::new_bad_req_err(err.to_string())
+        }
+        Err(err @ usecases::keep_revision::Error::NotFound(_)) => {
+            JsonResponse::new_not_found_err(err.to_string())
+        }
+        Err(err @ usecases::keep_revision::Error::GoogleUnauthenticated) => {
+            JsonResponse::new_unauthorized_err(err.to_string())
+        }
+        Err(err) => JsonResponse::new_int_ser_err(err.to_string()),
+    }
+}
+
+/// Takes either the `revision_ids` to delete, or how many of the latest revisions to keep.
+#[derive(Deserialize)]
+pub struct DeleteRevisionsRequest {
+    file_id: String,
+    drive_id: Option<String>,
+    revision_ids: Option<Vec<String>>,
+    keep_latest: Option<usize>,
+}
+
+pub async fn handler_delete_revisions(
+    Extension(user_id): Extension<Uuid>,
+    State(state): State<AppState>,
+    Json(request): Json<DeleteRevisionsRequest>,
+) -> JsonResponse<Vec<String>> {
+    let user_id = if let Ok(user_id) = Id::try_from(user_id) {
+        user_id
+    } else {
+        return JsonResponse::new_int_ser_err("Internal Server Error".to_string());
+    };
+    let selection = match (request.revision_ids, request.keep_latest) {
+        (Some(revision_ids), None) => {
+            usecases::delete_revisions::RevisionSelection::Ids(revision_ids)
+        }
+        (None, Some(keep_latest)) => {
+            usecases::delete_revisions::RevisionSelection::OlderThanLatest(keep_latest)
+        }
+        _ => {
+            return JsonResponse::new_bad_req_err(
+                "Either revision_ids or keep_latest must be given".to_string(),
+            )
+        }
+    };
+    let payload = usecases::delete_revisions::Payload {
+        file_id: request.file_id,
+        drive_id: request.drive_id,
+        selection,
+        user_id,
+    };
+    match usecases::delete_revisions::execute(
+        &state.user_repository,
+        &state.google_drive_service,
+        payload,
+    )
+    .await
+    {
+        Ok(revision_ids) => JsonResponse::new_ok(revision_ids),
+        Err(err @ usecases::delete_revisions::Error::InvalidRequest(_)) => {
+            JsonResponse::new_bad_req_err(err.to_string())
+        }
+        Err(err @ usecases::delete_revisions::Error::NotFound(_)) => {
+            JsonResponse::new_not_found_err(err.to_string())
+        }
+        Err(err @ usecases::delete_revisions::Error::GoogleUnauthenticated) => {
+            JsonResponse::new_unauthorized_err(err.to_string())
+        }
+        Err(err) => JsonResponse::new_int_ser_err(err.to_string()),
+    }
+}
+
+#[derive(Serialize)]
+pub struct ShareLinkResponse {
+    #[serde(flatten)]
+    share_link: ShareLink,
+    /// Public route the file is downloaded from, relative to the server
+    url: String,
+}
+
+impl ShareLinkResponse {
+    fn new(share_link: ShareLink, secret: &[u8]) -> Self {
+        let url = format!("/api/public/shared-file?token={}", share_link.token(secret));
+        Self { share_link, url }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct CreateShareLinkRequest {
+    file_id: String,
+    /// Defaults to a day, capped at `SHARE_LINK_MAX_TTL_SECS`
+    expires_in_secs: Option<i64>,
+    max_downloads: Option<u32>,
+}
+
+/// Lifetime asked for a link, `default_secs` when the request leaves it out. `None` when it
+/// is not between one second and `max_secs`.
+fn requested_ttl(
+    expires_in_secs: Option<i64>,
+    default_secs: i64,
+    max_secs: i64,
+) -> Option<Duration> {
+    let ttl_secs = expires_in_secs.unwrap_or(default_secs.min(max_secs));
+    if ttl_secs <= 0 || ttl_secs > max_secs {
+        return None;
+    }
+    Duration::try_seconds(ttl_secs)
+}
+
+pub async fn handler_create_share_link(
+    Extension(user_id): Extension<Uuid>,
+    State(state): State<AppState>,
+    Json(request): Json<CreateShareLinkRequest>,
+) -> JsonResponse<ShareLinkResponse> {
+    let user_id = if let Ok(user_id) = Id::try_from(user_id) {
+        user_id
+    } else {
+        return JsonResponse::new_int_ser_err("Internal Server Error".to_string());
+    };
+    let max_ttl_secs = state.config.share_link_max_ttl_secs;
+    let ttl = match requested_ttl(request.expires_in_secs, 24 * 60 * 60, max_ttl_secs) {
+        Some(ttl) => ttl,
+        None => {
+            return JsonResponse::new_bad_req_err(format!(
+                "Share links must last between 1 and {} seconds",
+                max_ttl_secs
+            ))
+        }
+    };
+    let payload = usecases::create_share_link::Payload {
+        file_id: request.file_id,
+        user_id,
+        ttl,
+        max_downloads: request.max_downloads,
+    };
+    match usecases::create_share_link::execute(
+        &state.user_repository,
+        &state.share_link_repository,
+        &state.google_drive_service,
+        payload,
+    )
+    .await
+    {
+        Ok(share_link) => {
+            JsonResponse::new_ok(ShareLinkResponse::new(share_link, &state.config.secret))
+        }
+        Err(err @ usecases::create_share_link::Error::InvalidLink(_)) => {
+            JsonResponse::new_bad_req_err(err.to_string())
+        }
+        Err(err @ usecases::create_share_link::Error::NotFound(_)) => {
+            JsonResponse::new_not_found_err(err.to_string())
+        }
+        Err(err @ usecases::create_share_link::Error::GoogleUnauthenticated) => {
+            JsonResponse::new_unauthorized_err(err.to_string())
+        }
+        Err(err) => JsonResponse::new_int_ser_err(err.to_string()),
+    }
+}
+
+pub async fn handler_get_share_links(
+    Extension(user_id): Extension<Uuid>,
+    State(state): State<AppState>,
+) -> JsonResponse<Vec<ShareLinkResponse>> {
+    let user_id = if let Ok(user_id) = Id::try_from(user_id) {
+        user_id
+    } else {
+        return JsonResponse::new_int_ser_err("Internal Server Error".to_string());
+    };
+    let payload = usecases::list_share_links::Payload { user_id };
+    match usecases::list_share_links::execute(&state.share_link_repository, payload).await {
+        Ok(share_links) => JsonResponse::new_ok(
+            share_links
+                .into_iter()
+                .map(|x| ShareLinkResponse::new(x, &state.config.secret))
+                .collect(),
+        ),
+        Err(err) => JsonResponse::new_int_ser_err(err.to_string()),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct RevokeShareLinkRequest {
+    link_id: Uuid,
+}
+
+pub async fn handler_revoke_share_link(
+    Extension(user_id): Extension<Uuid>,
+    State(state): State<AppState>,
+    Json(request): Json<RevokeShareLinkRequest>,
+) -> JsonResponse<ShareLink> {
+    let user_id = if let Ok(user_id) = Id::try_from(user_id) {
+        user_id
+    } else {
+        return JsonResponse::new_int_ser_err("Internal Server Error".to_string());
+    };
+    let link_id = if let Ok(link_id) = Id::try_from(request.link_id) {
+        link_id
+    } else {
+        return JsonResponse::new_bad_req_err("Invalid link id".to_string());
+    };
+    let payload = usecases::revoke_share_link::Payload { link_id, user_id };
+    match usecases::revoke_share_link::execute(&state.share_link_repository, payload).await {
+        Ok(share_link) => JsonResponse::new_ok(share_link),
+        Err(err @ usecases::revoke_share_link::Error::NotFound(_)) => {
+            JsonResponse::new_not_found_err(err.to_string())
+        }
+        Err(err) => JsonResponse::new_int_ser_err(err.to_string()),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct SharedFileQuery {
+    token: String,
+}
+
+pub async fn handler_download_shared_file(
+    State(state): State<AppState>,
+    Query(params): Query<SharedFileQuery>,
+) -> Result<Response, (StatusCode, String)> {
+    let payload = usecases::download_shared_file::Payload {
+        token: params.token,
+        allowed_types: state.config.allowed_file_types.clone(),
+    };
+    match usecases::download_shared_file::execute(
+        &state.user_repository,
+        &state.share_link_repository,
+        &state.google_drive_service,
+        &state.config.secret,
+        payload,
+    )
+    .await
+    {
+        Ok(download) => download_response(download),
+        Err(err @ usecases::download_shared_file::Error::InvalidLink(_)) => {
+            Err((StatusCode::NOT_FOUND, err.to_string()))
+        }
+        Err(err @ usecases::download_shared_file::Error::Unavailable(_)) => {
+            Err((StatusCode::GONE, err.to_string()))
+        }
+        Err(usecases::download_shared_file::Error::Download(err)) => Err(download_error(err)),
+    }
+}
+
+#[derive(Serialize)]
+pub struct UploadRequestResponse {
+    #[serde(flatten)]
+    upload_request: UploadRequest,
+    /// Public route files are uploaded to, relative to the server
+    url: String,
+}
+
+impl UploadRequestResponse {
+    fn new(upload_request: UploadRequest, secret: &[u8]) -> Self {
+        let url = format!("/api/public/upload?token={}", upload_request.token(secret));
+        Self {
+            upload_request,
+            url,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct CreateUploadRequestRequest {
+    folder_id: String,
+    /// Defaults to a week, capped at `UPLOAD_REQUEST_MAX_TTL_SECS`
+    expires_in_secs: Option<i64>,
+    max_files: Option<u32>,
+    #[serde(default)]
+    allowed_types: Vec<String>,
+    max_size: Option<u64>,
+}
+
+pub async fn handler_create_upload_request(
+    Extension(user_id): Extension<Uuid>,
+    State(state): State<AppState>,
+    Json(request): Json<CreateUploadRequestRequest>,
+) -> JsonResponse<UploadRequestResponse> {
+    let user_id = if let Ok(user_id) = Id::try_from(user_id) {
+        user_id
+    } else {
+        return JsonResponse::new_int_ser_err("Internal Server Error".to_string());
+    };
+    let max_ttl_secs = state.config.upload_request_max_ttl_secs;
+    let ttl = match requested_ttl(request.expires_in_secs, 7 * 24 * 60 * 60, max_ttl_secs) {
+        Some(ttl) => ttl,
+        None => {
+            return JsonResponse::new_bad_req_err(format!(
+                "Upload requests must last between 1 and {} seconds",
+                max_ttl_secs
+            ))
+        }
+    };
+    let payload = usecases::create_upload_request::Payload {
+        folder_id: request.folder_id,
+        user_id,
+        ttl,
+        max_files: request.max_files,
+        allowed_types: request.allowed_types,
+        max_size: request.max_size,
+        server_types: state.config.allowed_file_types.clone(),
+    };
+    match usecases::create_upload_request::execute(
+        &state.user_repository,
+        &state.upload_request_repository,
+        &state.google_drive_service,
+        payload,
+    )
+    .await
+    {
+        Ok(upload_request) => JsonResponse::new_ok(UploadRequestResponse::new(
+            upload_request,
+            &state.config.secret,
+        )),
+        Err(err @ usecases::create_upload_request::Error::InvalidRequest(_)) => {
+            JsonResponse::new_bad_req_err(err.to_string())
+        }
+        Err(err @ usecases::create_upload_request::Error::NotFound(_)) => {
+            JsonResponse::new_not_found_err(err.to_string())
+        }
+        Err(err @ usecases::create_upload_request::Error::GoogleUnauthenticated) => {
+            JsonResponse::new_unauthorized_err(err.to_string())
+        }
+        Err(err) => JsonResponse::new_int_ser_err(err.to_string()),
+    }
+}
+
+pub async fn handler_get_upload_requests(
+    Extension(user_id): Extension<Uuid>,
+    State(state): State<AppState>,
+) -> JsonResponse<Vec<UploadRequestResponse>> {
+    let user_id = if let Ok(user_id) = Id::try_from(user_id) {
+        user_id
+    } else {
+        return JsonResponse::new_int_ser_err("Internal Server Error".to_string());
+    };
+    let payload = usecases::list_upload_requests::Payload { user_id };
+    match usecases::list_upload_requests::execute(&state.upload_request_repository, payload).await {
+        Ok(upload_requests) => JsonResponse::new_ok(
+            upload_requests
+                .into_iter()
+                .map(|x| UploadRequestResponse::new(x, &state.config.secret))
+                .collect(),
+        ),
+        Err(err) => JsonResponse::new_int_ser_err(err.to_string()),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct UploadToRequestQuery {
+    token: String,
+}
+
+/// Takes the optional `uploader_name` and `uploader_email` fields followed by one file.
+pub async fn handler_upload_to_request(
+    State(state): State<AppState>,
+    Query(params): Query<UploadToRequestQuery>,
+    mut multipart: Multipart,
+) -> JsonResponse<UploadRecord> {
+    let mut uploader_name = None;
+    let mut uploader_email = None;
+    loop {
+        let field = match multipart.next_field().await {
+            Ok(Some(field)) => field,
+            Ok(None) => return JsonResponse::new_bad_req_err("No file uploaded".to_string()),
+            Err(err) => {
+                return JsonResponse::new_bad_req_err(format!("Error processing file: {}", err))
+            }
+        };
+
+        let file_name = match field.file_name() {
+            Some(file_name) => file_name.to_string(),
+            None => {
+                let name = field.name().unwrap_or_default().to_string();
+                let value = match field.text().await {
+                    Ok(value) => value.trim().to_string(),
+                    Err(err) => return JsonResponse::new_bad_req_err(err.to_string()),
+                };
+                match name.as_str() {
+                    "uploader_name" => uploader_name = Some(value),
+                    "uploader_email" => match Email::new(value.clone()) {
+                        Ok(_) => uploader_email = Some(value),
+                        Err(err) => return JsonResponse::new_bad_req_err(err),
+                    },
+                    _ => {}
+                }
+                continue;
+            }
+        };
+
+        // The field is forwarded to Drive as it arrives, without staging it on disk
+        let content = field.map_err(|err| {
+            google_drive_service::Error::Unknown(format!("Error while reading file: {}", err))
+        });
+        let payload = usecases::upload_to_request::Payload {
+            token: params.token,
+            file_name,
+            content: Box::pin(content),
+            uploader_name,
+            uploader_email,
+            allowed_types: state.config.allowed_file_types.clone(),
+        };
+
+        return match usecases::upload_to_request::execute(
+            &state.user_repository,
+            &state.upload_request_repository,
+            &state.google_drive_service,
+            &state.config.secret,
+            payload,
+        )
+        .await
+        {
+            Ok(upload) => JsonResponse::new_ok(upload),
+            Err(err @ usecases::upload_to_request::Error::InvalidLink(_)) => {
+                JsonResponse::new_not_found_err(err.to_string())
+            }
+            Err(err @ usecases::upload_to_request::Error::Unavailable(_)) => {
+                JsonResponse::new_err(StatusCode::GONE, "Gone", err.to_string())
+            }
+            // The owner's Google session and folder are not the uploader's concern
+            Err(usecases::upload_to_request::Error::Upload(
+                err @ (usecases::upload_file::Error::NotFound(_)
+                | usecases::upload_file::Error::GoogleUnauthenticated),
+            )) => JsonResponse::new_int_ser_err(err.to_string()),
+            Err(usecases::upload_to_request::Error::Upload(err)) => upload_error(err),
+        };
+    }
+}
+
+/// A new webhook, with the secret its deliveries are signed with. The secret is not
+/// shown again.
+#[derive(Serialize)]
+pub struct CreatedWebhookResponse {
+    #[serde(flatten)]
+    webhook: Webhook,
+    secret: String,
+}
+
+#[derive(Deserialize)]
+pub struct CreateWebhookRequest {
+    url: String,
+    #[serde(default)]
+    folder_ids: Vec<String>,
+}
+
+pub async fn handler_create_webhook(
+    Extension(user_id): Extension<Uuid>,
+    State(state): State<AppState>,
+    Json(request): Json<CreateWebhookRequest>,
+) -> JsonResponse<CreatedWebhookResponse> {
+    let user_id = if let Ok(user_id) = Id::try_from(user_id) {
+        user_id
+    } else {
+        return JsonResponse::new_int_ser_err("Internal Server Error".to_string());
+    };
+    let payload = usecases::create_webhook::Payload {
+        url: request.url,
+        folder_ids: request.folder_ids,
+        user_id,
+    };
+    match usecases::create_webhook::execute(
+        &state.user_repository,
+        &state.webhook_repository,
+        &state.change_cursor_repository,
+        &state.google_drive_service,
+        &state.webhook_sender,
+        payload,
+    )
+    .await
+    {
+        Ok(webhook) => JsonResponse::new_ok(CreatedWebhookResponse {
+            secret: webhook.secret.clone(),
+            webhook,
+        }),
+        Err(err @ usecases::create_webhook::Error::InvalidWebhook(_)) => {
+            JsonResponse::new_bad_req_err(err.to_string())
+        }
+        Err(err @ usecases::create_webhook::Error::NotAFolder(_)) => {
+            JsonResponse::new_bad_req_err(err.to_string())
+        }
+        Err(err @ usecases::create_webhook::Error::NotFound(_)) => {
+            JsonResponse::new_not_found_err(err.to_string())
+        }
+        Err(err @ usecases::create_webhook::Error::GoogleUnauthenticated) => {
+            JsonResponse::new_unauthorized_err(err.to_string())
+        }
+        Err(err) => JsonResponse::new_int_ser_err(err.to_string()),
+    }
+}
+
+pub async fn handler_get_webhooks(
+    Extension(user_id): Extension<Uuid>,
+    State(state): State<AppState>,
+) -> JsonResponse<Vec<Webhook>> {
+    let user_id = if let Ok(user_id) = Id::try_from(user_id) {
+        user_id
+    } else {
+        return JsonResponse::new_int_ser_err("Internal Server Error".to_string());
+    };
+    let payload = usecases::list_webhooks::Payload { user_id };
+    match usecases::list_webhooks::execute(&state.webhook_repository, payload).await {
+        Ok(webhooks) => JsonResponse::new_ok(webhooks),
+        Err(err) => JsonResponse::new_int_ser_err(err.to_string()),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct DeleteWebhookRequest {
+    webhook_id: Uuid,
+}
+
+pub async fn handler_delete_webhook(
+    Extension(user_id): Extension<Uuid>,
+    State(state): State<AppState>,
+    Json(request): Json<DeleteWebhookRequest>,
+) -> JsonResponse<Webhook> {
+    let user_id = if let Ok(user_id) = Id::try_from(user_id) {
+        user_id
+    } else {
+        return JsonResponse::new_int_ser_err("Internal Server Error".to_string());
+    };
+    let webhook_id = if let Ok(webhook_id) = Id::try_from(request.webhook_id) {
+        webhook_id
+    } else {
+        return JsonResponse::new_bad_req_err("Invalid webhook id".to_string());
+    };
+    let payload = usecases::delete_webhook::Payload {
+        webhook_id,
+        user_id,
+    };
+    match usecases::delete_webhook::execute(
+        &state.user_repository,
+        &state.webhook_repository,
+        &state.change_cursor_repository,
+        &state.file_parents_repository,
+        &state.google_drive_service,
+        payload,
+    )
+    .await
+    {
+        Ok(webhook) => JsonResponse::new_ok(webhook),
+        Err(err @ usecases::delete_webhook::Error::NotFound(_)) => {
+            JsonResponse::new_not_found_err(err.to_string())
+        }
+        Err(err) => JsonResponse::new_int_ser_err(err.to_string()),
+    }
+}
+
+/// Receives Drive push notifications. The changes are read and sent to the webhooks after
+/// answering, Drive only waits a few seconds.
+pub async fn handler_drive_notification(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> StatusCode {
+    let header_str = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|x| x.to_str().ok())
+            .unwrap_or_default()
+            .to_string()
+    };
+    // Sent once when the channel is opened, there is nothing to read yet
+    if header_str("X-Goog-Resource-State") == "sync" {
+        return StatusCode::OK;
+    }
+
+    let payload = usecases::verify_change_notification::Payload {
+        channel_id: header_str("X-Goog-Channel-ID"),
+        resource_id: header_str("X-Goog-Resource-ID"),
+    };
+    match usecases::verify_change_notification::execute(&state.change_cursor_repository, payload)
+        .await
+    {
+        Ok(user_id) => {
+            tokio::spawn(async move { changes_poller::sync(&state, user_id).await });
+            StatusCode::OK
+        }
+        // Drive stops sending to a channel that answers 404
+        Err(usecases::verify_change_notification::Error::NotFound(_)) => StatusCode::NOT_FOUND,
+        Err(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+pub async fn handler_get_drives(
+    Extension(user_id): Extension<Uuid>,
+    State(state): State<AppState>,
+) -> JsonResponse<Vec<SharedDrive>> {
+    let user_id = if let Ok(user_id) = Id::try_from(user_id) {
+        user_id
+    } else {
+        return JsonResponse::new_int_ser_err("Internal Server Error".to_string());
+    };
+    let payload = usecases::list_drives::Payload { user_id };
+    match usecases::list_drives::execute(
+        &state.user_repository,
+        &state.google_drive_service,
+        payload,
+    )
+    .await
+    {
+        Ok(drives) => JsonResponse::new_ok(drives),
+        Err(err @ usecases::list_drives::Error::NotFound(_)) => {
+            JsonResponse::new_not_found_err(err.to_string())
+        }
+        Err(err @ usecases::list_drives::Error::GoogleUnauthenticated) => {
+            JsonResponse::new_unauthorized_err(err.to_string())
+        }
+        Err(err) => JsonResponse::new_int_ser_err(err.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_requested_ttl() {
+        let day = 24 * 60 * 60;
+        assert_eq!(
+            requested_ttl(None, day, 7 * day),
+            Some(Duration::seconds(day))
+        );
+        assert_eq!(requested_ttl(None, day, 60), Some(Duration::seconds(60)));
+        assert_eq!(
+            requested_ttl(Some(60), day, 7 * day),
+            Some(Duration::seconds(60))
+        );
+        assert_eq!(requested_ttl(Some(7 * day + 1), day, 7 * day), None);
+        assert_eq!(requested_ttl(Some(0), day, 7 * day), None);
+        assert_eq!(requested_ttl(Some(-60), day, 7 * day), None);
+        assert_eq!(requested_ttl(Some(i64::MIN), day, 7 * day), None);
+        assert_eq!(requested_ttl(Some(i64::MAX), day, i64::MAX), None);
+
+        // Upload requests default to a week, capped at 30 days
+        assert_eq!(
+            requested_ttl(None, 7 * day, 30 * day),
+            Some(Duration::seconds(7 * day))
+        );
+        assert_eq!(requested_ttl(Some(-1), 7 * day, 30 * day), None);
+        assert_eq!(requested_ttl(Some(i64::MIN / 2), 7 * day, 30 * day), None);
+    }
 }
@@ -1,5 +1,5 @@
 use axum::body::{to_bytes, Body, Bytes};
-use axum::http::{Request, Response};
+use axum::http::{header, HeaderMap, Request, Response};
 use axum::middleware::Next;
 use axum::Error;
 use colored::*;
@@ -8,14 +8,40 @@ use log::info;
 pub async fn log_request_response(req: Request<Body>, next: Next) -> Response<Body> {
     let path = &req.uri().to_string();
 
-    let (req_parts, req_body) = req.into_parts();
+    // Uploads are streamed to Drive, so only textual requests are buffered and printed
+    let req = if is_printable(req.headers()) {
+        let (req_parts, req_body) = req.into_parts();
 
-    // Print request
-    let bytes = buffer_and_print("request", path, req_body).await.unwrap();
-    let req: Request<Body> = Request::from_parts(req_parts, Body::from(bytes));
+        // Print request
+        let bytes = buffer_and_print("request", path, req_body).await.unwrap();
+        Request::from_parts(req_parts, Body::from(bytes))
+    } else {
+        info!(
+            "{}",
+            format!(
+                "{} -> path: {} body: <stream>",
+                "request".green(),
+                path.blue()
+            )
+        );
+        req
+    };
 
     let res = next.run(req).await;
 
+    // File downloads are streamed as well
+    if !is_printable(res.headers()) {
+        info!(
+            "{}",
+            format!(
+                "{} -> path: {} body: <stream>",
+                "response".red(),
+                path.blue()
+            )
+        );
+        return res;
+    }
+
     let (mut res_parts, res_body) = res.into_parts();
 
     // Print response
@@ -24,9 +50,19 @@ pub async fn log_request_response(req: Request<Body>, next: Next) -> Response<Bo
     // When your encoding is chunked there can be problems without removing the header
     res_parts.headers.remove("transfer-encoding");
 
-    let res = Response::from_parts(res_parts, Body::from(bytes));
+    Response::from_parts(res_parts, Body::from(bytes))
+}
 
-    res
+fn is_printable(headers: &HeaderMap) -> bool {
+    match headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|x| x.to_str().ok())
+    {
+        Some(content_type) => {
+            content_type.starts_with("application/json") || content_type.starts_with("text/")
+        }
+        None => true,
+    }
 }
 
 // Consumes body and prints
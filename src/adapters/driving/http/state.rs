@@ -1,12 +1,33 @@
+use std::time::Duration;
+
 use crate::adapters::{
     config::Config,
-    driven::{google_drive_service::GoogleDriveService, user_repository::UserRepository},
+    driven::{
+        auth_request_repository::AuthRequestRepository,
+        change_cursor_repository::ChangeCursorRepository,
+        file_parents_repository::FileParentsRepository, google_drive_service::GoogleDriveService,
+        pdf_editor::PdfEditor, pdf_reader::PdfReader, pdf_renderer::PdfRenderer,
+        share_link_repository::ShareLinkRepository, thumbnail_repository::ThumbnailRepository,
+        upload_request_repository::UploadRequestRepository, user_repository::UserRepository,
+        webhook_repository::WebhookRepository, webhook_sender::WebhookSender,
+    },
 };
 
 #[derive(Clone)]
 pub struct AppState {
     pub user_repository: UserRepository,
+    pub auth_request_repository: AuthRequestRepository,
+    pub share_link_repository: ShareLinkRepository,
+    pub upload_request_repository: UploadRequestRepository,
+    pub webhook_repository: WebhookRepository,
+    pub change_cursor_repository: ChangeCursorRepository,
+    pub file_parents_repository: FileParentsRepository,
+    pub thumbnail_repository: ThumbnailRepository,
     pub google_drive_service: GoogleDriveService,
+    pub webhook_sender: WebhookSender,
+    pub pdf_renderer: PdfRenderer,
+    pub pdf_editor: PdfEditor,
+    pub pdf_reader: PdfReader,
     pub config: Config,
 }
 
@@ -14,10 +35,43 @@ impl AppState {
     pub async fn new() -> AppState {
         let config = Config::new();
         AppState {
-            user_repository: UserRepository::new(
+            user_repository: UserRepository::new(&config.db_url, &config.db_name, "users").await,
+            auth_request_repository: AuthRequestRepository::new(
+                &config.db_url,
+                &config.db_name,
+                "auth_requests",
+            )
+            .await,
+            share_link_repository: ShareLinkRepository::new(
+                &config.db_url,
+                &config.db_name,
+                "share_links",
+            )
+            .await,
+            upload_request_repository: UploadRequestRepository::new(
+                &config.db_url,
+                &config.db_name,
+                "upload_requests",
+            )
+            .await,
+            webhook_repository: WebhookRepository::new(&config.db_url, &config.db_name, "webhooks")
+                .await,
+            change_cursor_repository: ChangeCursorRepository::new(
+                &config.db_url,
+                &config.db_name,
+                "change_cursors",
+            )
+            .await,
+            file_parents_repository: FileParentsRepository::new(
+                &config.db_url,
+                &config.db_name,
+                "file_parents",
+            )
+            .await,
+            thumbnail_repository: ThumbnailRepository::new(
                 &config.db_url,
                 &config.db_name,
-                &"users".to_string(),
+                "thumbnails",
             )
             .await,
             google_drive_service: GoogleDriveService::new(
@@ -26,8 +80,26 @@ impl AppState {
                 config.google_auth_url.clone(),
                 config.google_token_url.clone(),
                 config.google_redirect_url.clone(),
+                config.upload_chunk_size,
             )
             .await,
+            webhook_sender: WebhookSender::new(
+                config.webhook_max_attempts,
+                Duration::from_millis(config.webhook_retry_delay_ms),
+            ),
+            pdf_renderer: PdfRenderer::new(
+                config.pdf_renderer_path.clone(),
+                Duration::from_secs(config.thumbnail_render_timeout_secs),
+            ),
+            pdf_editor: PdfEditor::new(
+                config.pdf_editor_path.clone(),
+                Duration::from_secs(config.pdf_edit_timeout_secs),
+            ),
+            pdf_reader: PdfReader::new(
+                config.pdfinfo_path.clone(),
+                config.pdftotext_path.clone(),
+                Duration::from_secs(config.pdf_edit_timeout_secs),
+            ),
             config,
         }
     }
@@ -0,0 +1,150 @@
+use chrono::{DateTime, Utc};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+
+use crate::domain::value_objects::byte_range::RangeSpec;
+
+/// Characters allowed unescaped in an RFC 5987 `ext-value` (`attr-char`).
+const ATTR_CHAR: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'!')
+    .remove(b'#')
+    .remove(b'$')
+    .remove(b'&')
+    .remove(b'+')
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'^')
+    .remove(b'_')
+    .remove(b'`')
+    .remove(b'|')
+    .remove(b'~');
+
+/// Builds a `Content-Disposition` value carrying the file name twice: a plain ASCII
+/// fallback for old clients and the exact UTF-8 name in `filename*`.
+pub fn content_disposition(disposition: &str, file_name: &str) -> String {
+    let fallback: String = file_name
+        .chars()
+        .map(|c| {
+            if c.is_ascii() && !c.is_ascii_control() && c != '"' && c != '\\' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+
+    format!(
+        "{}; filename=\"{}\"; filename*=UTF-8''{}",
+        disposition,
+        fallback,
+        utf8_percent_encode(file_name, ATTR_CHAR)
+    )
+}
+
+/// Parses a `Range` header. `None` means the header is malformed or not in bytes and must
+/// be ignored, in which case the whole file is sent.
+pub fn parse_range(value: &str) -> Option<Vec<RangeSpec>> {
+    let (unit, ranges) = value.split_once('=')?;
+    if unit.trim() != "bytes" {
+        return None;
+    }
+
+    ranges
+        .split(',')
+        .map(|range| {
+            let (start, end) = range.trim().split_once('-')?;
+            match (start.is_empty(), end.is_empty()) {
+                (false, false) => {
+                    let (start, end) = (start.parse().ok()?, end.parse().ok()?);
+                    (start <= end).then_some(RangeSpec::Bounded(start, end))
+                }
+                (false, true) => start.parse().ok().map(RangeSpec::From),
+                (true, false) => end.parse().ok().map(RangeSpec::Suffix),
+                (true, true) => None,
+            }
+        })
+        .collect()
+}
+
+/// Parses an `If-None-Match` header into bare entity tags. Weak tags are compared like
+/// strong ones, as allowed for `If-None-Match`.
+pub fn parse_if_none_match(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|tag| {
+            let tag = tag.trim();
+            let tag = tag.strip_prefix("W/").unwrap_or(tag);
+            tag.trim_matches('"').to_string()
+        })
+        .filter(|tag| !tag.is_empty())
+        .collect()
+}
+
+pub fn entity_tag(tag: &str) -> String {
+    format!("\"{}\"", tag)
+}
+
+pub fn http_date(date: DateTime<Utc>) -> String {
+    date.format("%a, %d %b %Y %H:%M:%S GMT").to_string()
+}
+
+pub fn parse_http_date(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc2822(value)
+        .ok()
+        .map(|x| x.with_timezone(&Utc))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    #[test]
+    fn test_content_disposition_ascii() {
+        assert_eq!(
+            content_disposition("inline", "contract.pdf"),
+            "inline; filename=\"contract.pdf\"; filename*=UTF-8''contract.pdf"
+        );
+    }
+
+    #[test]
+    fn test_content_disposition_escapes_name() {
+        assert_eq!(
+            content_disposition("attachment", "año \"final\".pdf"),
+            "attachment; filename=\"a_o _final_.pdf\"; filename*=UTF-8''a%C3%B1o%20%22final%22.pdf"
+        );
+    }
+
+    #[test]
+    fn test_parse_range() {
+        assert_eq!(
+            parse_range("bytes=0-99, 200-, -50"),
+            Some(vec![
+                RangeSpec::Bounded(0, 99),
+                RangeSpec::From(200),
+                RangeSpec::Suffix(50)
+            ])
+        );
+        assert_eq!(parse_range("bytes=10-5"), None);
+        assert_eq!(parse_range("bytes=-"), None);
+        assert_eq!(parse_range("items=0-5"), None);
+        assert_eq!(parse_range("bytes=a-5"), None);
+    }
+
+    #[test]
+    fn test_parse_if_none_match() {
+        assert_eq!(
+            parse_if_none_match("\"abc\", W/\"def\""),
+            vec!["abc".to_string(), "def".to_string()]
+        );
+        assert_eq!(parse_if_none_match("*"), vec!["*".to_string()]);
+    }
+
+    #[test]
+    fn test_http_date() {
+        let date = Utc.with_ymd_and_hms(1994, 11, 6, 8, 49, 37).unwrap();
+        assert_eq!(http_date(date), "Sun, 06 Nov 1994 08:49:37 GMT");
+        assert_eq!(parse_http_date("Sun, 06 Nov 1994 08:49:37 GMT"), Some(date));
+        assert_eq!(parse_http_date("yesterday"), None);
+    }
+}
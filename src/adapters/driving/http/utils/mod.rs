@@ -1 +1,3 @@
-pub mod responses;
\ No newline at end of file
+pub mod headers;
+pub mod responses;
+pub mod zip;
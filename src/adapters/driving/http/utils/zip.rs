@@ -0,0 +1,330 @@
+use bytes::{BufMut, Bytes, BytesMut};
+use chrono::{DateTime, Datelike, Timelike, Utc};
+
+const LOCAL_HEADER_SIGNATURE: u32 = 0x04034b50;
+const DATA_DESCRIPTOR_SIGNATURE: u32 = 0x08074b50;
+const CENTRAL_HEADER_SIGNATURE: u32 = 0x02014b50;
+const ZIP64_END_SIGNATURE: u32 = 0x06064b50;
+const ZIP64_LOCATOR_SIGNATURE: u32 = 0x07064b50;
+const END_SIGNATURE: u32 = 0x06054b50;
+
+/// Sizes and crc come after the content, and names are UTF-8
+const FLAGS: u16 = 0x0008 | 0x0800;
+const VERSION: u16 = 20;
+const VERSION_ZIP64: u16 = 45;
+/// Made by a Unix system, so the permissions in the external attributes apply
+const MADE_BY_UNIX: u16 = 3 << 8;
+
+/// Writes a ZIP archive as its entries go by, without knowing their content up front.
+///
+/// Entries are stored uncompressed: most of what is kept in Drive (PDF, images, office
+/// files) is compressed already. Each entry is `start_entry`, its content as is, then
+/// `finish_entry` with the crc and size of that content; `finish` closes the archive.
+/// ZIP64 is only used for the entries and offsets that need it.
+#[derive(Default)]
+pub struct ZipWriter {
+    offset: u64,
+    entries: Vec<CentralEntry>,
+}
+
+struct CentralEntry {
+    name: String,
+    directory: bool,
+    modified: (u16, u16),
+    zip64: bool,
+    offset: u64,
+    crc32: u32,
+    size: u64,
+}
+
+impl ZipWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Local header of the next entry. Folder names end with `/` and have no content.
+    /// `size_hint` is the expected content size, entries of 4 GiB or more need ZIP64.
+    pub fn start_entry(
+        &mut self,
+        name: &str,
+        modified: Option<DateTime<Utc>>,
+        size_hint: Option<u64>,
+    ) -> Bytes {
+        let zip64 = size_hint.is_some_and(|x| x >= u32::MAX as u64);
+        let entry = CentralEntry {
+            name: name.to_string(),
+            directory: name.ends_with('/'),
+            modified: dos_date_time(modified.unwrap_or_else(Utc::now)),
+            zip64,
+            offset: self.offset,
+            crc32: 0,
+            size: 0,
+        };
+
+        let mut header = BytesMut::new();
+        header.put_u32_le(LOCAL_HEADER_SIGNATURE);
+        header.put_u16_le(if zip64 { VERSION_ZIP64 } else { VERSION });
+        header.put_u16_le(FLAGS);
+        // Stored
+        header.put_u16_le(0);
+        header.put_u16_le(entry.modified.0);
+        header.put_u16_le(entry.modified.1);
+        header.put_u32_le(0);
+        let size = if zip64 { u32::MAX } else { 0 };
+        header.put_u32_le(size);
+        header.put_u32_le(size);
+        header.put_u16_le(name.len() as u16);
+        header.put_u16_le(if zip64 { 20 } else { 0 });
+        header.put_slice(name.as_bytes());
+        if zip64 {
+            header.put_u16_le(0x0001);
+            header.put_u16_le(16);
+            header.put_u64_le(0);
+            header.put_u64_le(0);
+        }
+
+        self.entries.push(entry);
+        self.offset += header.len() as u64;
+        header.freeze()
+    }
+
+    /// Data descriptor closing the entry started last. Fails when the entry reached 4 GiB
+    /// without having been started as ZIP64, since its size can't be written then.
+    pub fn finish_entry(&mut self, crc32: u32, size: u64) -> Result<Bytes, String> {
+        let entry = self
+            .entries
+            .last_mut()
+            .expect("finish_entry called before start_entry");
+        if !entry.zip64 && size >= u32::MAX as u64 {
+            return Err(format!(
+                "{} is larger than expected, it needs ZIP64",
+                entry.name
+            ));
+        }
+        entry.crc32 = crc32;
+        entry.size = size;
+
+        let mut descriptor = BytesMut::new();
+        descriptor.put_u32_le(DATA_DESCRIPTOR_SIGNATURE);
+        descriptor.put_u32_le(crc32);
+        if entry.zip64 {
+            descriptor.put_u64_le(size);
+            descriptor.put_u64_le(size);
+        } else {
+            descriptor.put_u32_le(size as u32);
+            descriptor.put_u32_le(size as u32);
+        }
+
+        self.offset += size + descriptor.len() as u64;
+        Ok(descriptor.freeze())
+    }
+
+    /// Central directory and end records, the last bytes of the archive.
+    pub fn finish(self) -> Bytes {
+        let mut directory = BytesMut::new();
+        for entry in &self.entries {
+            let size_overflows = entry.zip64 || entry.size >= u32::MAX as u64;
+            let offset_overflows = entry.offset >= u32::MAX as u64;
+            let mut extra = BytesMut::new();
+            if size_overflows {
+                extra.put_u64_le(entry.size);
+                extra.put_u64_le(entry.size);
+            }
+            if offset_overflows {
+                extra.put_u64_le(entry.offset);
+            }
+            let zip64 = !extra.is_empty();
+
+            directory.put_u32_le(CENTRAL_HEADER_SIGNATURE);
+            directory.put_u16_le(MADE_BY_UNIX | VERSION_ZIP64);
+            directory.put_u16_le(if zip64 { VERSION_ZIP64 } else { VERSION });
+            directory.put_u16_le(FLAGS);
+            directory.put_u16_le(0);
+            directory.put_u16_le(entry.modified.0);
+            directory.put_u16_le(entry.modified.1);
+            directory.put_u32_le(entry.crc32);
+            let size = if size_overflows {
+                u32::MAX
+            } else {
+                entry.size as u32
+            };
+            directory.put_u32_le(size);
+            directory.put_u32_le(size);
+            directory.put_u16_le(entry.name.len() as u16);
+            directory.put_u16_le(if zip64 { extra.len() as u16 + 4 } else { 0 });
+            // Comment, disk number and internal attributes
+            directory.put_u16_le(0);
+            directory.put_u16_le(0);
+            directory.put_u16_le(0);
+            let mode: u32 = if entry.directory { 0o040755 } else { 0o100644 };
+            directory.put_u32_le(mode << 16 | if entry.directory { 0x10 } else { 0 });
+            directory.put_u32_le(if offset_overflows {
+                u32::MAX
+            } else {
+                entry.offset as u32
+            });
+            directory.put_slice(entry.name.as_bytes());
+            if zip64 {
+                directory.put_u16_le(0x0001);
+                directory.put_u16_le(extra.len() as u16);
+                directory.put_slice(&extra);
+            }
+        }
+
+        let count = self.entries.len() as u64;
+        let directory_offset = self.offset;
+        let directory_size = directory.len() as u64;
+        let mut end = directory;
+        if count >= u16::MAX as u64
+            || directory_offset >= u32::MAX as u64
+            || directory_size >= u32::MAX as u64
+        {
+            let zip64_end_offset = directory_offset + directory_size;
+            end.put_u32_le(ZIP64_END_SIGNATURE);
+            // Size of the rest of the record
+            end.put_u64_le(44);
+            end.put_u16_le(MADE_BY_UNIX | VERSION_ZIP64);
+            end.put_u16_le(VERSION_ZIP64);
+            end.put_u32_le(0);
+            end.put_u32_le(0);
+            end.put_u64_le(count);
+            end.put_u64_le(count);
+            end.put_u64_le(directory_size);
+            end.put_u64_le(directory_offset);
+
+            end.put_u32_le(ZIP64_LOCATOR_SIGNATURE);
+            end.put_u32_le(0);
+            end.put_u64_le(zip64_end_offset);
+            end.put_u32_le(1);
+        }
+
+        end.put_u32_le(END_SIGNATURE);
+        end.put_u16_le(0);
+        end.put_u16_le(0);
+        end.put_u16_le(count.min(u16::MAX as u64) as u16);
+        end.put_u16_le(count.min(u16::MAX as u64) as u16);
+        end.put_u32_le(directory_size.min(u32::MAX as u64) as u32);
+        end.put_u32_le(directory_offset.min(u32::MAX as u64) as u32);
+        end.put_u16_le(0);
+        end.freeze()
+    }
+}
+
+/// CRC-32 of the content of an entry, fed as it streams.
+#[derive(Default)]
+pub struct Crc32(u32);
+
+const CRC32_TABLE: [u32; 256] = crc32_table();
+
+const fn crc32_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                0xEDB88320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+impl Crc32 {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        let mut crc = !self.0;
+        for byte in data {
+            crc = CRC32_TABLE[((crc ^ *byte as u32) & 0xFF) as usize] ^ (crc >> 8);
+        }
+        self.0 = !crc;
+    }
+
+    pub fn value(&self) -> u32 {
+        self.0
+    }
+}
+
+/// MS-DOS time and date, which can't go before 1980.
+fn dos_date_time(date: DateTime<Utc>) -> (u16, u16) {
+    if date.year() < 1980 {
+        return (0, 1 << 5 | 1);
+    }
+    let time = (date.hour() << 11 | date.minute() << 5 | (date.second() / 2)) as u16;
+    let day = ((date.year() as u32 - 1980).min(127) << 9 | date.month() << 5 | date.day()) as u16;
+    (time, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crc32() {
+        let mut crc = Crc32::new();
+        crc.update(b"1234");
+        crc.update(b"56789");
+        assert_eq!(crc.value(), 0xCBF43926);
+        assert_eq!(Crc32::new().value(), 0);
+    }
+
+    #[test]
+    fn test_zip_layout() {
+        let content = b"%PDF-1.7";
+        let mut crc = Crc32::new();
+        crc.update(content);
+
+        let mut zip = ZipWriter::new();
+        let mut archive = Vec::new();
+        archive.extend_from_slice(&zip.start_entry("client/", None, None));
+        archive.extend_from_slice(&zip.finish_entry(0, 0).unwrap());
+        archive.extend_from_slice(&zip.start_entry("client/contract.pdf", None, Some(8)));
+        archive.extend_from_slice(content);
+        archive.extend_from_slice(&zip.finish_entry(crc.value(), content.len() as u64).unwrap());
+        let directory_offset = archive.len() as u32;
+        archive.extend_from_slice(&zip.finish());
+
+        let u16_at = |i: usize| u16::from_le_bytes([archive[i], archive[i + 1]]);
+        let u32_at = |i: usize| u32::from_le_bytes(archive[i..i + 4].try_into().unwrap());
+        assert_eq!(u32_at(0), LOCAL_HEADER_SIGNATURE);
+        assert_eq!(u32_at(directory_offset as usize), CENTRAL_HEADER_SIGNATURE);
+
+        let end = archive.len() - 22;
+        assert_eq!(u32_at(end), END_SIGNATURE);
+        assert_eq!(u16_at(end + 10), 2);
+        assert_eq!(u32_at(end + 16), directory_offset);
+        assert_eq!(u32_at(end + 12) as usize, end - directory_offset as usize);
+    }
+
+    #[test]
+    fn test_entry_over_size_hint() {
+        let mut zip = ZipWriter::new();
+        zip.start_entry("export.pdf", None, None);
+        assert!(zip.finish_entry(0, u32::MAX as u64).is_err());
+
+        zip.start_entry("video.mp4", None, Some(5 << 30));
+        let descriptor = zip.finish_entry(0, 5 << 30).unwrap();
+        assert_eq!(descriptor.len(), 24);
+    }
+
+    #[test]
+    fn test_dos_date_time() {
+        let date = DateTime::parse_from_rfc3339("2024-06-01T12:30:45Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        assert_eq!(
+            dos_date_time(date),
+            (12 << 11 | 30 << 5 | 22, 44 << 9 | 6 << 5 | 1)
+        );
+        assert_eq!(dos_date_time(DateTime::UNIX_EPOCH), (0, 33));
+    }
+}
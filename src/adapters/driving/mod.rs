@@ -1 +1,2 @@
+pub mod changes_poller;
 pub mod http;
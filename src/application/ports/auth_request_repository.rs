@@ -0,0 +1,25 @@
+use crate::domain::entities::auth_request::AuthRequest;
+
+#[derive(Debug)]
+pub enum Error {
+    NotFound,
+    AlreadyUsed,
+    Unknown(String),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::NotFound => write!(f, "Not Found"),
+            Error::AlreadyUsed => write!(f, "Already Used"),
+            Error::Unknown(msg) => write!(f, "Unknown Error: {}", msg),
+        }
+    }
+}
+
+pub trait AuthRequestRepositoryTrait {
+    async fn create(&self, auth_request: AuthRequest) -> Result<AuthRequest, Error>;
+    /// Marks the request with the given state as used and returns it. A state can only
+    /// be consumed once, later calls fail with `AlreadyUsed`.
+    async fn consume(&self, state: &str) -> Result<AuthRequest, Error>;
+}
@@ -0,0 +1,36 @@
+use crate::domain::{entities::change_cursor::ChangeCursor, value_objects::id::Id};
+
+#[derive(Debug)]
+pub enum Error {
+    NotFound,
+    /// The cursor was moved by someone else since it was read
+    Outdated,
+    Unknown(String),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::NotFound => write!(f, "Not Found"),
+            Error::Outdated => write!(f, "Outdated"),
+            Error::Unknown(msg) => write!(f, "Unknown Error: {}", msg),
+        }
+    }
+}
+
+pub trait ChangeCursorRepositoryTrait {
+    /// Stores the cursor of a user who has none yet, and returns the one they have otherwise.
+    async fn create(&self, cursor: ChangeCursor) -> Result<ChangeCursor, Error>;
+    async fn find_by_user(&self, user_id: Id) -> Result<ChangeCursor, Error>;
+    async fn find_by_channel(&self, channel_id: &str) -> Result<ChangeCursor, Error>;
+    async fn find_all(&self) -> Result<Vec<ChangeCursor>, Error>;
+    /// Moves the cursor to `cursor.page_token` if it is still at `from_page_token`, so
+    /// changes read by two syncs at once are only handled by one of them.
+    async fn advance(
+        &self,
+        cursor: ChangeCursor,
+        from_page_token: &str,
+    ) -> Result<ChangeCursor, Error>;
+    async fn update_channel(&self, cursor: ChangeCursor) -> Result<ChangeCursor, Error>;
+    async fn delete(&self, user_id: Id) -> Result<(), Error>;
+}
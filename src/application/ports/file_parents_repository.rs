@@ -0,0 +1,32 @@
+use std::collections::HashMap;
+
+use crate::domain::value_objects::id::Id;
+
+#[derive(Debug)]
+pub enum Error {
+    Unknown(String),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Unknown(msg) => write!(f, "Unknown Error: {}", msg),
+        }
+    }
+}
+
+/// Folders each file of a user's Drive was last seen in, so a change can be placed even
+/// once the file is gone or has moved.
+pub trait FileParentsRepositoryTrait {
+    /// Last known parents of the files, the ones never seen are left out.
+    async fn find(
+        &self,
+        user_id: Id,
+        file_ids: &[String],
+    ) -> Result<HashMap<String, Vec<String>>, Error>;
+    /// Stores the parents of the files, replacing what was known of them.
+    async fn save(&self, user_id: Id, parents: HashMap<String, Vec<String>>) -> Result<(), Error>;
+    async fn delete(&self, user_id: Id, file_ids: &[String]) -> Result<(), Error>;
+    /// Forgets every file of the user.
+    async fn delete_by_user(&self, user_id: Id) -> Result<(), Error>;
+}
@@ -1,8 +1,36 @@
-use crate::domain::value_objects::file_info::FileInfo;
+use std::{collections::HashMap, pin::Pin};
+
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use futures::Stream;
+
+use crate::domain::{
+    entities::change_cursor::WatchChannel,
+    value_objects::{
+        byte_range::ByteRange,
+        drive_about::DriveAbout,
+        file_change::ChangesPage,
+        file_info::{FileField, FileInfo},
+        file_query::FileQuery,
+        files_page::FilesPage,
+        google_token::GoogleToken,
+        permission::{Permission, PermissionGrant, PermissionUpdate},
+        revision::Revision,
+        search_query::SearchQuery,
+        shared_drive::SharedDrive,
+    },
+};
 
 #[derive(Debug)]
 pub enum Error {
     GoogleUnauthenticated,
+    NotFound(String),
+    /// Drive refused the operation to the user
+    PermissionDenied(String),
+    /// Drive rejected the request as invalid
+    InvalidRequest(String),
+    /// The user's storage is full
+    QuotaExceeded(String),
     Unknown(String),
 }
 
@@ -10,25 +38,226 @@ impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Error::GoogleUnauthenticated => write!(f, "Google Unauthenticated"),
+            Error::NotFound(e) => write!(f, "Not found: {}", e),
+            Error::PermissionDenied(e) => write!(f, "Permission denied: {}", e),
+            Error::InvalidRequest(e) => write!(f, "Invalid request: {}", e),
+            Error::QuotaExceeded(e) => write!(f, "Storage quota exceeded: {}", e),
             Error::Unknown(e) => write!(f, "Unknown error: {}", e),
         }
     }
 }
 
+impl std::error::Error for Error {}
+
+pub type ByteStream = Pin<Box<dyn Stream<Item = Result<Bytes, Error>> + Send>>;
+
+/// File content as it comes from Drive, along with the metadata needed to serve it.
+pub struct FileDownload {
+    pub file: FileInfo,
+    pub content: ByteStream,
+}
+
+/// Google consent page URL plus the values the callback has to be checked against.
+pub struct AuthorizationUrl {
+    pub url: String,
+    pub state: String,
+    pub pkce_verifier: String,
+}
+
+/// An image as Drive serves it.
+pub struct Image {
+    pub content_type: String,
+    pub content: Bytes,
+}
+
+/// Push notification channel to open on the change log.
+pub struct ChannelRequest {
+    pub id: String,
+    /// HTTPS URL Drive sends the notifications to
+    pub address: String,
+    pub expires_at: DateTime<Utc>,
+}
+
 pub trait GoogleDriveServiceTrait {
-    async fn get_google_auth_url(&self) -> Result<(String, String), Error>;
-    async fn handle_google_callback(&self, code: String) -> Result<String, Error>;
+    async fn get_google_auth_url(&self, force_consent: bool) -> Result<AuthorizationUrl, Error>;
+    async fn handle_google_callback(
+        &self,
+        code: String,
+        pkce_verifier: String,
+    ) -> Result<GoogleToken, Error>;
+    async fn refresh_google_token(&self, refresh_token: String) -> Result<GoogleToken, Error>;
     async fn get_google_email(&self, access_token: String) -> Result<String, Error>;
-    async fn download_p_d_f(&self, access_token: String, file_id: &str) -> Result<String, Error>;
-    async fn list_files(
+    async fn get_file(&self, access_token: String, file_id: &str) -> Result<FileInfo, Error>;
+    /// Same as `get_file`, with only the given fields filled.
+    async fn get_file_fields(
         &self,
         access_token: String,
-        folder_id: &str,
-    ) -> Result<Vec<FileInfo>, Error>;
-    async fn create_p_d_f(
+        file_id: &str,
+        fields: &[FileField],
+    ) -> Result<FileInfo, Error>;
+    /// Fetches the image behind a file's `thumbnail_link`, scaled to `width` pixels.
+    async fn download_thumbnail(
+        &self,
+        access_token: String,
+        thumbnail_link: &str,
+        width: u32,
+    ) -> Result<Image, Error>;
+    /// The user's account as Drive sees it, with their storage quota.
+    async fn get_about(&self, access_token: String) -> Result<DriveAbout, Error>;
+    /// Every shared drive the user is a member of.
+    async fn list_drives(&self, access_token: String) -> Result<Vec<SharedDrive>, Error>;
+    /// Streams the content of a file, or only `range` of it.
+    async fn download_file_content(
+        &self,
+        access_token: String,
+        file_id: &str,
+        range: Option<ByteRange>,
+    ) -> Result<ByteStream, Error>;
+    /// Streams a native Google file converted to `mime_type`.
+    async fn export_file_content(
+        &self,
+        access_token: String,
+        file_id: &str,
+        mime_type: &str,
+    ) -> Result<ByteStream, Error>;
+    async fn list_files(&self, access_token: String, query: &FileQuery)
+        -> Result<FilesPage, Error>;
+    async fn search_files(
+        &self,
+        access_token: String,
+        query: &SearchQuery,
+    ) -> Result<FilesPage, Error>;
+    /// Starts a resumable upload and returns the session URI the content is sent to.
+    async fn create_upload_session(
         &self,
         access_token: String,
         file_name: &str,
-        file_path: String,
+        mime_type: &str,
+        parent_id: Option<&str>,
     ) -> Result<String, Error>;
+    /// Opens a resumable upload session that stores new content as a revision of an
+    /// existing file.
+    async fn create_update_session(
+        &self,
+        access_token: String,
+        file_id: &str,
+        mime_type: &str,
+    ) -> Result<String, Error>;
+    /// Sends the file content to a resumable upload session in chunks, resuming from the
+    /// last offset Drive confirmed when a chunk fails. Returns the created or updated file.
+    async fn upload_file_content(
+        &self,
+        session_uri: String,
+        content: ByteStream,
+    ) -> Result<FileInfo, Error>;
+    async fn trash_file(&self, access_token: String, file_id: &str) -> Result<FileInfo, Error>;
+    async fn restore_file(&self, access_token: String, file_id: &str) -> Result<FileInfo, Error>;
+    /// Deletes a file permanently, skipping the trash.
+    async fn delete_file(&self, access_token: String, file_id: &str) -> Result<(), Error>;
+    async fn rename_file(
+        &self,
+        access_token: String,
+        file_id: &str,
+        name: &str,
+    ) -> Result<FileInfo, Error>;
+    /// Sets the given app properties of a file, keeping the others it has.
+    async fn set_app_properties(
+        &self,
+        access_token: String,
+        file_id: &str,
+        properties: &HashMap<String, String>,
+    ) -> Result<FileInfo, Error>;
+    /// Replaces the current parents of a file with `folder_id`.
+    async fn move_file(
+        &self,
+        access_token: String,
+        file_id: &str,
+        folder_id: &str,
+    ) -> Result<FileInfo, Error>;
+    /// Copies a file, into the same folder as the original unless `folder_id` is given.
+    async fn copy_file(
+        &self,
+        access_token: String,
+        file_id: &str,
+        name: Option<&str>,
+        folder_id: Option<&str>,
+    ) -> Result<FileInfo, Error>;
+    /// Creates a folder, at the root of My Drive unless `parent_id` is given.
+    async fn create_folder(
+        &self,
+        access_token: String,
+        name: &str,
+        parent_id: Option<&str>,
+    ) -> Result<FileInfo, Error>;
+    async fn list_permissions(
+        &self,
+        access_token: String,
+        file_id: &str,
+    ) -> Result<Vec<Permission>, Error>;
+    async fn create_permission(
+        &self,
+        access_token: String,
+        file_id: &str,
+        grant: &PermissionGrant,
+    ) -> Result<Permission, Error>;
+    async fn update_permission(
+        &self,
+        access_token: String,
+        file_id: &str,
+        permission_id: &str,
+        update: &PermissionUpdate,
+    ) -> Result<Permission, Error>;
+    async fn delete_permission(
+        &self,
+        access_token: String,
+        file_id: &str,
+        permission_id: &str,
+    ) -> Result<(), Error>;
+    /// Every revision of a file, oldest first.
+    async fn list_revisions(
+        &self,
+        access_token: String,
+        file_id: &str,
+    ) -> Result<Vec<Revision>, Error>;
+    async fn get_revision(
+        &self,
+        access_token: String,
+        file_id: &str,
+        revision_id: &str,
+    ) -> Result<Revision, Error>;
+    async fn download_revision_content(
+        &self,
+        access_token: String,
+        file_id: &str,
+        revision_id: &str,
+    ) -> Result<ByteStream, Error>;
+    async fn update_revision(
+        &self,
+        access_token: String,
+        file_id: &str,
+        revision_id: &str,
+        keep_forever: bool,
+    ) -> Result<Revision, Error>;
+    async fn delete_revision(
+        &self,
+        access_token: String,
+        file_id: &str,
+        revision_id: &str,
+    ) -> Result<(), Error>;
+    /// Token of the current end of the user's change log.
+    async fn get_start_page_token(&self, access_token: String) -> Result<String, Error>;
+    async fn list_changes(
+        &self,
+        access_token: String,
+        page_token: &str,
+    ) -> Result<ChangesPage, Error>;
+    /// Asks Drive to call `channel.address` whenever the change log grows past `page_token`.
+    async fn watch_changes(
+        &self,
+        access_token: String,
+        page_token: &str,
+        channel: &ChannelRequest,
+    ) -> Result<WatchChannel, Error>;
+    async fn stop_channel(&self, access_token: String, channel: &WatchChannel)
+        -> Result<(), Error>;
 }
@@ -0,0 +1,732 @@
+//! In-memory ports for use case tests.
+
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+};
+
+use futures::TryStreamExt;
+
+use super::{
+    change_cursor_repository, file_parents_repository,
+    google_drive_service::{self, AuthorizationUrl, ByteStream, ChannelRequest, Image},
+    user_repository, webhook_repository,
+    webhook_sender::{self, Delivery},
+};
+use crate::domain::{
+    entities::{
+        change_cursor::{ChangeCursor, WatchChannel},
+        user::User,
+        webhook::Webhook,
+    },
+    value_objects::{
+        byte_range::ByteRange,
+        drive_about::{DriveAbout, StorageQuota},
+        email::Email,
+        file_change::ChangesPage,
+        file_info::{FileField, FileInfo, FOLDER_MIME_TYPE},
+        file_query::FileQuery,
+        files_page::FilesPage,
+        google_token::GoogleToken,
+        id::Id,
+        permission::{Permission, PermissionGrant, PermissionUpdate},
+        revision::Revision,
+        search_query::SearchQuery,
+        shared_drive::SharedDrive,
+    },
+};
+
+/// A user with a token that doesn't expire.
+pub fn user() -> User {
+    let email = Email::new("name@some.com".to_string()).unwrap();
+    User::new(email, GoogleToken::new("token".to_string(), None, None))
+}
+
+#[derive(Default)]
+pub struct MockUserRepository {
+    pub users: Mutex<Vec<User>>,
+}
+
+impl MockUserRepository {
+    pub fn with_user(user: User) -> Self {
+        Self {
+            users: Mutex::new(vec![user]),
+        }
+    }
+}
+
+impl user_repository::UserRepositoryTrait for MockUserRepository {
+    async fn find_by_id(&self, id: Id) -> Result<User, user_repository::Error> {
+        let users = self.users.lock().unwrap();
+        users
+            .iter()
+            .find(|x| x.id == id)
+            .cloned()
+            .ok_or(user_repository::Error::NotFound)
+    }
+
+    async fn find_by_email(&self, _email: &str) -> Result<User, user_repository::Error> {
+        unimplemented!()
+    }
+
+    async fn update(&self, user: User) -> Result<User, user_repository::Error> {
+        let mut users = self.users.lock().unwrap();
+        users.retain(|x| x.id != user.id);
+        users.push(user.clone());
+        Ok(user)
+    }
+
+    async fn create(&self, user: User) -> Result<User, user_repository::Error> {
+        self.users.lock().unwrap().push(user.clone());
+        Ok(user)
+    }
+}
+
+/// A Drive holding `files`, where uploads land once their content has been read.
+/// Methods the tests don't go through are left unimplemented.
+#[derive(Default)]
+pub struct MockGoogleDriveService {
+    pub files: Mutex<Vec<FileInfo>>,
+    /// `None` for unlimited storage
+    pub storage_limit: Option<u64>,
+    pub storage_usage: u64,
+    pub about_calls: AtomicUsize,
+    /// Pages of the change log, by the page token they are read with
+    pub change_pages: Mutex<HashMap<String, ChangesPage>>,
+    /// Files waiting for their content, by session URI
+    sessions: Mutex<HashMap<String, FileInfo>>,
+}
+
+impl MockGoogleDriveService {
+    pub fn with_files(files: Vec<FileInfo>) -> Self {
+        Self {
+            files: Mutex::new(files),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_storage(self, limit: Option<u64>, usage: u64) -> Self {
+        Self {
+            storage_limit: limit,
+            storage_usage: usage,
+            ..self
+        }
+    }
+
+    pub fn file_names(&self, folder_id: &str) -> Vec<String> {
+        let files = self.files.lock().unwrap();
+        files
+            .iter()
+            .filter(|x| is_in(x, folder_id))
+            .map(|x| x.name.clone())
+            .collect()
+    }
+
+    fn open_session(&self, file: FileInfo) -> String {
+        let mut sessions = self.sessions.lock().unwrap();
+        let session_uri = format!("session-{}", sessions.len());
+        sessions.insert(session_uri.clone(), file);
+        session_uri
+    }
+}
+
+fn is_in(file: &FileInfo, folder_id: &str) -> bool {
+    file.parents
+        .as_ref()
+        .is_some_and(|x| x.iter().any(|x| x == folder_id))
+}
+
+impl google_drive_service::GoogleDriveServiceTrait for MockGoogleDriveService {
+    async fn get_google_auth_url(
+        &self,
+        _force_consent: bool,
+    ) -> Result<AuthorizationUrl, google_drive_service::Error> {
+        unimplemented!()
+    }
+
+    async fn handle_google_callback(
+        &self,
+        _code: String,
+        _pkce_verifier: String,
+    ) -> Result<GoogleToken, google_drive_service::Error> {
+        unimplemented!()
+    }
+
+    async fn refresh_google_token(
+        &self,
+        _refresh_token: String,
+    ) -> Result<GoogleToken, google_drive_service::Error> {
+        unimplemented!()
+    }
+
+    async fn get_google_email(
+        &self,
+        _access_token: String,
+    ) -> Result<String, google_drive_service::Error> {
+        unimplemented!()
+    }
+
+    async fn get_file(
+        &self,
+        _access_token: String,
+        file_id: &str,
+    ) -> Result<FileInfo, google_drive_service::Error> {
+        let files = self.files.lock().unwrap();
+        files
+            .iter()
+            .find(|x| x.id == file_id)
+            .cloned()
+            .ok_or_else(|| google_drive_service::Error::NotFound(file_id.to_string()))
+    }
+
+    async fn get_file_fields(
+        &self,
+        access_token: String,
+        file_id: &str,
+        _fields: &[FileField],
+    ) -> Result<FileInfo, google_drive_service::Error> {
+        self.get_file(access_token, file_id).await
+    }
+
+    async fn download_thumbnail(
+        &self,
+        _access_token: String,
+        _thumbnail_link: &str,
+        _width: u32,
+    ) -> Result<Image, google_drive_service::Error> {
+        unimplemented!()
+    }
+
+    async fn get_about(
+        &self,
+        _access_token: String,
+    ) -> Result<DriveAbout, google_drive_service::Error> {
+        self.about_calls.fetch_add(1, Ordering::Relaxed);
+        Ok(DriveAbout {
+            storage_quota: StorageQuota {
+                limit: self.storage_limit,
+                usage: self.storage_usage,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+    }
+
+    async fn list_drives(
+        &self,
+        _access_token: String,
+    ) -> Result<Vec<SharedDrive>, google_drive_service::Error> {
+        unimplemented!()
+    }
+
+    async fn download_file_content(
+        &self,
+        _access_token: String,
+        _file_id: &str,
+        _range: Option<ByteRange>,
+    ) -> Result<ByteStream, google_drive_service::Error> {
+        unimplemented!()
+    }
+
+    async fn export_file_content(
+        &self,
+        _access_token: String,
+        _file_id: &str,
+        _mime_type: &str,
+    ) -> Result<ByteStream, google_drive_service::Error> {
+        unimplemented!()
+    }
+
+    async fn list_files(
+        &self,
+        _access_token: String,
+        query: &FileQuery,
+    ) -> Result<FilesPage, google_drive_service::Error> {
+        let files = self.files.lock().unwrap();
+        let files = files
+            .iter()
+            .filter(|x| is_in(x, &query.folder_id))
+            .filter(|x| match &query.name_contains {
+                Some(name) => x.name.contains(name.as_str()),
+                None => true,
+            })
+            .cloned()
+            .collect();
+        Ok(FilesPage {
+            files,
+            next_page_token: None,
+        })
+    }
+
+    async fn search_files(
+        &self,
+        _access_token: String,
+        _query: &SearchQuery,
+    ) -> Result<FilesPage, google_drive_service::Error> {
+        unimplemented!()
+    }
+
+    async fn create_upload_session(
+        &self,
+        _access_token: String,
+        file_name: &str,
+        mime_type: &str,
+        parent_id: Option<&str>,
+    ) -> Result<String, google_drive_service::Error> {
+        Ok(self.open_session(FileInfo {
+            name: file_name.to_string(),
+            file_type: mime_type.to_string(),
+            parents: Some(vec![parent_id.unwrap_or("root").to_string()]),
+            ..Default::default()
+        }))
+    }
+
+    async fn create_update_session(
+        &self,
+        access_token: String,
+        file_id: &str,
+        _mime_type: &str,
+    ) -> Result<String, google_drive_service::Error> {
+        let file = self.get_file(access_token, file_id).await?;
+        Ok(self.open_session(file))
+    }
+
+    async fn upload_file_content(
+        &self,
+        session_uri: String,
+        content: ByteStream,
+    ) -> Result<FileInfo, google_drive_service::Error> {
+        // Sending the content takes a while, which lets concurrent uploads interleave
+        tokio::task::yield_now().await;
+        let content: Vec<_> = content.try_collect().await?;
+
+        let mut file = match self.sessions.lock().unwrap().remove(&session_uri) {
+            Some(file) => file,
+            None => return Err(google_drive_service::Error::NotFound(session_uri)),
+        };
+        file.size = Some(content.iter().map(|x| x.len() as u64).sum());
+
+        let mut files = self.files.lock().unwrap();
+        if file.id.is_empty() {
+            file.id = format!("file-{}", files.len());
+        }
+        files.retain(|x| x.id != file.id);
+        files.push(file.clone());
+        Ok(file)
+    }
+
+    async fn trash_file(
+        &self,
+        _access_token: String,
+        _file_id: &str,
+    ) -> Result<FileInfo, google_drive_service::Error> {
+        unimplemented!()
+    }
+
+    async fn restore_file(
+        &self,
+        _access_token: String,
+        _file_id: &str,
+    ) -> Result<FileInfo, google_drive_service::Error> {
+        unimplemented!()
+    }
+
+    async fn delete_file(
+        &self,
+        _access_token: String,
+        _file_id: &str,
+    ) -> Result<(), google_drive_service::Error> {
+        unimplemented!()
+    }
+
+    async fn rename_file(
+        &self,
+        _access_token: String,
+        _file_id: &str,
+        _name: &str,
+    ) -> Result<FileInfo, google_drive_service::Error> {
+        unimplemented!()
+    }
+
+    async fn set_app_properties(
+        &self,
+        _access_token: String,
+        _file_id: &str,
+        _properties: &HashMap<String, String>,
+    ) -> Result<FileInfo, google_drive_service::Error> {
+        unimplemented!()
+    }
+
+    async fn move_file(
+        &self,
+        _access_token: String,
+        _file_id: &str,
+        _folder_id: &str,
+    ) -> Result<FileInfo, google_drive_service::Error> {
+        unimplemented!()
+    }
+
+    async fn copy_file(
+        &self,
+        _access_token: String,
+        _file_id: &str,
+        _name: Option<&str>,
+        _folder_id: Option<&str>,
+    ) -> Result<FileInfo, google_drive_service::Error> {
+        unimplemented!()
+    }
+
+    async fn create_folder(
+        &self,
+        _access_token: String,
+        name: &str,
+        parent_id: Option<&str>,
+    ) -> Result<FileInfo, google_drive_service::Error> {
+        let mut files = self.files.lock().unwrap();
+        let folder = FileInfo {
+            id: format!("file-{}", files.len()),
+            name: name.to_string(),
+            file_type: FOLDER_MIME_TYPE.to_string(),
+            parents: Some(vec![parent_id.unwrap_or("root").to_string()]),
+            ..Default::default()
+        };
+        files.push(folder.clone());
+        Ok(folder)
+    }
+
+    async fn list_permissions(
+        &self,
+        _access_token: String,
+        _file_id: &str,
+    ) -> Result<Vec<Permission>, google_drive_service::Error> {
+        unimplemented!()
+    }
+
+    async fn create_permission(
+        &self,
+        _access_token: String,
+        _file_id: &str,
+        _grant: &PermissionGrant,
+    ) -> Result<Permission, google_drive_service::Error> {
+        unimplemented!()
+    }
+
+    async fn update_permission(
+        &self,
+        _access_token: String,
+        _file_id: &str,
+        _permission_id: &str,
+        _update: &PermissionUpdate,
+    ) -> Result<Permission, google_drive_service::Error> {
+        unimplemented!()
+    }
+
+    async fn delete_permission(
+        &self,
+        _access_token: String,
+        _file_id: &str,
+        _permission_id: &str,
+    ) -> Result<(), google_drive_service::Error> {
+        unimplemented!()
+    }
+
+    async fn list_revisions(
+        &self,
+        _access_token: String,
+        _file_id: &str,
+    ) -> Result<Vec<Revision>, google_drive_service::Error> {
+        unimplemented!()
+    }
+
+    async fn get_revision(
+        &self,
+        _access_token: String,
+        _file_id: &str,
+        _revision_id: &str,
+    ) -> Result<Revision, google_drive_service::Error> {
+        unimplemented!()
+    }
+
+    async fn download_revision_content(
+        &self,
+        _access_token: String,
+        _file_id: &str,
+        _revision_id: &str,
+    ) -> Result<ByteStream, google_drive_service::Error> {
+        unimplemented!()
+    }
+
+    async fn update_revision(
+        &self,
+        _access_token: String,
+        _file_id: &str,
+        _revision_id: &str,
+        _keep_forever: bool,
+    ) -> Result<Revision, google_drive_service::Error> {
+        unimplemented!()
+    }
+
+    async fn delete_revision(
+        &self,
+        _access_token: String,
+        _file_id: &str,
+        _revision_id: &str,
+    ) -> Result<(), google_drive_service::Error> {
+        unimplemented!()
+    }
+
+    async fn get_start_page_token(
+        &self,
+        _access_token: String,
+    ) -> Result<String, google_drive_service::Error> {
+        unimplemented!()
+    }
+
+    async fn list_changes(
+        &self,
+        _access_token: String,
+        page_token: &str,
+    ) -> Result<ChangesPage, google_drive_service::Error> {
+        // Past the end of the log there is nothing new
+        let mut change_pages = self.change_pages.lock().unwrap();
+        Ok(change_pages.remove(page_token).unwrap_or(ChangesPage {
+            changes: Vec::new(),
+            next_page_token: None,
+            new_start_page_token: Some(page_token.to_string()),
+        }))
+    }
+
+    async fn watch_changes(
+        &self,
+        _access_token: String,
+        _page_token: &str,
+        _channel: &ChannelRequest,
+    ) -> Result<WatchChannel, google_drive_service::Error> {
+        unimplemented!()
+    }
+
+    async fn stop_channel(
+        &self,
+        _access_token: String,
+        _channel: &WatchChannel,
+    ) -> Result<(), google_drive_service::Error> {
+        unimplemented!()
+    }
+}
+
+#[derive(Default)]
+pub struct MockChangeCursorRepository {
+    pub cursors: Mutex<Vec<ChangeCursor>>,
+}
+
+impl MockChangeCursorRepository {
+    pub fn with_cursor(cursor: ChangeCursor) -> Self {
+        Self {
+            cursors: Mutex::new(vec![cursor]),
+        }
+    }
+
+    pub fn page_token(&self, user_id: Id) -> Option<String> {
+        let cursors = self.cursors.lock().unwrap();
+        cursors
+            .iter()
+            .find(|x| x.user_id == user_id)
+            .map(|x| x.page_token.clone())
+    }
+}
+
+impl change_cursor_repository::ChangeCursorRepositoryTrait for MockChangeCursorRepository {
+    async fn create(
+        &self,
+        cursor: ChangeCursor,
+    ) -> Result<ChangeCursor, change_cursor_repository::Error> {
+        self.cursors.lock().unwrap().push(cursor.clone());
+        Ok(cursor)
+    }
+
+    async fn find_by_user(
+        &self,
+        user_id: Id,
+    ) -> Result<ChangeCursor, change_cursor_repository::Error> {
+        let cursors = self.cursors.lock().unwrap();
+        cursors
+            .iter()
+            .find(|x| x.user_id == user_id)
+            .cloned()
+            .ok_or(change_cursor_repository::Error::NotFound)
+    }
+
+    async fn find_by_channel(
+        &self,
+        _channel_id: &str,
+    ) -> Result<ChangeCursor, change_cursor_repository::Error> {
+        unimplemented!()
+    }
+
+    async fn find_all(&self) -> Result<Vec<ChangeCursor>, change_cursor_repository::Error> {
+        Ok(self.cursors.lock().unwrap().clone())
+    }
+
+    async fn advance(
+        &self,
+        cursor: ChangeCursor,
+        from_page_token: &str,
+    ) -> Result<ChangeCursor, change_cursor_repository::Error> {
+        let mut cursors = self.cursors.lock().unwrap();
+        match cursors
+            .iter_mut()
+            .find(|x| x.user_id == cursor.user_id && x.page_token == from_page_token)
+        {
+            Some(stored) => {
+                *stored = cursor.clone();
+                Ok(cursor)
+            }
+            None => Err(change_cursor_repository::Error::Outdated),
+        }
+    }
+
+    async fn update_channel(
+        &self,
+        _cursor: ChangeCursor,
+    ) -> Result<ChangeCursor, change_cursor_repository::Error> {
+        unimplemented!()
+    }
+
+    async fn delete(&self, _user_id: Id) -> Result<(), change_cursor_repository::Error> {
+        unimplemented!()
+    }
+}
+
+/// Parents by user and file id.
+#[derive(Default)]
+pub struct MockFileParentsRepository {
+    pub parents: Mutex<HashMap<(Id, String), Vec<String>>>,
+}
+
+impl MockFileParentsRepository {
+    pub fn with_parents(user_id: Id, parents: &[(&str, &[&str])]) -> Self {
+        Self {
+            parents: Mutex::new(
+                parents
+                    .iter()
+                    .map(|(file_id, parents)| {
+                        let parents = parents.iter().map(|x| x.to_string()).collect();
+                        ((user_id, file_id.to_string()), parents)
+                    })
+                    .collect(),
+            ),
+        }
+    }
+
+    pub fn parents(&self, user_id: Id, file_id: &str) -> Option<Vec<String>> {
+        let parents = self.parents.lock().unwrap();
+        parents.get(&(user_id, file_id.to_string())).cloned()
+    }
+}
+
+impl file_parents_repository::FileParentsRepositoryTrait for MockFileParentsRepository {
+    async fn find(
+        &self,
+        user_id: Id,
+        file_ids: &[String],
+    ) -> Result<HashMap<String, Vec<String>>, file_parents_repository::Error> {
+        Ok(file_ids
+            .iter()
+            .filter_map(|x| Some((x.clone(), self.parents(user_id, x)?)))
+            .collect())
+    }
+
+    async fn save(
+        &self,
+        user_id: Id,
+        parents: HashMap<String, Vec<String>>,
+    ) -> Result<(), file_parents_repository::Error> {
+        let mut stored = self.parents.lock().unwrap();
+        for (file_id, parents) in parents {
+            stored.insert((user_id, file_id), parents);
+        }
+        Ok(())
+    }
+
+    async fn delete(
+        &self,
+        user_id: Id,
+        file_ids: &[String],
+    ) -> Result<(), file_parents_repository::Error> {
+        let mut stored = self.parents.lock().unwrap();
+        for file_id in file_ids {
+            stored.remove(&(user_id, file_id.clone()));
+        }
+        Ok(())
+    }
+
+    async fn delete_by_user(&self, user_id: Id) -> Result<(), file_parents_repository::Error> {
+        self.parents
+            .lock()
+            .unwrap()
+            .retain(|(x, _), _| *x != user_id);
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct MockWebhookRepository {
+    pub webhooks: Mutex<Vec<Webhook>>,
+}
+
+impl MockWebhookRepository {
+    pub fn with_webhooks(webhooks: Vec<Webhook>) -> Self {
+        Self {
+            webhooks: Mutex::new(webhooks),
+        }
+    }
+}
+
+impl webhook_repository::WebhookRepositoryTrait for MockWebhookRepository {
+    async fn create(&self, webhook: Webhook) -> Result<Webhook, webhook_repository::Error> {
+        self.webhooks.lock().unwrap().push(webhook.clone());
+        Ok(webhook)
+    }
+
+    async fn find_by_user(&self, user_id: Id) -> Result<Vec<Webhook>, webhook_repository::Error> {
+        let webhooks = self.webhooks.lock().unwrap();
+        Ok(webhooks
+            .iter()
+            .filter(|x| x.user_id == user_id)
+            .cloned()
+            .collect())
+    }
+
+    async fn delete(&self, _id: Id, _user_id: Id) -> Result<Webhook, webhook_repository::Error> {
+        unimplemented!()
+    }
+}
+
+/// Keeps every delivery instead of sending it. Deliveries to `failing_url` are rejected.
+#[derive(Default)]
+pub struct MockWebhookSender {
+    pub deliveries: Mutex<Vec<Delivery>>,
+    pub failing_url: Option<String>,
+}
+
+impl webhook_sender::WebhookSenderTrait for MockWebhookSender {
+    async fn check_url(&self, _url: &str) -> Result<(), webhook_sender::Error> {
+        Ok(())
+    }
+
+    async fn send(&self, delivery: &Delivery) -> Result<(), webhook_sender::Error> {
+        if self.failing_url.as_ref() == Some(&delivery.url) {
+            return Err(webhook_sender::Error::Rejected(500));
+        }
+        self.deliveries.lock().unwrap().push(Delivery {
+            url: delivery.url.clone(),
+            body: delivery.body.clone(),
+            timestamp: delivery.timestamp,
+            signature: delivery.signature.clone(),
+        });
+        Ok(())
+    }
+}
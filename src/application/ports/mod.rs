@@ -1,2 +1,15 @@
+pub mod auth_request_repository;
+pub mod change_cursor_repository;
+pub mod file_parents_repository;
 pub mod google_drive_service;
+#[cfg(test)]
+pub mod mocks;
+pub mod pdf_editor;
+pub mod pdf_reader;
+pub mod pdf_renderer;
+pub mod share_link_repository;
+pub mod thumbnail_repository;
+pub mod upload_request_repository;
 pub mod user_repository;
+pub mod webhook_repository;
+pub mod webhook_sender;
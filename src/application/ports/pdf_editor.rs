@@ -0,0 +1,31 @@
+use bytes::Bytes;
+
+use crate::domain::value_objects::page_range::PageRanges;
+
+#[derive(Debug)]
+pub enum Error {
+    /// A document can't be read, or doesn't have the pages asked for
+    InvalidDocument(String),
+    /// The editor couldn't run or took too long
+    Unavailable(String),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::InvalidDocument(msg) => write!(f, "Invalid document: {}", msg),
+            Error::Unavailable(msg) => write!(f, "Editor unavailable: {}", msg),
+        }
+    }
+}
+
+/// Pages taken from one PDF, all of them when `pages` is `None`.
+pub struct PdfPart {
+    pub content: Bytes,
+    pub pages: Option<PageRanges>,
+}
+
+pub trait PdfEditorTrait {
+    /// A new PDF made of the pages of every part, in order.
+    async fn combine(&self, parts: &[PdfPart]) -> Result<Bytes, Error>;
+}
@@ -0,0 +1,29 @@
+use bytes::Bytes;
+
+use crate::domain::value_objects::pdf_document::PdfInfo;
+
+#[derive(Debug)]
+pub enum Error {
+    /// The content is not a PDF the reader can open
+    InvalidDocument(String),
+    /// The PDF can't be opened without its password
+    Encrypted,
+    /// The reader couldn't run or took too long
+    Unavailable(String),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::InvalidDocument(msg) => write!(f, "Invalid document: {}", msg),
+            Error::Encrypted => write!(f, "The document is protected with a password"),
+            Error::Unavailable(msg) => write!(f, "Reader unavailable: {}", msg),
+        }
+    }
+}
+
+pub trait PdfReaderTrait {
+    async fn info(&self, content: Bytes) -> Result<PdfInfo, Error>;
+    /// Text of every page, in order.
+    async fn text(&self, content: Bytes) -> Result<Vec<String>, Error>;
+}
@@ -0,0 +1,23 @@
+use bytes::Bytes;
+
+#[derive(Debug)]
+pub enum Error {
+    /// The content is not a PDF the renderer can read
+    InvalidDocument(String),
+    /// The renderer couldn't run or took too long
+    Unavailable(String),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::InvalidDocument(msg) => write!(f, "Invalid document: {}", msg),
+            Error::Unavailable(msg) => write!(f, "Renderer unavailable: {}", msg),
+        }
+    }
+}
+
+pub trait PdfRendererTrait {
+    /// PNG image of the first page of the PDF, `width` pixels wide.
+    async fn render_first_page(&self, content: Bytes, width: u32) -> Result<Bytes, Error>;
+}
@@ -0,0 +1,31 @@
+use crate::domain::{entities::share_link::ShareLink, value_objects::id::Id};
+
+#[derive(Debug)]
+pub enum Error {
+    NotFound,
+    /// The link exists but was revoked, expired or ran out of downloads
+    Unavailable,
+    Unknown(String),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::NotFound => write!(f, "Not Found"),
+            Error::Unavailable => write!(f, "Unavailable"),
+            Error::Unknown(msg) => write!(f, "Unknown Error: {}", msg),
+        }
+    }
+}
+
+pub trait ShareLinkRepositoryTrait {
+    async fn create(&self, share_link: ShareLink) -> Result<ShareLink, Error>;
+    async fn find_by_user(&self, user_id: Id) -> Result<Vec<ShareLink>, Error>;
+    /// Revokes a link created by the user, other users' links are `NotFound`.
+    async fn revoke(&self, id: Id, user_id: Id) -> Result<ShareLink, Error>;
+    /// Counts one download through the link and returns it. Fails with `Unavailable` when
+    /// the link can't be used anymore.
+    async fn register_download(&self, id: Id) -> Result<ShareLink, Error>;
+    /// Gives back a download counted by `register_download` that couldn't be served.
+    async fn release_download(&self, id: Id) -> Result<(), Error>;
+}
@@ -0,0 +1,23 @@
+use crate::domain::entities::thumbnail::Thumbnail;
+
+#[derive(Debug)]
+pub enum Error {
+    NotFound,
+    Unknown(String),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::NotFound => write!(f, "Not Found"),
+            Error::Unknown(msg) => write!(f, "Unknown Error: {}", msg),
+        }
+    }
+}
+
+pub trait ThumbnailRepositoryTrait {
+    async fn find(&self, file_id: &str, md5_checksum: &str, width: u32)
+        -> Result<Thumbnail, Error>;
+    /// Stores the thumbnail, replacing the one of the same file, checksum and width.
+    async fn save(&self, thumbnail: &Thumbnail) -> Result<(), Error>;
+}
@@ -0,0 +1,33 @@
+use crate::domain::{
+    entities::upload_request::{UploadRecord, UploadRequest},
+    value_objects::id::Id,
+};
+
+#[derive(Debug)]
+pub enum Error {
+    NotFound,
+    /// The request exists but expired or has no uploads left
+    Unavailable,
+    Unknown(String),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::NotFound => write!(f, "Not Found"),
+            Error::Unavailable => write!(f, "Unavailable"),
+            Error::Unknown(msg) => write!(f, "Unknown Error: {}", msg),
+        }
+    }
+}
+
+pub trait UploadRequestRepositoryTrait {
+    async fn create(&self, upload_request: UploadRequest) -> Result<UploadRequest, Error>;
+    async fn find_by_user(&self, user_id: Id) -> Result<Vec<UploadRequest>, Error>;
+    /// Takes one of the uploads the request allows and returns it. Fails with `Unavailable`
+    /// when the request can't be used anymore.
+    async fn reserve_upload(&self, id: Id) -> Result<UploadRequest, Error>;
+    /// Gives back an upload taken with `reserve_upload` that did not finish.
+    async fn release_upload(&self, id: Id) -> Result<(), Error>;
+    async fn record_upload(&self, id: Id, upload: UploadRecord) -> Result<(), Error>;
+}
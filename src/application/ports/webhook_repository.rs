@@ -0,0 +1,23 @@
+use crate::domain::{entities::webhook::Webhook, value_objects::id::Id};
+
+#[derive(Debug)]
+pub enum Error {
+    NotFound,
+    Unknown(String),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::NotFound => write!(f, "Not Found"),
+            Error::Unknown(msg) => write!(f, "Unknown Error: {}", msg),
+        }
+    }
+}
+
+pub trait WebhookRepositoryTrait {
+    async fn create(&self, webhook: Webhook) -> Result<Webhook, Error>;
+    async fn find_by_user(&self, user_id: Id) -> Result<Vec<Webhook>, Error>;
+    /// Deletes a webhook registered by the user, other users' webhooks are `NotFound`.
+    async fn delete(&self, id: Id, user_id: Id) -> Result<Webhook, Error>;
+}
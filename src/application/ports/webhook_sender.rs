@@ -0,0 +1,34 @@
+#[derive(Debug)]
+pub enum Error {
+    /// The receiver answered with an error status on the last attempt
+    Rejected(u16),
+    Unreachable(String),
+    /// The URL is not HTTPS or points to an address outside the public internet
+    ForbiddenUrl(String),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Rejected(status) => write!(f, "Rejected with status {}", status),
+            Error::Unreachable(msg) => write!(f, "Unreachable: {}", msg),
+            Error::ForbiddenUrl(msg) => write!(f, "Forbidden URL: {}", msg),
+        }
+    }
+}
+
+/// A signed webhook call, ready to be sent.
+pub struct Delivery {
+    pub url: String,
+    pub body: Vec<u8>,
+    pub timestamp: i64,
+    pub signature: String,
+}
+
+pub trait WebhookSenderTrait {
+    /// Checks that deliveries can be sent to the URL, so webhooks can't be used to reach
+    /// the server's own network.
+    async fn check_url(&self, url: &str) -> Result<(), Error>;
+    /// Posts the delivery, trying again with a growing delay while the receiver fails.
+    async fn send(&self, delivery: &Delivery) -> Result<(), Error>;
+}
@@ -0,0 +1,79 @@
+use std::fmt::Display;
+
+use crate::{
+    application::{
+        ports::{
+            google_drive_service::GoogleDriveServiceTrait,
+            user_repository::{self, UserRepositoryTrait},
+        },
+        usecases::google_session,
+    },
+    domain::value_objects::{file_info::FileInfo, id::Id, shared_drive::folder_or_root},
+};
+
+pub enum Error {
+    NotFound(String),
+    InvalidName(String),
+    GoogleUnauthenticated,
+    ConnectionError(String),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::NotFound(e) => write!(f, "Not found: {}", e),
+            Error::InvalidName(e) => write!(f, "Invalid name: {}", e),
+            Error::GoogleUnauthenticated => {
+                write!(f, "Google session expired, please log in again")
+            }
+            Error::ConnectionError(e) => write!(f, "Connection error: {}", e),
+        }
+    }
+}
+
+google_session::impl_from_session_error!(Error);
+
+pub struct Payload {
+    pub file_id: String,
+    /// Name of the copy, Drive uses "Copy of ..." when missing
+    pub name: Option<String>,
+    pub folder_id: Option<String>,
+    /// Copies to the root of this shared drive when there is no folder
+    pub drive_id: Option<String>,
+    pub user_id: Id,
+}
+
+pub async fn execute(
+    user_repository: &impl UserRepositoryTrait,
+    google_drive_service: &impl GoogleDriveServiceTrait,
+    payload: Payload,
+) -> Result<FileInfo, Error> {
+    if payload.name.as_ref().is_some_and(|x| x.trim().is_empty()) {
+        return Err(Error::InvalidName("File name cannot be empty".to_string()));
+    }
+
+    let mut user = match user_repository.find_by_id(payload.user_id).await {
+        Ok(user) => user,
+        Err(user_repository::Error::NotFound) => {
+            return Err(Error::NotFound("User not found".to_string()))
+        }
+        Err(err) => return Err(Error::ConnectionError(err.to_string())),
+    };
+
+    let folder_id = folder_or_root(payload.folder_id, payload.drive_id.as_deref());
+    google_session::with_access_token(
+        user_repository,
+        google_drive_service,
+        &mut user,
+        |access_token| {
+            google_drive_service.copy_file(
+                access_token,
+                &payload.file_id,
+                payload.name.as_deref().map(str::trim),
+                folder_id.as_deref(),
+            )
+        },
+    )
+    .await
+    .map_err(Error::from)
+}
@@ -0,0 +1,106 @@
+use std::fmt::Display;
+
+use crate::{
+    application::{
+        ports::{
+            google_drive_service::GoogleDriveServiceTrait,
+            user_repository::{self, UserRepositoryTrait},
+        },
+        usecases::google_session,
+    },
+    domain::value_objects::{file_info::FileInfo, id::Id, shared_drive::folder_or_root},
+};
+
+pub enum Error {
+    NotFound(String),
+    InvalidName(String),
+    GoogleUnauthenticated,
+    ConnectionError(String),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::NotFound(e) => write!(f, "Not found: {}", e),
+            Error::InvalidName(e) => write!(f, "Invalid name: {}", e),
+            Error::GoogleUnauthenticated => {
+                write!(f, "Google session expired, please log in again")
+            }
+            Error::ConnectionError(e) => write!(f, "Connection error: {}", e),
+        }
+    }
+}
+
+google_session::impl_from_session_error!(Error);
+
+pub struct Payload {
+    pub name: String,
+    pub parent_id: Option<String>,
+    /// Creates the folder at the root of this shared drive when there is no parent
+    pub drive_id: Option<String>,
+    pub user_id: Id,
+}
+
+pub async fn execute(
+    user_repository: &impl UserRepositoryTrait,
+    google_drive_service: &impl GoogleDriveServiceTrait,
+    payload: Payload,
+) -> Result<FileInfo, Error> {
+    if payload.name.trim().is_empty() {
+        return Err(Error::InvalidName(
+            "Folder name cannot be empty".to_string(),
+        ));
+    }
+
+    let mut user = match user_repository.find_by_id(payload.user_id).await {
+        Ok(user) => user,
+        Err(user_repository::Error::NotFound) => {
+            return Err(Error::NotFound("User not found".to_string()))
+        }
+        Err(err) => return Err(Error::ConnectionError(err.to_string())),
+    };
+
+    let parent_id = folder_or_root(payload.parent_id, payload.drive_id.as_deref());
+    google_session::with_access_token(
+        user_repository,
+        google_drive_service,
+        &mut user,
+        |access_token| {
+            google_drive_service.create_folder(
+                access_token,
+                payload.name.trim(),
+                parent_id.as_deref(),
+            )
+        },
+    )
+    .await
+    .map_err(Error::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::application::ports::mocks::{self, MockGoogleDriveService, MockUserRepository};
+
+    #[tokio::test]
+    async fn test_create_at_drive_root() {
+        let user = mocks::user();
+        let user_id = user.id;
+        let google_drive_service = MockGoogleDriveService::with_files(Vec::new());
+
+        let result = execute(
+            &MockUserRepository::with_user(user),
+            &google_drive_service,
+            Payload {
+                name: "Contratos".to_string(),
+                parent_id: None,
+                drive_id: Some("drive".to_string()),
+                user_id,
+            },
+        )
+        .await;
+
+        assert!(result.is_ok());
+        assert_eq!(google_drive_service.file_names("drive"), vec!["Contratos"]);
+    }
+}
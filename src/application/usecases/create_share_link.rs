@@ -0,0 +1,94 @@
+use std::fmt::Display;
+
+use chrono::Duration;
+
+use crate::{
+    application::{
+        ports::{
+            google_drive_service::{self, GoogleDriveServiceTrait},
+            share_link_repository::ShareLinkRepositoryTrait,
+            user_repository::{self, UserRepositoryTrait},
+        },
+        usecases::google_session,
+    },
+    domain::{entities::share_link::ShareLink, value_objects::id::Id},
+};
+
+pub enum Error {
+    NotFound(String),
+    InvalidLink(String),
+    GoogleUnauthenticated,
+    ConnectionError(String),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::NotFound(e) => write!(f, "Not found: {}", e),
+            Error::InvalidLink(e) => write!(f, "Invalid link: {}", e),
+            Error::GoogleUnauthenticated => {
+                write!(f, "Google session expired, please log in again")
+            }
+            Error::ConnectionError(e) => write!(f, "Connection error: {}", e),
+        }
+    }
+}
+
+pub struct Payload {
+    pub file_id: String,
+    pub user_id: Id,
+    pub ttl: Duration,
+    /// Downloads allowed through the link, `Some(1)` for a single use link
+    pub max_downloads: Option<u32>,
+}
+
+pub async fn execute(
+    user_repository: &impl UserRepositoryTrait,
+    share_link_repository: &impl ShareLinkRepositoryTrait,
+    google_drive_service: &impl GoogleDriveServiceTrait,
+    payload: Payload,
+) -> Result<ShareLink, Error> {
+    if payload.ttl <= Duration::zero() {
+        return Err(Error::InvalidLink(
+            "The link must expire in the future".to_string(),
+        ));
+    }
+    if payload.max_downloads == Some(0) {
+        return Err(Error::InvalidLink(
+            "The link must allow at least one download".to_string(),
+        ));
+    }
+
+    let mut user = match user_repository.find_by_id(payload.user_id).await {
+        Ok(user) => user,
+        Err(user_repository::Error::NotFound) => {
+            return Err(Error::NotFound("User not found".to_string()))
+        }
+        Err(err) => return Err(Error::ConnectionError(err.to_string())),
+    };
+
+    // Links are only handed out for files the user can currently read
+    match google_session::with_access_token(
+        user_repository,
+        google_drive_service,
+        &mut user,
+        |access_token| google_drive_service.get_file(access_token, &payload.file_id),
+    )
+    .await
+    {
+        Ok(_) => (),
+        Err(google_session::Error::Google(google_drive_service::Error::GoogleUnauthenticated)) => {
+            return Err(Error::GoogleUnauthenticated)
+        }
+        Err(google_session::Error::Google(google_drive_service::Error::NotFound(e))) => {
+            return Err(Error::NotFound(e))
+        }
+        Err(err) => return Err(Error::ConnectionError(err.to_string())),
+    };
+
+    let share_link = ShareLink::new(user.id, payload.file_id, payload.ttl, payload.max_downloads);
+    share_link_repository
+        .create(share_link)
+        .await
+        .map_err(|err| Error::ConnectionError(err.to_string()))
+}
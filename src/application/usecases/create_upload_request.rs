@@ -0,0 +1,127 @@
+use std::fmt::Display;
+
+use chrono::Duration;
+
+use crate::{
+    application::{
+        ports::{
+            google_drive_service::{self, GoogleDriveServiceTrait},
+            upload_request_repository::UploadRequestRepositoryTrait,
+            user_repository::{self, UserRepositoryTrait},
+        },
+        usecases::google_session,
+    },
+    domain::{
+        entities::upload_request::UploadRequest,
+        value_objects::{file_type::FileTypePolicy, id::Id},
+    },
+};
+
+pub enum Error {
+    NotFound(String),
+    InvalidRequest(String),
+    GoogleUnauthenticated,
+    ConnectionError(String),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::NotFound(e) => write!(f, "Not found: {}", e),
+            Error::InvalidRequest(e) => write!(f, "Invalid upload request: {}", e),
+            Error::GoogleUnauthenticated => {
+                write!(f, "Google session expired, please log in again")
+            }
+            Error::ConnectionError(e) => write!(f, "Connection error: {}", e),
+        }
+    }
+}
+
+pub struct Payload {
+    pub folder_id: String,
+    pub user_id: Id,
+    pub ttl: Duration,
+    pub max_files: Option<u32>,
+    /// MIME types accepted, every type in `server_types` when empty
+    pub allowed_types: Vec<String>,
+    pub max_size: Option<u64>,
+    /// Types the server accepts, a request can only narrow them down
+    pub server_types: FileTypePolicy,
+}
+
+pub async fn execute(
+    user_repository: &impl UserRepositoryTrait,
+    upload_request_repository: &impl UploadRequestRepositoryTrait,
+    google_drive_service: &impl GoogleDriveServiceTrait,
+    payload: Payload,
+) -> Result<UploadRequest, Error> {
+    if payload.ttl <= Duration::zero() {
+        return Err(Error::InvalidRequest(
+            "The request must expire in the future".to_string(),
+        ));
+    }
+    if payload.max_files == Some(0) || payload.max_size == Some(0) {
+        return Err(Error::InvalidRequest(
+            "The request must accept at least one file".to_string(),
+        ));
+    }
+    let allowed_types: Vec<String> = payload
+        .allowed_types
+        .iter()
+        .map(|x| x.trim().to_lowercase())
+        .collect();
+    if let Some(mime_type) = allowed_types
+        .iter()
+        .find(|x| payload.server_types.find_by_mime_type(x).is_none())
+    {
+        return Err(Error::InvalidRequest(format!(
+            "{} is not an allowed file type",
+            mime_type
+        )));
+    }
+
+    let mut user = match user_repository.find_by_id(payload.user_id).await {
+        Ok(user) => user,
+        Err(user_repository::Error::NotFound) => {
+            return Err(Error::NotFound("User not found".to_string()))
+        }
+        Err(err) => return Err(Error::ConnectionError(err.to_string())),
+    };
+
+    let folder = match google_session::with_access_token(
+        user_repository,
+        google_drive_service,
+        &mut user,
+        |access_token| google_drive_service.get_file(access_token, &payload.folder_id),
+    )
+    .await
+    {
+        Ok(folder) => folder,
+        Err(google_session::Error::Google(google_drive_service::Error::GoogleUnauthenticated)) => {
+            return Err(Error::GoogleUnauthenticated)
+        }
+        Err(google_session::Error::Google(google_drive_service::Error::NotFound(e))) => {
+            return Err(Error::NotFound(e))
+        }
+        Err(err) => return Err(Error::ConnectionError(err.to_string())),
+    };
+    if !folder.is_folder() {
+        return Err(Error::InvalidRequest(format!(
+            "{} is not a folder",
+            folder.name
+        )));
+    }
+
+    let upload_request = UploadRequest::new(
+        user.id,
+        folder.id,
+        payload.ttl,
+        payload.max_files,
+        allowed_types,
+        payload.max_size,
+    );
+    upload_request_repository
+        .create(upload_request)
+        .await
+        .map_err(|err| Error::ConnectionError(err.to_string()))
+}
@@ -0,0 +1,108 @@
+use std::fmt::Display;
+
+use crate::{
+    application::{
+        ports::{
+            change_cursor_repository::ChangeCursorRepositoryTrait,
+            google_drive_service::GoogleDriveServiceTrait,
+            user_repository::{self, UserRepositoryTrait},
+            webhook_repository::WebhookRepositoryTrait,
+            webhook_sender::WebhookSenderTrait,
+        },
+        usecases::google_session,
+    },
+    domain::{
+        entities::{change_cursor::ChangeCursor, webhook::Webhook},
+        value_objects::id::Id,
+    },
+};
+
+pub enum Error {
+    NotFound(String),
+    NotAFolder(String),
+    InvalidWebhook(String),
+    GoogleUnauthenticated,
+    ConnectionError(String),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::NotFound(e) => write!(f, "Not found: {}", e),
+            Error::NotAFolder(e) => write!(f, "Not a folder: {}", e),
+            Error::InvalidWebhook(e) => write!(f, "Invalid webhook: {}", e),
+            Error::GoogleUnauthenticated => {
+                write!(f, "Google session expired, please log in again")
+            }
+            Error::ConnectionError(e) => write!(f, "Connection error: {}", e),
+        }
+    }
+}
+
+google_session::impl_from_session_error!(Error);
+
+pub struct Payload {
+    pub url: String,
+    /// Folders to watch, the whole Drive when empty
+    pub folder_ids: Vec<String>,
+    pub user_id: Id,
+}
+
+pub async fn execute(
+    user_repository: &impl UserRepositoryTrait,
+    webhook_repository: &impl WebhookRepositoryTrait,
+    change_cursor_repository: &impl ChangeCursorRepositoryTrait,
+    google_drive_service: &impl GoogleDriveServiceTrait,
+    webhook_sender: &impl WebhookSenderTrait,
+    payload: Payload,
+) -> Result<Webhook, Error> {
+    if let Err(err) = webhook_sender.check_url(&payload.url).await {
+        return Err(Error::InvalidWebhook(err.to_string()));
+    }
+
+    let mut user = match user_repository.find_by_id(payload.user_id).await {
+        Ok(user) => user,
+        Err(user_repository::Error::NotFound) => {
+            return Err(Error::NotFound("User not found".to_string()))
+        }
+        Err(err) => return Err(Error::ConnectionError(err.to_string())),
+    };
+
+    for folder_id in &payload.folder_ids {
+        let folder = google_session::with_access_token(
+            user_repository,
+            google_drive_service,
+            &mut user,
+            |access_token| google_drive_service.get_file(access_token, folder_id),
+        )
+        .await?;
+        if !folder.is_folder() {
+            return Err(Error::NotAFolder(folder.name));
+        }
+    }
+
+    // Changes are read from the moment the first webhook of the user is registered
+    if change_cursor_repository
+        .find_by_user(user.id)
+        .await
+        .is_err()
+    {
+        let page_token = google_session::with_access_token(
+            user_repository,
+            google_drive_service,
+            &mut user,
+            |access_token| google_drive_service.get_start_page_token(access_token),
+        )
+        .await?;
+        change_cursor_repository
+            .create(ChangeCursor::new(user.id, page_token))
+            .await
+            .map_err(|err| Error::ConnectionError(err.to_string()))?;
+    }
+
+    let webhook = Webhook::new(user.id, payload.url, payload.folder_ids);
+    webhook_repository
+        .create(webhook)
+        .await
+        .map_err(|err| Error::ConnectionError(err.to_string()))
+}
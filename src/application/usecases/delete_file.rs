@@ -0,0 +1,80 @@
+use std::fmt::Display;
+
+use crate::{
+    application::{
+        ports::{
+            google_drive_service::GoogleDriveServiceTrait,
+            user_repository::{self, UserRepositoryTrait},
+        },
+        usecases::{drive_scope, google_session},
+    },
+    domain::value_objects::{file_info::FileInfo, id::Id},
+};
+
+pub enum Error {
+    NotFound(String),
+    GoogleUnauthenticated,
+    ConnectionError(String),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::NotFound(e) => write!(f, "Not found: {}", e),
+            Error::GoogleUnauthenticated => {
+                write!(f, "Google session expired, please log in again")
+            }
+            Error::ConnectionError(e) => write!(f, "Connection error: {}", e),
+        }
+    }
+}
+
+google_session::impl_from_session_error!(Error);
+
+pub struct Payload {
+    pub file_id: String,
+    pub drive_id: Option<String>,
+    pub user_id: Id,
+}
+
+pub async fn execute(
+    user_repository: &impl UserRepositoryTrait,
+    google_drive_service: &impl GoogleDriveServiceTrait,
+    payload: Payload,
+) -> Result<FileInfo, Error> {
+    let mut user = match user_repository.find_by_id(payload.user_id).await {
+        Ok(user) => user,
+        Err(user_repository::Error::NotFound) => {
+            return Err(Error::NotFound("User not found".to_string()))
+        }
+        Err(err) => return Err(Error::ConnectionError(err.to_string())),
+    };
+
+    drive_scope::check(
+        user_repository,
+        google_drive_service,
+        &mut user,
+        &payload.file_id,
+        payload.drive_id.as_deref(),
+    )
+    .await?;
+
+    // Drive returns nothing on delete, so the metadata is read first to report what was removed
+    let file = google_session::with_access_token(
+        user_repository,
+        google_drive_service,
+        &mut user,
+        |access_token| google_drive_service.get_file(access_token, &payload.file_id),
+    )
+    .await?;
+
+    google_session::with_access_token(
+        user_repository,
+        google_drive_service,
+        &mut user,
+        |access_token| google_drive_service.delete_file(access_token, &payload.file_id),
+    )
+    .await?;
+
+    Ok(file)
+}
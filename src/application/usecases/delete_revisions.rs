@@ -0,0 +1,154 @@
+use std::fmt::Display;
+
+use crate::{
+    application::{
+        ports::{
+            google_drive_service::{self, GoogleDriveServiceTrait},
+            user_repository::{self, UserRepositoryTrait},
+        },
+        usecases::{drive_scope, google_session},
+    },
+    domain::value_objects::{id::Id, revision::Revision},
+};
+
+pub enum Error {
+    NotFound(String),
+    InvalidRequest(String),
+    GoogleUnauthenticated,
+    ConnectionError(String),
+    /// Deleting a revision failed after the `deleted` ones were gone
+    PartiallyDeleted {
+        deleted: Vec<String>,
+        error: Box<Error>,
+    },
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::NotFound(e) => write!(f, "Not found: {}", e),
+            Error::InvalidRequest(e) => write!(f, "Invalid request: {}", e),
+            Error::GoogleUnauthenticated => {
+                write!(f, "Google session expired, please log in again")
+            }
+            Error::ConnectionError(e) => write!(f, "Connection error: {}", e),
+            Error::PartiallyDeleted { deleted, error } => write!(
+                f,
+                "{} (revisions {} were deleted before)",
+                error,
+                deleted.join(", ")
+            ),
+        }
+    }
+}
+
+pub enum RevisionSelection {
+    Ids(Vec<String>),
+    /// Every revision but the latest ones, pinned revisions excepted
+    OlderThanLatest(usize),
+}
+
+pub struct Payload {
+    pub file_id: String,
+    pub drive_id: Option<String>,
+    pub selection: RevisionSelection,
+    pub user_id: Id,
+}
+
+/// Deletes old revisions of a file and returns the ids of the deleted ones. The current
+/// revision is the file content itself and can't be deleted.
+pub async fn execute(
+    user_repository: &impl UserRepositoryTrait,
+    google_drive_service: &impl GoogleDriveServiceTrait,
+    payload: Payload,
+) -> Result<Vec<String>, Error> {
+    let mut user = match user_repository.find_by_id(payload.user_id).await {
+        Ok(user) => user,
+        Err(user_repository::Error::NotFound) => {
+            return Err(Error::NotFound("User not found".to_string()))
+        }
+        Err(err) => return Err(Error::ConnectionError(err.to_string())),
+    };
+
+    drive_scope::check(
+        user_repository,
+        google_drive_service,
+        &mut user,
+        &payload.file_id,
+        payload.drive_id.as_deref(),
+    )
+    .await
+    .map_err(map_error)?;
+
+    let revisions = google_session::with_access_token(
+        user_repository,
+        google_drive_service,
+        &mut user,
+        |access_token| google_drive_service.list_revisions(access_token, &payload.file_id),
+    )
+    .await
+    .map_err(map_error)?;
+
+    let revision_ids: Vec<String> = match payload.selection {
+        RevisionSelection::Ids(ids) => {
+            let current = revisions.iter().max_by_key(|x| x.modified_at);
+            for id in &ids {
+                if !revisions.iter().any(|x| &x.id == id) {
+                    return Err(Error::NotFound(format!("Revision {} not found", id)));
+                }
+                if current.is_some_and(|x| &x.id == id) {
+                    return Err(Error::InvalidRequest(
+                        "The current revision cannot be deleted".to_string(),
+                    ));
+                }
+            }
+            ids
+        }
+        RevisionSelection::OlderThanLatest(keep) => Revision::older_than_latest(&revisions, keep)
+            .into_iter()
+            .map(|x| x.id.clone())
+            .collect(),
+    };
+
+    let mut deleted = Vec::with_capacity(revision_ids.len());
+    for revision_id in revision_ids {
+        let result = google_session::with_access_token(
+            user_repository,
+            google_drive_service,
+            &mut user,
+            |access_token| {
+                google_drive_service.delete_revision(access_token, &payload.file_id, &revision_id)
+            },
+        )
+        .await;
+        match result {
+            Ok(()) => deleted.push(revision_id),
+            Err(err) if deleted.is_empty() => return Err(map_error(err)),
+            Err(err) => {
+                return Err(Error::PartiallyDeleted {
+                    deleted,
+                    error: Box::new(map_error(err)),
+                })
+            }
+        }
+    }
+
+    Ok(deleted)
+}
+
+fn map_error(err: google_session::Error) -> Error {
+    match err {
+        google_session::Error::Google(google_drive_service::Error::GoogleUnauthenticated) => {
+            Error::GoogleUnauthenticated
+        }
+        google_session::Error::Google(google_drive_service::Error::NotFound(e)) => {
+            Error::NotFound(e)
+        }
+        // Revisions of native files can't be deleted one by one
+        google_session::Error::Google(google_drive_service::Error::InvalidRequest(e))
+        | google_session::Error::Google(google_drive_service::Error::PermissionDenied(e)) => {
+            Error::InvalidRequest(e)
+        }
+        err => Error::ConnectionError(err.to_string()),
+    }
+}
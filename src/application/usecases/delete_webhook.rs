@@ -0,0 +1,85 @@
+use std::fmt::Display;
+
+use crate::{
+    application::{
+        ports::{
+            change_cursor_repository::ChangeCursorRepositoryTrait,
+            file_parents_repository::FileParentsRepositoryTrait,
+            google_drive_service::GoogleDriveServiceTrait,
+            user_repository::UserRepositoryTrait,
+            webhook_repository::{self, WebhookRepositoryTrait},
+        },
+        usecases::google_session,
+    },
+    domain::{entities::webhook::Webhook, value_objects::id::Id},
+};
+
+pub enum Error {
+    NotFound(String),
+    ConnectionError(String),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::NotFound(e) => write!(f, "Not found: {}", e),
+            Error::ConnectionError(e) => write!(f, "Connection error: {}", e),
+        }
+    }
+}
+
+pub struct Payload {
+    pub webhook_id: Id,
+    pub user_id: Id,
+}
+
+pub async fn execute(
+    user_repository: &impl UserRepositoryTrait,
+    webhook_repository: &impl WebhookRepositoryTrait,
+    change_cursor_repository: &impl ChangeCursorRepositoryTrait,
+    file_parents_repository: &impl FileParentsRepositoryTrait,
+    google_drive_service: &impl GoogleDriveServiceTrait,
+    payload: Payload,
+) -> Result<Webhook, Error> {
+    let webhook = match webhook_repository
+        .delete(payload.webhook_id, payload.user_id)
+        .await
+    {
+        Ok(webhook) => webhook,
+        Err(webhook_repository::Error::NotFound) => {
+            return Err(Error::NotFound("Webhook not found".to_string()))
+        }
+        Err(err) => return Err(Error::ConnectionError(err.to_string())),
+    };
+
+    let remaining = webhook_repository
+        .find_by_user(payload.user_id)
+        .await
+        .map_err(|err| Error::ConnectionError(err.to_string()))?;
+    if !remaining.is_empty() {
+        return Ok(webhook);
+    }
+
+    // Nobody is listening anymore, the user's changes stop being read. Stopping the push
+    // channel is best effort, Drive drops it anyway when it expires.
+    if let Ok(cursor) = change_cursor_repository.find_by_user(payload.user_id).await {
+        if let (Some(channel), Ok(mut user)) = (
+            &cursor.channel,
+            user_repository.find_by_id(payload.user_id).await,
+        ) {
+            let _ = google_session::with_access_token(
+                user_repository,
+                google_drive_service,
+                &mut user,
+                |access_token| google_drive_service.stop_channel(access_token, channel),
+            )
+            .await;
+        }
+        let _ = change_cursor_repository.delete(payload.user_id).await;
+    }
+    let _ = file_parents_repository
+        .delete_by_user(payload.user_id)
+        .await;
+
+    Ok(webhook)
+}
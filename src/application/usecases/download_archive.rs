@@ -0,0 +1,315 @@
+use std::fmt::Display;
+
+use crate::{
+    application::{
+        ports::{
+            google_drive_service::{ByteStream, GoogleDriveServiceTrait},
+            user_repository::{self, UserRepositoryTrait},
+        },
+        usecases::{folder_tree, google_session},
+    },
+    domain::{
+        entities::user::User,
+        value_objects::{
+            conflict_policy::free_name, export_format::ExportFormat, file_info::FileInfo,
+            file_type::FileTypePolicy, folder_tree::FolderTree, id::Id,
+            shared_drive::folder_or_root,
+        },
+    },
+};
+
+pub enum Error {
+    NotFound(String),
+    InvalidRequest(String),
+    GoogleUnauthenticated,
+    ConnectionError(String),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::NotFound(e) => write!(f, "Not found: {}", e),
+            Error::InvalidRequest(e) => write!(f, "Invalid request: {}", e),
+            Error::GoogleUnauthenticated => {
+                write!(f, "Google session expired, please log in again")
+            }
+            Error::ConnectionError(e) => write!(f, "Connection error: {}", e),
+        }
+    }
+}
+
+google_session::impl_from_session_error!(Error);
+
+pub struct Payload {
+    /// Folder archived with everything below it
+    pub folder_id: Option<String>,
+    /// The whole shared drive is archived when there is no folder nor files
+    pub drive_id: Option<String>,
+    /// Files and folders archived side by side, when no folder is given
+    pub file_ids: Vec<String>,
+    pub user_id: Id,
+    pub allowed_types: FileTypePolicy,
+    /// Levels below each folder that are archived
+    pub max_depth: u32,
+    /// Folders listed at the same time while walking them
+    pub concurrency: usize,
+}
+
+/// A file or folder of the archive, the content of files is fetched with `open_entry`
+/// when the archive gets to it.
+pub struct ArchiveEntry {
+    /// Path in the archive, folders end with `/`
+    pub path: String,
+    pub file: FileInfo,
+    /// Format native Google files are exported to
+    pub export: Option<ExportFormat>,
+}
+
+pub struct Archive {
+    /// File name for the whole archive
+    pub name: String,
+    pub entries: Vec<ArchiveEntry>,
+    /// Files left out because their type can't be downloaded, or folders past the depth
+    /// limit
+    pub skipped: usize,
+    /// Owner of the files, with the access token the entries are opened with
+    pub user: User,
+}
+
+/// Lists everything that goes into the archive. Nothing is downloaded yet, so a missing
+/// file or an expired session is reported before the archive starts.
+pub async fn execute(
+    user_repository: &impl UserRepositoryTrait,
+    google_drive_service: &impl GoogleDriveServiceTrait,
+    payload: Payload,
+) -> Result<Archive, Error> {
+    let folder_id = match payload.file_ids.is_empty() {
+        true => folder_or_root(payload.folder_id.clone(), payload.drive_id.as_deref()),
+        false => payload.folder_id.clone(),
+    };
+    let (name, roots) = match (&folder_id, payload.file_ids.is_empty()) {
+        (Some(folder_id), true) => {
+            let tree =
+                walk_folder(user_repository, google_drive_service, &payload, folder_id).await?;
+            (tree.file.name.clone(), vec![tree])
+        }
+        (None, false) => {
+            let mut user = find_user(user_repository, payload.user_id).await?;
+            let mut roots = Vec::with_capacity(payload.file_ids.len());
+            for file_id in &payload.file_ids {
+                let file = google_session::with_access_token(
+                    user_repository,
+                    google_drive_service,
+                    &mut user,
+                    |access_token| google_drive_service.get_file(access_token, file_id),
+                )
+                .await?;
+                roots.push(if file.is_folder() {
+                    walk_folder(user_repository, google_drive_service, &payload, file_id).await?
+                } else {
+                    FolderTree::leaf(file)
+                });
+            }
+            ("files".to_string(), roots)
+        }
+        _ => {
+            return Err(Error::InvalidRequest(
+                "Either a folder id or a list of file ids is required".to_string(),
+            ))
+        }
+    };
+
+    let mut entries = Vec::new();
+    let mut skipped = 0;
+    add_entries(
+        roots,
+        "",
+        &payload.allowed_types,
+        &mut entries,
+        &mut skipped,
+    );
+
+    // Looked up last, so it has the token any refresh during the walk left behind
+    let user = find_user(user_repository, payload.user_id).await?;
+    Ok(Archive {
+        name: format!("{}.zip", entry_name(&name)),
+        entries,
+        skipped,
+        user,
+    })
+}
+
+/// Streams the content of a file entry, exporting native Google files.
+pub async fn open_entry(
+    user_repository: &impl UserRepositoryTrait,
+    google_drive_service: &impl GoogleDriveServiceTrait,
+    user: &mut User,
+    entry: &ArchiveEntry,
+) -> Result<ByteStream, Error> {
+    google_session::with_access_token(
+        user_repository,
+        google_drive_service,
+        user,
+        |access_token| async move {
+            match entry.export {
+                Some(format) => {
+                    google_drive_service
+                        .export_file_content(access_token, &entry.file.id, format.mime_type)
+                        .await
+                }
+                None => {
+                    google_drive_service
+                        .download_file_content(access_token, &entry.file.id, None)
+                        .await
+                }
+            }
+        },
+    )
+    .await
+    .map_err(Error::from)
+}
+
+async fn find_user(user_repository: &impl UserRepositoryTrait, user_id: Id) -> Result<User, Error> {
+    match user_repository.find_by_id(user_id).await {
+        Ok(user) => Ok(user),
+        Err(user_repository::Error::NotFound) => Err(Error::NotFound("User not found".to_string())),
+        Err(err) => Err(Error::ConnectionError(err.to_string())),
+    }
+}
+
+async fn walk_folder(
+    user_repository: &impl UserRepositoryTrait,
+    google_drive_service: &impl GoogleDriveServiceTrait,
+    payload: &Payload,
+    folder_id: &str,
+) -> Result<FolderTree, Error> {
+    let tree_payload = folder_tree::Payload {
+        folder_id: Some(folder_id.to_string()),
+        drive_id: None,
+        max_depth: payload.max_depth,
+        concurrency: payload.concurrency,
+        user_id: payload.user_id,
+    };
+    match folder_tree::execute(user_repository, google_drive_service, tree_payload).await {
+        Ok(tree) => Ok(tree),
+        Err(folder_tree::Error::NotFound(e)) => Err(Error::NotFound(e)),
+        Err(err @ folder_tree::Error::NotAFolder(_)) => Err(Error::InvalidRequest(err.to_string())),
+        Err(folder_tree::Error::InvalidRequest(e)) => Err(Error::InvalidRequest(e)),
+        Err(folder_tree::Error::GoogleUnauthenticated) => Err(Error::GoogleUnauthenticated),
+        Err(folder_tree::Error::ConnectionError(e)) => Err(Error::ConnectionError(e)),
+    }
+}
+
+/// Adds the trees to the archive under `prefix`. Names are made unique within each folder
+/// since Drive allows several files with the same name.
+fn add_entries(
+    trees: Vec<FolderTree>,
+    prefix: &str,
+    allowed_types: &FileTypePolicy,
+    entries: &mut Vec<ArchiveEntry>,
+    skipped: &mut usize,
+) {
+    let mut taken = Vec::new();
+    for tree in trees {
+        let file = tree.file;
+        if file.is_folder() {
+            let children = match tree.children {
+                Some(children) => children,
+                None => {
+                    *skipped += 1;
+                    continue;
+                }
+            };
+            let name = free_name(&entry_name(&file.name), &taken);
+            taken.push(name.clone());
+            let path = format!("{}{}/", prefix, name);
+            entries.push(ArchiveEntry {
+                path: path.clone(),
+                file,
+                export: None,
+            });
+            add_entries(children, &path, allowed_types, entries, skipped);
+            continue;
+        }
+
+        let (name, export) = match ExportFormat::supported_by(&file.file_type).first() {
+            // Native files go in as PDF
+            Some(format) if allowed_types.find_by_mime_type(format.mime_type).is_some() => {
+                (format!("{}.{}", file.name, format.name), Some(*format))
+            }
+            None if allowed_types.find_by_mime_type(&file.file_type).is_some() => {
+                (file.name.clone(), None)
+            }
+            _ => {
+                *skipped += 1;
+                continue;
+            }
+        };
+        let name = free_name(&entry_name(&name), &taken);
+        taken.push(name.clone());
+        entries.push(ArchiveEntry {
+            path: format!("{}{}", prefix, name),
+            file,
+            export,
+        });
+    }
+}
+
+/// Drive names can have characters that mean something else in a path.
+fn entry_name(name: &str) -> String {
+    let name = name.replace(['/', '\\'], "_");
+    match name.trim() {
+        "" | "." | ".." => "_".to_string(),
+        _ => name,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::value_objects::file_info::FOLDER_MIME_TYPE;
+
+    fn file(name: &str, file_type: &str) -> FileInfo {
+        FileInfo {
+            id: name.to_string(),
+            name: name.to_string(),
+            file_type: file_type.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_add_entries() {
+        let allowed_types = FileTypePolicy::parse("application/pdf:pdf", 10).unwrap();
+        let trees = vec![FolderTree::folder(
+            file("client", FOLDER_MIME_TYPE),
+            vec![
+                FolderTree::leaf(file("contract.pdf", "application/pdf")),
+                FolderTree::leaf(file("contract.pdf", "application/pdf")),
+                FolderTree::leaf(file("notes", "application/vnd.google-apps.document")),
+                FolderTree::leaf(file("photo.png", "image/png")),
+                FolderTree::leaf(file("a/b", FOLDER_MIME_TYPE)),
+                FolderTree::folder(file("empty", FOLDER_MIME_TYPE), vec![]),
+            ],
+        )];
+
+        let mut entries = Vec::new();
+        let mut skipped = 0;
+        add_entries(trees, "", &allowed_types, &mut entries, &mut skipped);
+
+        let paths: Vec<&str> = entries.iter().map(|x| x.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            [
+                "client/",
+                "client/contract.pdf",
+                "client/contract (1).pdf",
+                "client/notes.pdf",
+                "client/empty/",
+            ]
+        );
+        assert_eq!(entries[3].export.map(|x| x.name), Some("pdf"));
+        // The image type is not allowed and the unwalked folder has no children
+        assert_eq!(skipped, 2);
+    }
+}
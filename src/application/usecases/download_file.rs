@@ -0,0 +1,219 @@
+use std::fmt::Display;
+
+use crate::{
+    application::{
+        ports::{
+            google_drive_service::{ByteStream, FileDownload, GoogleDriveServiceTrait},
+            user_repository::{self, UserRepositoryTrait},
+        },
+        usecases::{drive_scope, google_session},
+    },
+    domain::value_objects::{
+        byte_range::{ByteRange, RangeSpec},
+        download_conditions::DownloadConditions,
+        export_format::ExportFormat,
+        file_info::FileInfo,
+        file_type::FileTypePolicy,
+        id::Id,
+    },
+};
+
+/// Requests with more ranges than this get the whole file, each range costs a Drive request.
+const MAX_RANGES: usize = 8;
+
+pub enum Error {
+    NotFound(String),
+    UnsupportedFileType(String),
+    GoogleUnauthenticated,
+    ConnectionError(String),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::NotFound(e) => write!(f, "Not found: {}", e),
+            Error::UnsupportedFileType(e) => write!(f, "Unsupported file type: {}", e),
+            Error::GoogleUnauthenticated => {
+                write!(f, "Google session expired, please log in again")
+            }
+            Error::ConnectionError(e) => write!(f, "Connection error: {}", e),
+        }
+    }
+}
+
+google_session::impl_from_session_error!(Error);
+
+pub struct Payload {
+    pub file_id: String,
+    pub drive_id: Option<String>,
+    pub user_id: Id,
+    pub ranges: Option<Vec<RangeSpec>>,
+    pub conditions: DownloadConditions,
+    pub allowed_types: FileTypePolicy,
+    /// Format native Google files are exported to, PDF when missing
+    pub format: Option<String>,
+}
+
+pub enum Download {
+    /// The client's copy is current
+    NotModified(FileInfo),
+    Full(FileDownload),
+    Partial(FileInfo, Vec<(ByteRange, ByteStream)>),
+    /// None of the requested ranges overlaps the file
+    RangeNotSatisfiable(FileInfo),
+}
+
+pub async fn execute(
+    user_repository: &impl UserRepositoryTrait,
+    google_drive_service: &impl GoogleDriveServiceTrait,
+    payload: Payload,
+) -> Result<Download, Error> {
+    let mut user = match user_repository.find_by_id(payload.user_id).await {
+        Ok(user) => user,
+        Err(user_repository::Error::NotFound) => {
+            return Err(Error::NotFound("User not found".to_string()))
+        }
+        Err(err) => return Err(Error::ConnectionError(err.to_string())),
+    };
+
+    drive_scope::check(
+        user_repository,
+        google_drive_service,
+        &mut user,
+        &payload.file_id,
+        payload.drive_id.as_deref(),
+    )
+    .await?;
+
+    let file = google_session::with_access_token(
+        user_repository,
+        google_drive_service,
+        &mut user,
+        |access_token| google_drive_service.get_file(access_token, &payload.file_id),
+    )
+    .await?;
+
+    let export_formats = ExportFormat::supported_by(&file.file_type);
+    if !export_formats.is_empty() {
+        let format = match &payload.format {
+            Some(name) => ExportFormat::find(name)
+                .filter(|x| export_formats.contains(x))
+                .ok_or_else(|| {
+                    Error::UnsupportedFileType(format!(
+                        "{} files cannot be exported to {}",
+                        file.file_type, name
+                    ))
+                })?,
+            None => export_formats[0],
+        };
+        if payload
+            .allowed_types
+            .find_by_mime_type(format.mime_type)
+            .is_none()
+        {
+            return Err(Error::UnsupportedFileType(format!(
+                "{} files cannot be downloaded",
+                format.mime_type
+            )));
+        }
+
+        // Exports have no size or checksum, so they are served whole and only the
+        // modification time can validate them
+        let file = FileInfo {
+            name: format!("{}.{}", file.name, format.name),
+            file_type: format.mime_type.to_string(),
+            size: None,
+            md5_checksum: None,
+            ..file
+        };
+        if payload.conditions.is_not_modified(&file) {
+            return Ok(Download::NotModified(file));
+        }
+
+        let content = google_session::with_access_token(
+            user_repository,
+            google_drive_service,
+            &mut user,
+            |access_token| {
+                google_drive_service.export_file_content(
+                    access_token,
+                    &payload.file_id,
+                    format.mime_type,
+                )
+            },
+        )
+        .await?;
+
+        return Ok(Download::Full(FileDownload { file, content }));
+    }
+
+    // Other files are only served as they are stored
+    if let Some(name) = &payload.format {
+        if ExportFormat::find(name).map(|x| x.mime_type) != Some(file.file_type.as_str()) {
+            return Err(Error::UnsupportedFileType(format!(
+                "{} files cannot be converted to {}",
+                file.file_type, name
+            )));
+        }
+    }
+
+    if payload
+        .allowed_types
+        .find_by_mime_type(&file.file_type)
+        .is_none()
+    {
+        return Err(Error::UnsupportedFileType(format!(
+            "{} files cannot be downloaded",
+            file.file_type
+        )));
+    }
+
+    if payload.conditions.is_not_modified(&file) {
+        return Ok(Download::NotModified(file));
+    }
+
+    let ranges = match (payload.ranges, file.size) {
+        (Some(specs), Some(size)) if specs.len() <= MAX_RANGES => {
+            let ranges: Vec<ByteRange> = specs.iter().filter_map(|x| x.resolve(size)).collect();
+            if ranges.is_empty() {
+                return Ok(Download::RangeNotSatisfiable(file));
+            }
+            ranges
+        }
+        _ => Vec::new(),
+    };
+
+    if ranges.is_empty() {
+        let content = google_session::with_access_token(
+            user_repository,
+            google_drive_service,
+            &mut user,
+            |access_token| {
+                google_drive_service.download_file_content(access_token, &payload.file_id, None)
+            },
+        )
+        .await?;
+
+        return Ok(Download::Full(FileDownload { file, content }));
+    }
+
+    let mut parts = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        let content = google_session::with_access_token(
+            user_repository,
+            google_drive_service,
+            &mut user,
+            |access_token| {
+                google_drive_service.download_file_content(
+                    access_token,
+                    &payload.file_id,
+                    Some(range),
+                )
+            },
+        )
+        .await?;
+        parts.push((range, content));
+    }
+
+    Ok(Download::Partial(file, parts))
+}
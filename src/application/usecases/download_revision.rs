@@ -0,0 +1,127 @@
+use std::fmt::Display;
+
+use crate::{
+    application::{
+        ports::{
+            google_drive_service::{FileDownload, GoogleDriveServiceTrait},
+            user_repository::{self, UserRepositoryTrait},
+        },
+        usecases::{drive_scope, google_session},
+    },
+    domain::value_objects::{
+        export_format::ExportFormat, file_info::FileInfo, file_type::FileTypePolicy, id::Id,
+    },
+};
+
+pub enum Error {
+    NotFound(String),
+    UnsupportedFileType(String),
+    GoogleUnauthenticated,
+    ConnectionError(String),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::NotFound(e) => write!(f, "Not found: {}", e),
+            Error::UnsupportedFileType(e) => write!(f, "Unsupported file type: {}", e),
+            Error::GoogleUnauthenticated => {
+                write!(f, "Google session expired, please log in again")
+            }
+            Error::ConnectionError(e) => write!(f, "Connection error: {}", e),
+        }
+    }
+}
+
+google_session::impl_from_session_error!(Error);
+
+pub struct Payload {
+    pub file_id: String,
+    pub drive_id: Option<String>,
+    pub revision_id: String,
+    pub user_id: Id,
+    pub allowed_types: FileTypePolicy,
+}
+
+pub async fn execute(
+    user_repository: &impl UserRepositoryTrait,
+    google_drive_service: &impl GoogleDriveServiceTrait,
+    payload: Payload,
+) -> Result<FileDownload, Error> {
+    let mut user = match user_repository.find_by_id(payload.user_id).await {
+        Ok(user) => user,
+        Err(user_repository::Error::NotFound) => {
+            return Err(Error::NotFound("User not found".to_string()))
+        }
+        Err(err) => return Err(Error::ConnectionError(err.to_string())),
+    };
+
+    drive_scope::check(
+        user_repository,
+        google_drive_service,
+        &mut user,
+        &payload.file_id,
+        payload.drive_id.as_deref(),
+    )
+    .await?;
+
+    let file = google_session::with_access_token(
+        user_repository,
+        google_drive_service,
+        &mut user,
+        |access_token| google_drive_service.get_file(access_token, &payload.file_id),
+    )
+    .await?;
+    let revision = google_session::with_access_token(
+        user_repository,
+        google_drive_service,
+        &mut user,
+        |access_token| {
+            google_drive_service.get_revision(access_token, &payload.file_id, &payload.revision_id)
+        },
+    )
+    .await?;
+
+    // Drive keeps no content of its own for revisions of native files
+    if !ExportFormat::supported_by(&revision.file_type).is_empty() {
+        return Err(Error::UnsupportedFileType(format!(
+            "Revisions of {} files cannot be downloaded",
+            revision.file_type
+        )));
+    }
+    if payload
+        .allowed_types
+        .find_by_mime_type(&revision.file_type)
+        .is_none()
+    {
+        return Err(Error::UnsupportedFileType(format!(
+            "{} files cannot be downloaded",
+            revision.file_type
+        )));
+    }
+
+    let content = google_session::with_access_token(
+        user_repository,
+        google_drive_service,
+        &mut user,
+        |access_token| {
+            google_drive_service.download_revision_content(
+                access_token,
+                &payload.file_id,
+                &payload.revision_id,
+            )
+        },
+    )
+    .await?;
+
+    let file = FileInfo {
+        id: file.id,
+        name: revision.original_file_name.unwrap_or(file.name),
+        file_type: revision.file_type,
+        modified_at: revision.modified_at,
+        size: revision.size,
+        md5_checksum: revision.md5_checksum,
+        ..Default::default()
+    };
+    Ok(FileDownload { file, content })
+}
@@ -0,0 +1,101 @@
+use std::fmt::Display;
+
+use crate::{
+    application::{
+        ports::{
+            google_drive_service::GoogleDriveServiceTrait,
+            share_link_repository::{self, ShareLinkRepositoryTrait},
+            user_repository::UserRepositoryTrait,
+        },
+        usecases::download_file::{self, Download},
+    },
+    domain::{
+        entities::{share_link::ShareLink, token_data::TokenDataError},
+        value_objects::{download_conditions::DownloadConditions, file_type::FileTypePolicy},
+    },
+};
+
+pub enum Error {
+    InvalidLink(String),
+    /// The link was revoked, expired or ran out of downloads
+    Unavailable(String),
+    Download(download_file::Error),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::InvalidLink(e) => write!(f, "Invalid link: {}", e),
+            Error::Unavailable(e) => write!(f, "Link unavailable: {}", e),
+            Error::Download(e) => e.fmt(f),
+        }
+    }
+}
+
+pub struct Payload {
+    pub token: String,
+    pub allowed_types: FileTypePolicy,
+}
+
+pub async fn execute(
+    user_repository: &impl UserRepositoryTrait,
+    share_link_repository: &impl ShareLinkRepositoryTrait,
+    google_drive_service: &impl GoogleDriveServiceTrait,
+    secret: &[u8],
+    payload: Payload,
+) -> Result<Download, Error> {
+    let link_id = match ShareLink::id_from_token(&payload.token, secret) {
+        Ok(link_id) => link_id,
+        Err(TokenDataError::ExpiredToken) => {
+            return Err(Error::Unavailable("link expired".to_string()))
+        }
+        Err(TokenDataError::InvalidData) => {
+            return Err(Error::InvalidLink("bad signature".to_string()))
+        }
+    };
+
+    let share_link = match share_link_repository.register_download(link_id).await {
+        Ok(share_link) => share_link,
+        Err(share_link_repository::Error::NotFound) => {
+            return Err(Error::InvalidLink("unknown link".to_string()))
+        }
+        Err(share_link_repository::Error::Unavailable) => {
+            return Err(Error::Unavailable(
+                "link revoked, expired or used up".to_string(),
+            ))
+        }
+        Err(err) => {
+            return Err(Error::Download(download_file::Error::ConnectionError(
+                err.to_string(),
+            )))
+        }
+    };
+
+    // Every request counts as a download, so links are served whole and unconditionally
+    let result = download_file::execute(
+        user_repository,
+        google_drive_service,
+        download_file::Payload {
+            file_id: share_link.file_id,
+            drive_id: None,
+            user_id: share_link.user_id,
+            ranges: None,
+            conditions: DownloadConditions::default(),
+            allowed_types: payload.allowed_types,
+            format: None,
+        },
+    )
+    .await;
+
+    match result {
+        Ok(download) => Ok(download),
+        Err(err) => {
+            // Counted up front so concurrent requests can't go over the limit, a download
+            // Drive didn't serve is given back
+            if let Err(err) = share_link_repository.release_download(share_link.id).await {
+                eprintln!("Error releasing share link download: {}", err);
+            }
+            Err(Error::Download(err))
+        }
+    }
+}
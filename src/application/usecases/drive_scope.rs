@@ -0,0 +1,100 @@
+use crate::{
+    application::{
+        ports::{
+            google_drive_service::{self, GoogleDriveServiceTrait},
+            user_repository::UserRepositoryTrait,
+        },
+        usecases::google_session,
+    },
+    domain::{entities::user::User, value_objects::file_info::FileField},
+};
+
+/// Checks that a file asked for within the shared drive `drive_id` is in it. A file of
+/// another drive is reported as not found, like Drive does for files the user can't see.
+/// Nothing is checked without a `drive_id`.
+pub async fn check(
+    user_repository: &impl UserRepositoryTrait,
+    google_drive_service: &impl GoogleDriveServiceTrait,
+    user: &mut User,
+    file_id: &str,
+    drive_id: Option<&str>,
+) -> Result<(), google_session::Error> {
+    let drive_id = match drive_id {
+        Some(drive_id) => drive_id,
+        None => return Ok(()),
+    };
+
+    let file = google_session::with_access_token(
+        user_repository,
+        google_drive_service,
+        user,
+        |access_token| {
+            google_drive_service.get_file_fields(
+                access_token,
+                file_id,
+                &[FileField::Id, FileField::DriveId],
+            )
+        },
+    )
+    .await?;
+
+    if file.drive_id.as_deref() == Some(drive_id) {
+        Ok(())
+    } else {
+        Err(google_session::Error::Google(
+            google_drive_service::Error::NotFound(format!(
+                "File {} is not in shared drive {}",
+                file_id, drive_id
+            )),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        application::ports::mocks::{self, MockGoogleDriveService, MockUserRepository},
+        domain::value_objects::file_info::FileInfo,
+    };
+
+    async fn check_file(
+        file_id: &str,
+        drive_id: Option<&str>,
+    ) -> Result<(), google_session::Error> {
+        let mut user = mocks::user();
+        let google_drive_service = MockGoogleDriveService::with_files(vec![
+            FileInfo {
+                id: "in-drive".to_string(),
+                drive_id: Some("drive".to_string()),
+                ..Default::default()
+            },
+            FileInfo {
+                id: "in-my-drive".to_string(),
+                ..Default::default()
+            },
+        ]);
+        check(
+            &MockUserRepository::with_user(user.clone()),
+            &google_drive_service,
+            &mut user,
+            file_id,
+            drive_id,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_check() {
+        assert!(check_file("in-drive", Some("drive")).await.is_ok());
+        assert!(check_file("in-my-drive", None).await.is_ok());
+        for (file_id, drive_id) in [("in-my-drive", "drive"), ("in-drive", "other")] {
+            assert!(matches!(
+                check_file(file_id, Some(drive_id)).await,
+                Err(google_session::Error::Google(
+                    google_drive_service::Error::NotFound(_)
+                ))
+            ));
+        }
+    }
+}
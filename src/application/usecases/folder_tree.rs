@@ -0,0 +1,186 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+};
+
+use futures::{stream, StreamExt, TryStreamExt};
+
+use crate::{
+    application::{
+        ports::{
+            google_drive_service::{self, GoogleDriveServiceTrait},
+            user_repository::{self, UserRepositoryTrait},
+        },
+        usecases::google_session,
+    },
+    domain::value_objects::{
+        file_info::FileInfo,
+        file_query::{FileQuery, MAX_PAGE_SIZE},
+        folder_tree::FolderTree,
+        id::Id,
+        shared_drive::folder_or_root,
+    },
+};
+
+pub enum Error {
+    NotFound(String),
+    NotAFolder(String),
+    InvalidRequest(String),
+    GoogleUnauthenticated,
+    ConnectionError(String),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::NotFound(e) => write!(f, "Not found: {}", e),
+            Error::NotAFolder(e) => write!(f, "Not a folder: {}", e),
+            Error::InvalidRequest(e) => write!(f, "Invalid request: {}", e),
+            Error::GoogleUnauthenticated => {
+                write!(f, "Google session expired, please log in again")
+            }
+            Error::ConnectionError(e) => write!(f, "Connection error: {}", e),
+        }
+    }
+}
+
+google_session::impl_from_session_error!(Error);
+
+pub struct Payload {
+    /// Defaults to the root of the shared drive when there is a `drive_id`
+    pub folder_id: Option<String>,
+    pub drive_id: Option<String>,
+    /// Levels below the folder to list, folders deeper than this are returned unexpanded
+    pub max_depth: u32,
+    /// Folders listed at the same time
+    pub concurrency: usize,
+    pub user_id: Id,
+}
+
+pub async fn execute(
+    user_repository: &impl UserRepositoryTrait,
+    google_drive_service: &impl GoogleDriveServiceTrait,
+    payload: Payload,
+) -> Result<FolderTree, Error> {
+    let folder_id = match folder_or_root(payload.folder_id, payload.drive_id.as_deref()) {
+        Some(folder_id) => folder_id,
+        None => return Err(Error::InvalidRequest("folder_id is required".to_string())),
+    };
+    let mut user = match user_repository.find_by_id(payload.user_id).await {
+        Ok(user) => user,
+        Err(user_repository::Error::NotFound) => {
+            return Err(Error::NotFound("User not found".to_string()))
+        }
+        Err(err) => return Err(Error::ConnectionError(err.to_string())),
+    };
+
+    let root = google_session::with_access_token(
+        user_repository,
+        google_drive_service,
+        &mut user,
+        |access_token| google_drive_service.get_file(access_token, &folder_id),
+    )
+    .await?;
+
+    if !root.is_folder() {
+        return Err(Error::NotAFolder(root.name));
+    }
+
+    // The tree is walked one level at a time, listing the folders of each level concurrently
+    let mut contents: HashMap<String, Vec<FileInfo>> = HashMap::new();
+    // A folder can have several parents, each one is only listed once
+    let mut visited = HashSet::from([root.id.clone()]);
+    let mut level = vec![root.id.clone()];
+    for _ in 0..payload.max_depth {
+        if level.is_empty() {
+            break;
+        }
+
+        let listed = google_session::with_access_token(
+            user_repository,
+            google_drive_service,
+            &mut user,
+            |access_token| {
+                list_folders(
+                    google_drive_service,
+                    access_token,
+                    &level,
+                    payload.concurrency,
+                )
+            },
+        )
+        .await?;
+
+        let mut next_level = Vec::new();
+        for (folder_id, files) in listed {
+            next_level.extend(
+                files
+                    .iter()
+                    .filter(|x| x.is_folder() && visited.insert(x.id.clone()))
+                    .map(|x| x.id.clone()),
+            );
+            contents.insert(folder_id, files);
+        }
+        level = next_level;
+    }
+
+    Ok(build_tree(root, &mut contents))
+}
+
+async fn list_folders(
+    google_drive_service: &impl GoogleDriveServiceTrait,
+    access_token: String,
+    folder_ids: &[String],
+    concurrency: usize,
+) -> Result<Vec<(String, Vec<FileInfo>)>, google_drive_service::Error> {
+    // Created before they go into the stream, mapping the stream itself makes the handler
+    // future fail the `Send` check
+    let listings: Vec<_> = folder_ids
+        .iter()
+        .map(|folder_id| list_folder(google_drive_service, access_token.clone(), folder_id))
+        .collect();
+
+    let files: Vec<Vec<FileInfo>> = stream::iter(listings)
+        .buffered(concurrency.max(1))
+        .try_collect()
+        .await?;
+
+    Ok(folder_ids.iter().cloned().zip(files).collect())
+}
+
+/// Every file directly inside a folder, going through all the result pages.
+async fn list_folder(
+    google_drive_service: &impl GoogleDriveServiceTrait,
+    access_token: String,
+    folder_id: &str,
+) -> Result<Vec<FileInfo>, google_drive_service::Error> {
+    let mut query = FileQuery {
+        folder_id: folder_id.to_string(),
+        page_size: Some(MAX_PAGE_SIZE),
+        ..Default::default()
+    };
+    let mut files = Vec::new();
+    loop {
+        let page = google_drive_service
+            .list_files(access_token.clone(), &query)
+            .await?;
+        files.extend(page.files);
+        match page.next_page_token {
+            Some(page_token) => query.page_token = Some(page_token),
+            None => return Ok(files),
+        }
+    }
+}
+
+fn build_tree(file: FileInfo, contents: &mut HashMap<String, Vec<FileInfo>>) -> FolderTree {
+    match contents.remove(&file.id) {
+        Some(children) => {
+            let children = children
+                .into_iter()
+                .map(|x| build_tree(x, contents))
+                .collect();
+            FolderTree::folder(file, children)
+        }
+        None => FolderTree::leaf(file),
+    }
+}
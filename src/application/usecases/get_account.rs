@@ -0,0 +1,72 @@
+use std::fmt::Display;
+
+use crate::{
+    application::{
+        ports::{
+            google_drive_service::{self, GoogleDriveServiceTrait},
+            user_repository::{self, UserRepositoryTrait},
+        },
+        usecases::google_session,
+    },
+    domain::{
+        entities::user::User,
+        value_objects::{drive_about::DriveAbout, id::Id},
+    },
+};
+
+pub enum Error {
+    NotFound(String),
+    GoogleUnauthenticated,
+    ConnectionError(String),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::NotFound(e) => write!(f, "Not found: {}", e),
+            Error::GoogleUnauthenticated => {
+                write!(f, "Google session expired, please log in again")
+            }
+            Error::ConnectionError(e) => write!(f, "Connection error: {}", e),
+        }
+    }
+}
+
+pub struct Payload {
+    pub user_id: Id,
+}
+
+/// The stored user along with what Drive reports about their account.
+pub struct Account {
+    pub user: User,
+    pub about: DriveAbout,
+}
+
+pub async fn execute(
+    user_repository: &impl UserRepositoryTrait,
+    google_drive_service: &impl GoogleDriveServiceTrait,
+    payload: Payload,
+) -> Result<Account, Error> {
+    let mut user = match user_repository.find_by_id(payload.user_id).await {
+        Ok(user) => user,
+        Err(user_repository::Error::NotFound) => {
+            return Err(Error::NotFound("User not found".to_string()))
+        }
+        Err(err) => return Err(Error::ConnectionError(err.to_string())),
+    };
+
+    match google_session::with_access_token(
+        user_repository,
+        google_drive_service,
+        &mut user,
+        |access_token| google_drive_service.get_about(access_token),
+    )
+    .await
+    {
+        Ok(about) => Ok(Account { user, about }),
+        Err(google_session::Error::Google(google_drive_service::Error::GoogleUnauthenticated)) => {
+            Err(Error::GoogleUnauthenticated)
+        }
+        Err(err) => Err(Error::ConnectionError(err.to_string())),
+    }
+}
@@ -1,13 +1,42 @@
-use crate::application::ports::google_drive_service::GoogleDriveServiceTrait;
+use chrono::Duration;
+
+use crate::{
+    application::ports::{
+        auth_request_repository::AuthRequestRepositoryTrait,
+        google_drive_service::GoogleDriveServiceTrait,
+    },
+    domain::entities::auth_request::AuthRequest,
+};
 
 pub enum Error {
-    NotFound(String),
     ConnectionError(String),
 }
 
-pub async fn execute(google_drive_service: &impl GoogleDriveServiceTrait) -> Result<String, Error> {
-    match google_drive_service.get_google_auth_url().await {
-        Ok(files_id) => Ok(files_id.0),
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::ConnectionError(e) => write!(f, "Connection error: {}", e),
+        }
+    }
+}
+
+pub async fn execute(
+    auth_request_repository: &impl AuthRequestRepositoryTrait,
+    google_drive_service: &impl GoogleDriveServiceTrait,
+    state_ttl: Duration,
+    force_consent: bool,
+) -> Result<String, Error> {
+    let auth_url = match google_drive_service
+        .get_google_auth_url(force_consent)
+        .await
+    {
+        Ok(auth_url) => auth_url,
+        Err(err) => return Err(Error::ConnectionError(err.to_string())),
+    };
+
+    let auth_request = AuthRequest::new(auth_url.state, auth_url.pkce_verifier, state_ttl);
+    match auth_request_repository.create(auth_request).await {
+        Ok(_) => Ok(auth_url.url),
         Err(err) => Err(Error::ConnectionError(err.to_string())),
     }
 }
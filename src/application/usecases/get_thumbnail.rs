@@ -0,0 +1,163 @@
+use std::fmt::Display;
+
+use crate::{
+    application::{
+        ports::{
+            google_drive_service::GoogleDriveServiceTrait,
+            pdf_renderer::{self, PdfRendererTrait},
+            thumbnail_repository::{self, ThumbnailRepositoryTrait},
+            user_repository::{self, UserRepositoryTrait},
+        },
+        usecases::{google_session, pdf_content},
+    },
+    domain::{
+        entities::thumbnail::Thumbnail,
+        value_objects::{file_info::FileField, id::Id},
+    },
+};
+
+pub enum Error {
+    NotFound(String),
+    /// Drive has no thumbnail for the file and it can't be rendered here
+    NoPreview(String),
+    GoogleUnauthenticated,
+    ConnectionError(String),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::NotFound(e) => write!(f, "Not found: {}", e),
+            Error::NoPreview(e) => write!(f, "No preview available: {}", e),
+            Error::GoogleUnauthenticated => {
+                write!(f, "Google session expired, please log in again")
+            }
+            Error::ConnectionError(e) => write!(f, "Connection error: {}", e),
+        }
+    }
+}
+
+google_session::impl_from_session_error!(Error);
+
+pub struct Payload {
+    pub file_id: String,
+    pub user_id: Id,
+    /// Defaults to `thumbnail::DEFAULT_WIDTH`, kept within the allowed widths
+    pub width: Option<u32>,
+    /// Largest PDF downloaded to render its first page, in bytes
+    pub max_pdf_size: u64,
+}
+
+/// Thumbnail of the file, Drive's own when it has one, otherwise the first page of a PDF
+/// rendered here. Thumbnails are cached by file, checksum and width; files without a
+/// checksum, such as Google documents, always ask Drive.
+pub async fn execute(
+    user_repository: &impl UserRepositoryTrait,
+    thumbnail_repository: &impl ThumbnailRepositoryTrait,
+    google_drive_service: &impl GoogleDriveServiceTrait,
+    pdf_renderer: &impl PdfRendererTrait,
+    payload: Payload,
+) -> Result<Thumbnail, Error> {
+    let mut user = match user_repository.find_by_id(payload.user_id).await {
+        Ok(user) => user,
+        Err(user_repository::Error::NotFound) => {
+            return Err(Error::NotFound("User not found".to_string()))
+        }
+        Err(err) => return Err(Error::ConnectionError(err.to_string())),
+    };
+
+    let file_id = payload.file_id.as_str();
+    let fields = [
+        FileField::Id,
+        FileField::FileType,
+        FileField::Size,
+        FileField::Md5Checksum,
+        FileField::ThumbnailLink,
+    ];
+    let file = google_session::with_access_token(
+        user_repository,
+        google_drive_service,
+        &mut user,
+        |access_token| google_drive_service.get_file_fields(access_token, file_id, &fields),
+    )
+    .await?;
+
+    let width = Thumbnail::width(payload.width);
+    if let Some(md5_checksum) = &file.md5_checksum {
+        match thumbnail_repository
+            .find(&file.id, md5_checksum, width)
+            .await
+        {
+            Ok(thumbnail) => return Ok(thumbnail),
+            Err(thumbnail_repository::Error::NotFound) => {}
+            // The cache is only a shortcut, the thumbnail is made again without it
+            Err(err) => eprintln!("Error reading thumbnail cache: {}", err),
+        }
+    }
+
+    let (content_type, content) = match &file.thumbnail_link {
+        Some(thumbnail_link) => {
+            let image = google_session::with_access_token(
+                user_repository,
+                google_drive_service,
+                &mut user,
+                |access_token| {
+                    google_drive_service.download_thumbnail(access_token, thumbnail_link, width)
+                },
+            )
+            .await?;
+            (image.content_type, image.content)
+        }
+        None if file.file_type == pdf_content::PDF => {
+            let content = google_session::with_access_token(
+                user_repository,
+                google_drive_service,
+                &mut user,
+                |access_token| {
+                    pdf_content::download(
+                        google_drive_service,
+                        access_token,
+                        &file,
+                        payload.max_pdf_size,
+                    )
+                },
+            )
+            .await?
+            .ok_or_else(|| {
+                Error::NoPreview(format!(
+                    "PDF files are rendered up to {} bytes",
+                    payload.max_pdf_size
+                ))
+            })?;
+            let image = pdf_renderer
+                .render_first_page(content, width)
+                .await
+                .map_err(|err| match err {
+                    pdf_renderer::Error::InvalidDocument(e) => Error::NoPreview(e),
+                    err => Error::ConnectionError(err.to_string()),
+                })?;
+            ("image/png".to_string(), image)
+        }
+        None => {
+            return Err(Error::NoPreview(format!(
+                "Drive has no thumbnail for this {} file",
+                file.file_type
+            )))
+        }
+    };
+
+    let cached = file.md5_checksum.is_some();
+    let thumbnail = Thumbnail::new(
+        file.id,
+        file.md5_checksum.unwrap_or_default(),
+        width,
+        content_type,
+        content,
+    );
+    if cached {
+        if let Err(err) = thumbnail_repository.save(&thumbnail).await {
+            eprintln!("Error caching thumbnail: {}", err);
+        }
+    }
+    Ok(thumbnail)
+}
@@ -0,0 +1,110 @@
+use std::future::Future;
+
+use crate::{
+    application::ports::{
+        google_drive_service::{self, GoogleDriveServiceTrait},
+        user_repository::{self, UserRepositoryTrait},
+    },
+    domain::entities::user::User,
+};
+
+#[derive(Debug)]
+pub enum Error {
+    Google(google_drive_service::Error),
+    Repository(user_repository::Error),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Google(e) => write!(f, "{}", e),
+            Error::Repository(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+/// Implements `From<google_session::Error>` for a use case error that has `NotFound(String)`,
+/// `GoogleUnauthenticated` and `ConnectionError(String)` variants, so `?` can be used on
+/// `with_access_token`.
+macro_rules! impl_from_session_error {
+    ($error:ty) => {
+        impl From<$crate::application::usecases::google_session::Error> for $error {
+            fn from(err: $crate::application::usecases::google_session::Error) -> Self {
+                use $crate::application::{
+                    ports::google_drive_service::Error as GoogleError,
+                    usecases::google_session::Error as SessionError,
+                };
+
+                match err {
+                    SessionError::Google(GoogleError::GoogleUnauthenticated) => {
+                        Self::GoogleUnauthenticated
+                    }
+                    SessionError::Google(GoogleError::NotFound(e)) => Self::NotFound(e),
+                    err => Self::ConnectionError(err.to_string()),
+                }
+            }
+        }
+    };
+}
+pub(crate) use impl_from_session_error;
+
+/// Runs `operation` with the user's Google access token.
+///
+/// The token is refreshed up front when it has expired, and once more if Google
+/// still rejects it. Every refreshed token is written back to the user repository
+/// and to `user`, so the same user can be passed to later calls.
+pub async fn with_access_token<T, F, Fut>(
+    user_repository: &impl UserRepositoryTrait,
+    google_drive_service: &impl GoogleDriveServiceTrait,
+    user: &mut User,
+    operation: F,
+) -> Result<T, Error>
+where
+    F: Fn(String) -> Fut,
+    Fut: Future<Output = Result<T, google_drive_service::Error>>,
+{
+    let mut refreshed = false;
+    if user.is_access_token_expired() && user.refresh_token.is_some() {
+        refresh(user_repository, google_drive_service, user).await?;
+        refreshed = true;
+    }
+
+    match operation(user.access_token.clone()).await {
+        Err(google_drive_service::Error::GoogleUnauthenticated)
+            if !refreshed && user.refresh_token.is_some() =>
+        {
+            refresh(user_repository, google_drive_service, user).await?;
+            operation(user.access_token.clone())
+                .await
+                .map_err(Error::Google)
+        }
+        result => result.map_err(Error::Google),
+    }
+}
+
+async fn refresh(
+    user_repository: &impl UserRepositoryTrait,
+    google_drive_service: &impl GoogleDriveServiceTrait,
+    user: &mut User,
+) -> Result<(), Error> {
+    let refresh_token = match &user.refresh_token {
+        Some(refresh_token) => refresh_token.clone(),
+        None => {
+            return Err(Error::Google(
+                google_drive_service::Error::GoogleUnauthenticated,
+            ))
+        }
+    };
+
+    let token = google_drive_service
+        .refresh_google_token(refresh_token)
+        .await
+        .map_err(Error::Google)?;
+    user.set_google_token(token);
+
+    *user = user_repository
+        .update(user.clone())
+        .await
+        .map_err(Error::Repository)?;
+    Ok(())
+}
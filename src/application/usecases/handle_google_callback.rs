@@ -1,5 +1,6 @@
 use crate::{
     application::ports::{
+        auth_request_repository::{self, AuthRequestRepositoryTrait},
         google_drive_service::GoogleDriveServiceTrait,
         user_repository::{self, UserRepositoryTrait},
     },
@@ -9,55 +10,90 @@ use crate::{
     },
 };
 
+pub enum Error {
+    InvalidState(String),
+    /// Google sent no refresh token and none is stored, the user has to consent again
+    ConsentRequired,
+    ConnectionError(String),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::InvalidState(e) => write!(f, "Invalid state: {}", e),
+            Error::ConsentRequired => write!(f, "Consent required"),
+            Error::ConnectionError(e) => write!(f, "Connection error: {}", e),
+        }
+    }
+}
+
 pub struct Payload {
     pub code: String,
+    pub state: String,
 }
 
 pub async fn execute(
     user_repository: &impl UserRepositoryTrait,
+    auth_request_repository: &impl AuthRequestRepositoryTrait,
     google_drive_service: &impl GoogleDriveServiceTrait,
     secret: &[u8],
     payload: Payload,
-) -> Result<String, String> {
-    let access_token = match google_drive_service
-        .handle_google_callback(payload.code)
+) -> Result<String, Error> {
+    let auth_request = match auth_request_repository.consume(&payload.state).await {
+        Ok(auth_request) => auth_request,
+        Err(auth_request_repository::Error::NotFound) => {
+            return Err(Error::InvalidState("unknown state".to_string()))
+        }
+        Err(auth_request_repository::Error::AlreadyUsed) => {
+            return Err(Error::InvalidState("state already used".to_string()))
+        }
+        Err(err) => return Err(Error::ConnectionError(err.to_string())),
+    };
+    if auth_request.is_expired() {
+        return Err(Error::InvalidState("state expired".to_string()));
+    }
+
+    let token = match google_drive_service
+        .handle_google_callback(payload.code, auth_request.pkce_verifier)
         .await
     {
-        Ok(access_token) => access_token,
-        Err(err) => return Err(err.to_string()),
+        Ok(token) => token,
+        Err(err) => return Err(Error::ConnectionError(err.to_string())),
     };
 
     let email = match google_drive_service
-        .get_google_email(access_token.clone())
+        .get_google_email(token.access_token.clone())
         .await
     {
         Ok(email) => email,
-        Err(err) => return Err(err.to_string()),
+        Err(err) => return Err(Error::ConnectionError(err.to_string())),
     };
 
     let user = match user_repository.find_by_email(&email).await {
-        Ok(user) => {
-            let user = User {
-                access_token,
-                updated_at: chrono::Utc::now(),
-                ..user
-            };
+        Ok(mut user) => {
+            if user.refresh_token.is_none() && token.refresh_token.is_none() {
+                return Err(Error::ConsentRequired);
+            }
+            user.set_google_token(token);
 
             user_repository
                 .update(user)
                 .await
-                .map_err(|x| x.to_string())?
+                .map_err(|x| Error::ConnectionError(x.to_string()))?
         }
         Err(user_repository::Error::NotFound) => {
-            let email = Email::new(email).map_err(|x| x.to_string())?;
-            let user = User::new(email, access_token);
+            if token.refresh_token.is_none() {
+                return Err(Error::ConsentRequired);
+            }
+            let email = Email::new(email).map_err(Error::ConnectionError)?;
+            let user = User::new(email, token);
             user_repository
                 .create(user.clone())
                 .await
-                .map_err(|x| x.to_string())?;
+                .map_err(|x| Error::ConnectionError(x.to_string()))?;
             user
         }
-        Err(err) => return Err(err.to_string()),
+        Err(err) => return Err(Error::ConnectionError(err.to_string())),
     };
 
     Ok(TokenData::new(&user.id).token(secret))
@@ -0,0 +1,96 @@
+use std::fmt::Display;
+
+use crate::{
+    application::{
+        ports::{
+            google_drive_service::{self, GoogleDriveServiceTrait},
+            user_repository::{self, UserRepositoryTrait},
+        },
+        usecases::{drive_scope, google_session},
+    },
+    domain::value_objects::{id::Id, revision::Revision},
+};
+
+pub enum Error {
+    NotFound(String),
+    InvalidRequest(String),
+    GoogleUnauthenticated,
+    ConnectionError(String),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::NotFound(e) => write!(f, "Not found: {}", e),
+            Error::InvalidRequest(e) => write!(f, "Invalid request: {}", e),
+            Error::GoogleUnauthenticated => {
+                write!(f, "Google session expired, please log in again")
+            }
+            Error::ConnectionError(e) => write!(f, "Connection error: {}", e),
+        }
+    }
+}
+
+google_session::impl_from_session_error!(Error);
+
+pub struct Payload {
+    pub file_id: String,
+    pub drive_id: Option<String>,
+    pub revision_id: String,
+    /// `false` unpins the revision, Drive may then delete it when it gets old
+    pub keep_forever: bool,
+    pub user_id: Id,
+}
+
+pub async fn execute(
+    user_repository: &impl UserRepositoryTrait,
+    google_drive_service: &impl GoogleDriveServiceTrait,
+    payload: Payload,
+) -> Result<Revision, Error> {
+    let mut user = match user_repository.find_by_id(payload.user_id).await {
+        Ok(user) => user,
+        Err(user_repository::Error::NotFound) => {
+            return Err(Error::NotFound("User not found".to_string()))
+        }
+        Err(err) => return Err(Error::ConnectionError(err.to_string())),
+    };
+
+    drive_scope::check(
+        user_repository,
+        google_drive_service,
+        &mut user,
+        &payload.file_id,
+        payload.drive_id.as_deref(),
+    )
+    .await?;
+
+    match google_session::with_access_token(
+        user_repository,
+        google_drive_service,
+        &mut user,
+        |access_token| {
+            google_drive_service.update_revision(
+                access_token,
+                &payload.file_id,
+                &payload.revision_id,
+                payload.keep_forever,
+            )
+        },
+    )
+    .await
+    {
+        Ok(revision) => Ok(revision),
+        Err(google_session::Error::Google(google_drive_service::Error::GoogleUnauthenticated)) => {
+            Err(Error::GoogleUnauthenticated)
+        }
+        Err(google_session::Error::Google(google_drive_service::Error::NotFound(e))) => {
+            Err(Error::NotFound(e))
+        }
+        // Native files can't be pinned, and Drive limits how many revisions a file can pin
+        Err(google_session::Error::Google(google_drive_service::Error::InvalidRequest(e)))
+        | Err(google_session::Error::Google(google_drive_service::Error::PermissionDenied(e))) => {
+            Err(Error::InvalidRequest(e))
+        }
+        Err(err) => Err(Error::ConnectionError(err.to_string())),
+    }
+}
@@ -1,25 +1,39 @@
 use crate::{
-    application::ports::{
-        google_drive_service::{self, GoogleDriveServiceTrait},
-        user_repository::UserRepositoryTrait,
+    application::{
+        ports::{
+            google_drive_service::{self, GoogleDriveServiceTrait},
+            user_repository::UserRepositoryTrait,
+        },
+        usecases::google_session,
+    },
+    domain::value_objects::{
+        file_query::FileQuery, files_page::FilesPage, id::Id, shared_drive::folder_or_root,
     },
-    domain::value_objects::{file_info::FileInfo, id::Id},
 };
 
 pub enum Error {
+    InvalidRequest(String),
+    GoogleUnauthenticated,
     ConnectionError(String),
 }
 
 impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
+            Error::InvalidRequest(e) => write!(f, "Invalid request: {}", e),
+            Error::GoogleUnauthenticated => {
+                write!(f, "Google session expired, please log in again")
+            }
             Error::ConnectionError(e) => write!(f, "Connection error: {}", e),
         }
     }
 }
 
 pub struct Payload {
-    pub path: String,
+    /// Folder listed, the root of the shared drive `query.drive_id` when `None`
+    pub folder_id: Option<String>,
+    /// What to list, its `folder_id` is set from the one above
+    pub query: FileQuery,
     pub user_id: Id,
 }
 
@@ -27,20 +41,85 @@ pub async fn execute(
     user_repository: &impl UserRepositoryTrait,
     google_drive_service: &impl GoogleDriveServiceTrait,
     payload: Payload,
-) -> Result<Vec<FileInfo>, Error> {
-    let user = match user_repository.find_by_id(payload.user_id).await {
+) -> Result<FilesPage, Error> {
+    let mut query = payload.query;
+    query.folder_id = match folder_or_root(payload.folder_id, query.drive_id.as_deref()) {
+        Some(folder_id) => folder_id,
+        None => return Err(Error::InvalidRequest("folder_id is required".to_string())),
+    };
+
+    let mut user = match user_repository.find_by_id(payload.user_id).await {
         Ok(user) => user,
         Err(err) => return Err(Error::ConnectionError(err.to_string())),
     };
 
-    match google_drive_service
-        .list_files(user.access_token, &payload.path)
-        .await
+    match google_session::with_access_token(
+        user_repository,
+        google_drive_service,
+        &mut user,
+        |access_token| google_drive_service.list_files(access_token, &query),
+    )
+    .await
     {
-        Ok(files_id) => Ok(files_id),
-        Err(google_drive_service::Error::GoogleUnauthenticated) => Err(Error::ConnectionError(
-            "Google access token expired".to_string(),
-        )),
+        Ok(files_page) => Ok(files_page),
+        Err(google_session::Error::Google(google_drive_service::Error::GoogleUnauthenticated)) => {
+            Err(Error::GoogleUnauthenticated)
+        }
         Err(err) => Err(Error::ConnectionError(err.to_string())),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        application::ports::mocks::{self, MockGoogleDriveService, MockUserRepository},
+        domain::value_objects::file_info::FileInfo,
+    };
+
+    fn file(name: &str, parent: &str) -> FileInfo {
+        FileInfo {
+            id: name.to_string(),
+            name: name.to_string(),
+            parents: Some(vec![parent.to_string()]),
+            ..Default::default()
+        }
+    }
+
+    async fn list(folder_id: Option<&str>, drive_id: Option<&str>) -> Result<FilesPage, Error> {
+        let user = mocks::user();
+        let user_id = user.id;
+        let google_drive_service =
+            MockGoogleDriveService::with_files(vec![file("a.pdf", "drive"), file("b.pdf", "root")]);
+        execute(
+            &MockUserRepository::with_user(user),
+            &google_drive_service,
+            Payload {
+                folder_id: folder_id.map(|x| x.to_string()),
+                query: FileQuery {
+                    drive_id: drive_id.map(|x| x.to_string()),
+                    ..Default::default()
+                },
+                user_id,
+            },
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_list_drive_root() {
+        let page = list(None, Some("drive")).await.ok().unwrap();
+
+        let names: Vec<_> = page.files.iter().map(|x| x.name.as_str()).collect();
+        assert_eq!(names, vec!["a.pdf"]);
+    }
+
+    #[tokio::test]
+    async fn test_folder_required() {
+        assert!(list(Some("root"), None).await.is_ok());
+        assert!(matches!(
+            list(None, None).await,
+            Err(Error::InvalidRequest(_))
+        ));
+    }
+}
@@ -0,0 +1,91 @@
+use std::fmt::Display;
+
+use crate::{
+    application::{
+        ports::{
+            google_drive_service::{self, GoogleDriveServiceTrait},
+            user_repository::{self, UserRepositoryTrait},
+        },
+        usecases::{drive_scope, google_session},
+    },
+    domain::value_objects::{id::Id, permission::Permission},
+};
+
+pub enum Error {
+    NotFound(String),
+    InvalidPermission(String),
+    Forbidden(String),
+    GoogleUnauthenticated,
+    ConnectionError(String),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::NotFound(e) => write!(f, "Not found: {}", e),
+            Error::InvalidPermission(e) => write!(f, "Invalid permission: {}", e),
+            Error::Forbidden(e) => write!(f, "Forbidden: {}", e),
+            Error::GoogleUnauthenticated => {
+                write!(f, "Google session expired, please log in again")
+            }
+            Error::ConnectionError(e) => write!(f, "Connection error: {}", e),
+        }
+    }
+}
+
+pub struct Payload {
+    pub file_id: String,
+    pub drive_id: Option<String>,
+    pub user_id: Id,
+}
+
+pub async fn execute(
+    user_repository: &impl UserRepositoryTrait,
+    google_drive_service: &impl GoogleDriveServiceTrait,
+    payload: Payload,
+) -> Result<Vec<Permission>, Error> {
+    let mut user = match user_repository.find_by_id(payload.user_id).await {
+        Ok(user) => user,
+        Err(user_repository::Error::NotFound) => {
+            return Err(Error::NotFound("User not found".to_string()))
+        }
+        Err(err) => return Err(Error::ConnectionError(err.to_string())),
+    };
+
+    drive_scope::check(
+        user_repository,
+        google_drive_service,
+        &mut user,
+        &payload.file_id,
+        payload.drive_id.as_deref(),
+    )
+    .await
+    .map_err(map_error)?;
+
+    google_session::with_access_token(
+        user_repository,
+        google_drive_service,
+        &mut user,
+        |access_token| google_drive_service.list_permissions(access_token, &payload.file_id),
+    )
+    .await
+    .map_err(map_error)
+}
+
+fn map_error(err: google_session::Error) -> Error {
+    match err {
+        google_session::Error::Google(google_drive_service::Error::GoogleUnauthenticated) => {
+            Error::GoogleUnauthenticated
+        }
+        google_session::Error::Google(google_drive_service::Error::NotFound(e)) => {
+            Error::NotFound(e)
+        }
+        google_session::Error::Google(google_drive_service::Error::PermissionDenied(e)) => {
+            Error::Forbidden(e)
+        }
+        google_session::Error::Google(google_drive_service::Error::InvalidRequest(e)) => {
+            Error::InvalidPermission(e)
+        }
+        err => Error::ConnectionError(err.to_string()),
+    }
+}
@@ -0,0 +1,32 @@
+use std::fmt::Display;
+
+use crate::{
+    application::ports::share_link_repository::ShareLinkRepositoryTrait,
+    domain::{entities::share_link::ShareLink, value_objects::id::Id},
+};
+
+pub enum Error {
+    ConnectionError(String),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::ConnectionError(e) => write!(f, "Connection error: {}", e),
+        }
+    }
+}
+
+pub struct Payload {
+    pub user_id: Id,
+}
+
+pub async fn execute(
+    share_link_repository: &impl ShareLinkRepositoryTrait,
+    payload: Payload,
+) -> Result<Vec<ShareLink>, Error> {
+    share_link_repository
+        .find_by_user(payload.user_id)
+        .await
+        .map_err(|err| Error::ConnectionError(err.to_string()))
+}
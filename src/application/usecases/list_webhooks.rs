@@ -0,0 +1,32 @@
+use std::fmt::Display;
+
+use crate::{
+    application::ports::webhook_repository::WebhookRepositoryTrait,
+    domain::{entities::webhook::Webhook, value_objects::id::Id},
+};
+
+pub enum Error {
+    ConnectionError(String),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::ConnectionError(e) => write!(f, "Connection error: {}", e),
+        }
+    }
+}
+
+pub struct Payload {
+    pub user_id: Id,
+}
+
+pub async fn execute(
+    webhook_repository: &impl WebhookRepositoryTrait,
+    payload: Payload,
+) -> Result<Vec<Webhook>, Error> {
+    webhook_repository
+        .find_by_user(payload.user_id)
+        .await
+        .map_err(|err| Error::ConnectionError(err.to_string()))
+}
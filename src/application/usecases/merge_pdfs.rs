@@ -0,0 +1,173 @@
+use std::fmt::Display;
+
+use futures::{future, stream};
+
+use crate::{
+    application::{
+        ports::{
+            google_drive_service::GoogleDriveServiceTrait,
+            pdf_editor::{self, PdfEditorTrait, PdfPart},
+            user_repository::{self, UserRepositoryTrait},
+        },
+        usecases::{google_session, pdf_content, upload_file},
+    },
+    domain::value_objects::{
+        conflict_policy::ConflictPolicy, file_info::FileInfo, file_type::FileTypePolicy, id::Id,
+        page_range::PageRanges,
+    },
+};
+
+pub enum Error {
+    NotFound(String),
+    InvalidRequest(String),
+    TooLarge(String),
+    GoogleUnauthenticated,
+    ConnectionError(String),
+    Upload(upload_file::Error),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::NotFound(e) => write!(f, "Not found: {}", e),
+            Error::InvalidRequest(e) => write!(f, "Invalid request: {}", e),
+            Error::TooLarge(e) => write!(f, "Too large: {}", e),
+            Error::GoogleUnauthenticated => {
+                write!(f, "Google session expired, please log in again")
+            }
+            Error::ConnectionError(e) => write!(f, "Connection error: {}", e),
+            Error::Upload(e) => e.fmt(f),
+        }
+    }
+}
+
+google_session::impl_from_session_error!(Error);
+
+/// A Drive file to take pages from, all of them when `pages` is `None`.
+pub struct MergeSource {
+    pub file_id: String,
+    pub pages: Option<PageRanges>,
+}
+
+pub struct Payload {
+    pub sources: Vec<MergeSource>,
+    pub file_name: String,
+    pub user_id: Id,
+    /// Folder of the first source when `None`
+    pub folder_id: Option<String>,
+    pub conflict_policy: ConflictPolicy,
+    pub allowed_types: FileTypePolicy,
+    /// Most files that can be merged at once
+    pub max_files: usize,
+    /// Largest total size of the sources, in bytes. They are held in memory
+    pub max_size: u64,
+}
+
+/// Merges the pages of the sources, in order, into a new PDF uploaded like `upload_file`
+/// does.
+pub async fn execute(
+    user_repository: &impl UserRepositoryTrait,
+    google_drive_service: &impl GoogleDriveServiceTrait,
+    pdf_editor: &impl PdfEditorTrait,
+    payload: Payload,
+) -> Result<FileInfo, Error> {
+    if payload.sources.is_empty() {
+        return Err(Error::InvalidRequest("no file to merge".to_string()));
+    }
+    if payload.sources.len() > payload.max_files {
+        return Err(Error::TooLarge(format!(
+            "up to {} files can be merged at once",
+            payload.max_files
+        )));
+    }
+
+    let mut user = match user_repository.find_by_id(payload.user_id).await {
+        Ok(user) => user,
+        Err(user_repository::Error::NotFound) => {
+            return Err(Error::NotFound("User not found".to_string()))
+        }
+        Err(err) => return Err(Error::ConnectionError(err.to_string())),
+    };
+
+    let mut parts = Vec::with_capacity(payload.sources.len());
+    let mut folder_id = payload.folder_id;
+    let mut remaining = payload.max_size;
+    for source in payload.sources {
+        let file_id = source.file_id.as_str();
+        let file = google_session::with_access_token(
+            user_repository,
+            google_drive_service,
+            &mut user,
+            |access_token| google_drive_service.get_file(access_token, file_id),
+        )
+        .await?;
+        if file.file_type != pdf_content::PDF {
+            return Err(Error::InvalidRequest(format!("{} is not a PDF", file.name)));
+        }
+        if folder_id.is_none() {
+            folder_id = file.parents.as_ref().and_then(|x| x.first().cloned());
+        }
+
+        let file = &file;
+        let content = google_session::with_access_token(
+            user_repository,
+            google_drive_service,
+            &mut user,
+            |access_token| {
+                pdf_content::download(google_drive_service, access_token, file, remaining)
+            },
+        )
+        .await?
+        .ok_or_else(|| {
+            Error::TooLarge(format!(
+                "the files to merge can add up to {} bytes",
+                payload.max_size
+            ))
+        })?;
+        remaining -= content.len() as u64;
+        parts.push(PdfPart {
+            content,
+            pages: source.pages,
+        });
+    }
+
+    // Looked up before combining, so a full Drive fails without doing the work
+    let space = upload_file::available_space(
+        user_repository,
+        google_drive_service,
+        payload.user_id,
+        folder_id.as_deref(),
+    )
+    .await
+    .map_err(Error::Upload)?;
+    if space.remaining == Some(0) {
+        return Err(Error::Upload(upload_file::Error::QuotaExceeded(
+            "Google Drive storage is full".to_string(),
+        )));
+    }
+
+    let content = pdf_editor.combine(&parts).await.map_err(map_editor_error)?;
+    upload_file::execute(
+        user_repository,
+        google_drive_service,
+        upload_file::Payload {
+            file_name: pdf_content::file_name(&payload.file_name),
+            user_id: payload.user_id,
+            content: Box::pin(stream::once(future::ready(Ok(content)))),
+            allowed_types: payload.allowed_types,
+            parent_id: folder_id,
+            drive_id: None,
+            conflict_policy: payload.conflict_policy,
+            space: Some(space),
+        },
+    )
+    .await
+    .map_err(Error::Upload)
+}
+
+fn map_editor_error(err: pdf_editor::Error) -> Error {
+    match err {
+        pdf_editor::Error::InvalidDocument(e) => Error::InvalidRequest(e),
+        err => Error::ConnectionError(err.to_string()),
+    }
+}
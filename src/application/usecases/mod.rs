@@ -1,5 +1,47 @@
-pub mod download_pdf;
+pub mod copy_file;
+pub mod create_folder;
+pub mod create_share_link;
+pub mod create_upload_request;
+pub mod create_webhook;
+pub mod delete_file;
+pub mod delete_revisions;
+pub mod delete_webhook;
+pub mod download_archive;
+pub mod download_file;
+pub mod download_revision;
+pub mod download_shared_file;
+pub mod drive_scope;
+pub mod folder_tree;
+pub mod get_account;
 pub mod get_google_auth_url;
+pub mod get_thumbnail;
+pub mod google_session;
+pub mod grant_permission;
 pub mod handle_google_callback;
+pub mod keep_revision;
+pub mod list_drives;
 pub mod list_files;
-pub mod upload_pdf;
+pub mod list_permissions;
+pub mod list_revisions;
+pub mod list_share_links;
+pub mod list_upload_requests;
+pub mod list_webhooks;
+pub mod merge_pdfs;
+pub mod move_file;
+pub mod pdf_content;
+pub mod read_pdf;
+pub mod rename_file;
+pub mod restore_file;
+pub mod revoke_permission;
+pub mod revoke_share_link;
+pub mod search_files;
+pub mod split_pdf;
+pub mod store_pdf_properties;
+pub mod sync_changes;
+pub mod trash_file;
+pub mod update_permission;
+pub mod upload_file;
+pub mod upload_files;
+pub mod upload_to_request;
+pub mod verify_change_notification;
+pub mod watch_changes;
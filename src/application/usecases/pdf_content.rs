@@ -0,0 +1,53 @@
+use bytes::{Bytes, BytesMut};
+use futures::StreamExt;
+
+use crate::{
+    application::ports::google_drive_service::{self, GoogleDriveServiceTrait},
+    domain::value_objects::file_info::FileInfo,
+};
+
+pub const PDF: &str = "application/pdf";
+
+/// The name with a `.pdf` extension, which is what uploads find the type from.
+pub fn file_name(name: &str) -> String {
+    if name.to_lowercase().ends_with(".pdf") {
+        name.to_string()
+    } else {
+        format!("{}.pdf", name)
+    }
+}
+
+/// The whole content of a file, for the PDF tools that need all of it at once. `None`
+/// when it is larger than `max_size`, which is checked before and while downloading.
+pub async fn download(
+    google_drive_service: &impl GoogleDriveServiceTrait,
+    access_token: String,
+    file: &FileInfo,
+    max_size: u64,
+) -> Result<Option<Bytes>, google_drive_service::Error> {
+    if file.size.is_some_and(|x| x > max_size) {
+        return Ok(None);
+    }
+    let mut stream = google_drive_service
+        .download_file_content(access_token, &file.id, None)
+        .await?;
+    let mut content = BytesMut::new();
+    while let Some(chunk) = stream.next().await {
+        content.extend_from_slice(&chunk?);
+        if content.len() as u64 > max_size {
+            return Ok(None);
+        }
+    }
+    Ok(Some(content.freeze()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_name() {
+        assert_eq!(file_name("packet"), "packet.pdf");
+        assert_eq!(file_name("packet.PDF"), "packet.PDF");
+    }
+}
@@ -0,0 +1,161 @@
+use std::fmt::Display;
+
+use bytes::Bytes;
+
+use crate::{
+    application::{
+        ports::{
+            google_drive_service::GoogleDriveServiceTrait,
+            pdf_reader::{self, PdfReaderTrait},
+            user_repository::{self, UserRepositoryTrait},
+        },
+        usecases::{google_session, pdf_content},
+    },
+    domain::value_objects::{
+        id::Id,
+        pdf_document::{PdfDocument, PdfInfo, PdfPage},
+    },
+};
+
+pub enum Error {
+    NotFound(String),
+    /// The file is not a PDF, or one that can't be read
+    InvalidRequest(String),
+    TooLarge(String),
+    GoogleUnauthenticated,
+    ConnectionError(String),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::NotFound(e) => write!(f, "Not found: {}", e),
+            Error::InvalidRequest(e) => write!(f, "Invalid request: {}", e),
+            Error::TooLarge(e) => write!(f, "Too large: {}", e),
+            Error::GoogleUnauthenticated => {
+                write!(f, "Google session expired, please log in again")
+            }
+            Error::ConnectionError(e) => write!(f, "Connection error: {}", e),
+        }
+    }
+}
+
+google_session::impl_from_session_error!(Error);
+
+pub struct Payload {
+    pub file_id: String,
+    pub user_id: Id,
+    /// Largest PDF that can be read, in bytes. It is held in memory
+    pub max_size: u64,
+}
+
+/// Information and text of every page of a PDF stored in Drive.
+pub async fn execute(
+    user_repository: &impl UserRepositoryTrait,
+    google_drive_service: &impl GoogleDriveServiceTrait,
+    pdf_reader: &impl PdfReaderTrait,
+    payload: Payload,
+) -> Result<PdfDocument, Error> {
+    let mut user = match user_repository.find_by_id(payload.user_id).await {
+        Ok(user) => user,
+        Err(user_repository::Error::NotFound) => {
+            return Err(Error::NotFound("User not found".to_string()))
+        }
+        Err(err) => return Err(Error::ConnectionError(err.to_string())),
+    };
+
+    let file_id = payload.file_id.as_str();
+    let file = google_session::with_access_token(
+        user_repository,
+        google_drive_service,
+        &mut user,
+        |access_token| google_drive_service.get_file(access_token, file_id),
+    )
+    .await?;
+    if file.file_type != pdf_content::PDF {
+        return Err(Error::InvalidRequest(format!("{} is not a PDF", file.name)));
+    }
+
+    let file = &file;
+    let content = google_session::with_access_token(
+        user_repository,
+        google_drive_service,
+        &mut user,
+        |access_token| {
+            pdf_content::download(google_drive_service, access_token, file, payload.max_size)
+        },
+    )
+    .await?
+    .ok_or_else(|| {
+        Error::TooLarge(format!(
+            "PDF files can be read up to {} bytes",
+            payload.max_size
+        ))
+    })?;
+
+    read(pdf_reader, content).await
+}
+
+/// Information and text of every page. A PDF that needs a password can't be read, so it
+/// only says that it is encrypted.
+async fn read(pdf_reader: &impl PdfReaderTrait, content: Bytes) -> Result<PdfDocument, Error> {
+    let info = match pdf_reader.info(content.clone()).await {
+        Ok(info) => info,
+        Err(pdf_reader::Error::Encrypted) => {
+            return Ok(PdfDocument {
+                info: PdfInfo {
+                    encrypted: true,
+                    ..Default::default()
+                },
+                pages: Vec::new(),
+            })
+        }
+        Err(err) => return Err(map_reader_error(err)),
+    };
+    let pages = pdf_reader
+        .text(content)
+        .await
+        .map_err(map_reader_error)?
+        .into_iter()
+        .zip(1..)
+        .map(|(text, number)| PdfPage { number, text })
+        .collect();
+    Ok(PdfDocument { info, pages })
+}
+
+fn map_reader_error(err: pdf_reader::Error) -> Error {
+    match err {
+        pdf_reader::Error::InvalidDocument(e) => Error::InvalidRequest(e),
+        err @ pdf_reader::Error::Encrypted => Error::InvalidRequest(err.to_string()),
+        err => Error::ConnectionError(err.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Reads every document as one that needs a password.
+    struct LockedPdfReader;
+
+    impl PdfReaderTrait for LockedPdfReader {
+        async fn info(&self, _content: Bytes) -> Result<PdfInfo, pdf_reader::Error> {
+            Err(pdf_reader::Error::Encrypted)
+        }
+
+        async fn text(&self, _content: Bytes) -> Result<Vec<String>, pdf_reader::Error> {
+            Err(pdf_reader::Error::Encrypted)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_read_encrypted() {
+        let document = read(&LockedPdfReader, Bytes::from_static(b"%PDF-1.7"))
+            .await
+            .ok()
+            .unwrap();
+        assert!(document.info.encrypted);
+        assert_eq!(document.info.page_count, 0);
+        assert!(document.pages.is_empty());
+    }
+}
@@ -0,0 +1,41 @@
+use std::fmt::Display;
+
+use crate::{
+    application::ports::share_link_repository::{self, ShareLinkRepositoryTrait},
+    domain::{entities::share_link::ShareLink, value_objects::id::Id},
+};
+
+pub enum Error {
+    NotFound(String),
+    ConnectionError(String),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::NotFound(e) => write!(f, "Not found: {}", e),
+            Error::ConnectionError(e) => write!(f, "Connection error: {}", e),
+        }
+    }
+}
+
+pub struct Payload {
+    pub link_id: Id,
+    pub user_id: Id,
+}
+
+pub async fn execute(
+    share_link_repository: &impl ShareLinkRepositoryTrait,
+    payload: Payload,
+) -> Result<ShareLink, Error> {
+    match share_link_repository
+        .revoke(payload.link_id, payload.user_id)
+        .await
+    {
+        Ok(share_link) => Ok(share_link),
+        Err(share_link_repository::Error::NotFound) => {
+            Err(Error::NotFound("Share link not found".to_string()))
+        }
+        Err(err) => Err(Error::ConnectionError(err.to_string())),
+    }
+}
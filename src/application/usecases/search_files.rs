@@ -0,0 +1,191 @@
+use std::{collections::HashSet, fmt::Display};
+
+use futures::{stream, StreamExt, TryStreamExt};
+
+use crate::{
+    application::{
+        ports::{
+            google_drive_service::{self, GoogleDriveServiceTrait},
+            user_repository::{self, UserRepositoryTrait},
+        },
+        usecases::google_session,
+    },
+    domain::value_objects::{
+        file_info::{FileField, FOLDER_MIME_TYPE},
+        file_query::{FileQuery, MAX_PAGE_SIZE},
+        files_page::FilesPage,
+        id::Id,
+        search_query::SearchQuery,
+    },
+};
+
+pub enum Error {
+    NotFound(String),
+    NotAFolder(String),
+    InvalidQuery(String),
+    GoogleUnauthenticated,
+    ConnectionError(String),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::NotFound(e) => write!(f, "Not found: {}", e),
+            Error::NotAFolder(e) => write!(f, "Not a folder: {}", e),
+            Error::InvalidQuery(e) => write!(f, "Invalid query: {}", e),
+            Error::GoogleUnauthenticated => {
+                write!(f, "Google session expired, please log in again")
+            }
+            Error::ConnectionError(e) => write!(f, "Connection error: {}", e),
+        }
+    }
+}
+
+google_session::impl_from_session_error!(Error);
+
+pub struct Payload {
+    pub query: SearchQuery,
+    /// Restricts the search to the files below this folder, at any depth
+    pub folder_id: Option<String>,
+    pub user_id: Id,
+    /// Most folders a search can be scoped to, the folder itself included
+    pub max_folders: usize,
+    /// Folders listed at the same time while collecting the subtree
+    pub concurrency: usize,
+}
+
+pub async fn execute(
+    user_repository: &impl UserRepositoryTrait,
+    google_drive_service: &impl GoogleDriveServiceTrait,
+    payload: Payload,
+) -> Result<FilesPage, Error> {
+    payload.query.validate().map_err(Error::InvalidQuery)?;
+
+    let mut user = match user_repository.find_by_id(payload.user_id).await {
+        Ok(user) => user,
+        Err(user_repository::Error::NotFound) => {
+            return Err(Error::NotFound("User not found".to_string()))
+        }
+        Err(err) => return Err(Error::ConnectionError(err.to_string())),
+    };
+
+    let mut query = payload.query;
+    if let Some(folder_id) = &payload.folder_id {
+        let root = google_session::with_access_token(
+            user_repository,
+            google_drive_service,
+            &mut user,
+            |access_token| google_drive_service.get_file(access_token, folder_id),
+        )
+        .await?;
+
+        if !root.is_folder() {
+            return Err(Error::NotAFolder(root.name));
+        }
+
+        // Drive only matches direct parents, so the search goes through every folder
+        // of the subtree, collected one level at a time
+        let mut visited = HashSet::from([root.id.clone()]);
+        let mut folder_ids = vec![root.id.clone()];
+        let mut level = vec![root.id];
+        while !level.is_empty() {
+            let subfolders = google_session::with_access_token(
+                user_repository,
+                google_drive_service,
+                &mut user,
+                |access_token| {
+                    list_subfolders(
+                        google_drive_service,
+                        access_token,
+                        &level,
+                        query.drive_id.as_deref(),
+                        payload.concurrency,
+                    )
+                },
+            )
+            .await?;
+
+            level = subfolders
+                .into_iter()
+                .filter(|x| visited.insert(x.clone()))
+                .collect();
+            folder_ids.extend(level.iter().cloned());
+            if folder_ids.len() > payload.max_folders {
+                return Err(Error::InvalidQuery(format!(
+                    "The folder has more than {} subfolders, search a narrower one",
+                    payload.max_folders.saturating_sub(1)
+                )));
+            }
+        }
+        query.folder_ids = folder_ids;
+    }
+
+    google_session::with_access_token(
+        user_repository,
+        google_drive_service,
+        &mut user,
+        |access_token| google_drive_service.search_files(access_token, &query),
+    )
+    .await
+    .map_err(Error::from)
+}
+
+async fn list_subfolders(
+    google_drive_service: &impl GoogleDriveServiceTrait,
+    access_token: String,
+    folder_ids: &[String],
+    drive_id: Option<&str>,
+    concurrency: usize,
+) -> Result<Vec<String>, google_drive_service::Error> {
+    let listings: Vec<_> = folder_ids
+        .iter()
+        .map(|folder_id| {
+            list_subfolder_ids(
+                google_drive_service,
+                access_token.clone(),
+                folder_id,
+                drive_id,
+            )
+        })
+        .collect();
+
+    let subfolders: Vec<Vec<String>> = stream::iter(listings)
+        .buffered(concurrency.max(1))
+        .try_collect()
+        .await?;
+
+    Ok(subfolders.into_iter().flatten().collect())
+}
+
+/// Ids of the folders directly inside a folder, going through all the result pages.
+async fn list_subfolder_ids(
+    google_drive_service: &impl GoogleDriveServiceTrait,
+    access_token: String,
+    folder_id: &str,
+    drive_id: Option<&str>,
+) -> Result<Vec<String>, google_drive_service::Error> {
+    let mut query = FileQuery {
+        folder_id: folder_id.to_string(),
+        drive_id: drive_id.map(|x| x.to_string()),
+        mime_type: Some(FOLDER_MIME_TYPE.to_string()),
+        page_size: Some(MAX_PAGE_SIZE),
+        fields: vec![FileField::Id, FileField::Trashed],
+        ..Default::default()
+    };
+    let mut folder_ids = Vec::new();
+    loop {
+        let page = google_drive_service
+            .list_files(access_token.clone(), &query)
+            .await?;
+        folder_ids.extend(
+            page.files
+                .into_iter()
+                .filter(|x| x.trashed != Some(true))
+                .map(|x| x.id),
+        );
+        match page.next_page_token {
+            Some(page_token) => query.page_token = Some(page_token),
+            None => return Ok(folder_ids),
+        }
+    }
+}
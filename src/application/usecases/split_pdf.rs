@@ -0,0 +1,172 @@
+use std::fmt::Display;
+
+use futures::{future, stream};
+
+use crate::{
+    application::{
+        ports::{
+            google_drive_service::GoogleDriveServiceTrait,
+            pdf_editor::{self, PdfEditorTrait, PdfPart},
+            user_repository::{self, UserRepositoryTrait},
+        },
+        usecases::{google_session, pdf_content, upload_file, upload_files},
+    },
+    domain::value_objects::{
+        conflict_policy::ConflictPolicy, file_info::FileInfo, file_type::FileTypePolicy, id::Id,
+        page_range::PageRanges,
+    },
+};
+
+pub enum Error {
+    NotFound(String),
+    InvalidRequest(String),
+    TooLarge(String),
+    GoogleUnauthenticated,
+    ConnectionError(String),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::NotFound(e) => write!(f, "Not found: {}", e),
+            Error::InvalidRequest(e) => write!(f, "Invalid request: {}", e),
+            Error::TooLarge(e) => write!(f, "Too large: {}", e),
+            Error::GoogleUnauthenticated => {
+                write!(f, "Google session expired, please log in again")
+            }
+            Error::ConnectionError(e) => write!(f, "Connection error: {}", e),
+        }
+    }
+}
+
+google_session::impl_from_session_error!(Error);
+
+/// Pages written to a file of their own, named after the source and the pages when
+/// `file_name` is `None`.
+pub struct SplitPart {
+    pub pages: PageRanges,
+    pub file_name: Option<String>,
+}
+
+pub struct Payload {
+    pub file_id: String,
+    pub parts: Vec<SplitPart>,
+    pub user_id: Id,
+    /// Folder of the source when `None`
+    pub folder_id: Option<String>,
+    pub conflict_policy: ConflictPolicy,
+    pub allowed_types: FileTypePolicy,
+    /// Most files a split can write
+    pub max_files: usize,
+    /// Largest source that can be split, in bytes. It is held in memory
+    pub max_size: u64,
+    /// Files sent to Drive at the same time
+    pub concurrency: usize,
+}
+
+/// Writes each part of the PDF to a new file. Every part is made before any is uploaded,
+/// so pages the document doesn't have leave nothing behind; after that a failed upload
+/// doesn't stop the others, and each file gets its own result, in the order of the parts.
+pub async fn execute(
+    user_repository: &impl UserRepositoryTrait,
+    google_drive_service: &impl GoogleDriveServiceTrait,
+    pdf_editor: &impl PdfEditorTrait,
+    payload: Payload,
+) -> Result<Vec<(String, Result<FileInfo, upload_file::Error>)>, Error> {
+    if payload.parts.is_empty() {
+        return Err(Error::InvalidRequest("no pages to split".to_string()));
+    }
+    if payload.parts.len() > payload.max_files {
+        return Err(Error::TooLarge(format!(
+            "a PDF can be split into up to {} files",
+            payload.max_files
+        )));
+    }
+
+    let mut user = match user_repository.find_by_id(payload.user_id).await {
+        Ok(user) => user,
+        Err(user_repository::Error::NotFound) => {
+            return Err(Error::NotFound("User not found".to_string()))
+        }
+        Err(err) => return Err(Error::ConnectionError(err.to_string())),
+    };
+
+    let file_id = payload.file_id.as_str();
+    let file = google_session::with_access_token(
+        user_repository,
+        google_drive_service,
+        &mut user,
+        |access_token| google_drive_service.get_file(access_token, file_id),
+    )
+    .await?;
+    if file.file_type != pdf_content::PDF {
+        return Err(Error::InvalidRequest(format!("{} is not a PDF", file.name)));
+    }
+
+    let file = &file;
+    let content = google_session::with_access_token(
+        user_repository,
+        google_drive_service,
+        &mut user,
+        |access_token| {
+            pdf_content::download(google_drive_service, access_token, file, payload.max_size)
+        },
+    )
+    .await?
+    .ok_or_else(|| {
+        Error::TooLarge(format!(
+            "PDF files can be split up to {} bytes",
+            payload.max_size
+        ))
+    })?;
+
+    let stem = match file.name.rsplit_once('.') {
+        Some((stem, _)) if !stem.is_empty() => stem,
+        _ => file.name.as_str(),
+    };
+    let mut files = Vec::with_capacity(payload.parts.len());
+    for part in payload.parts {
+        let file_name = match part.file_name {
+            Some(file_name) => pdf_content::file_name(&file_name),
+            None => format!("{} (pages {}).pdf", stem, part.pages),
+        };
+        let part = PdfPart {
+            content: content.clone(),
+            pages: Some(part.pages),
+        };
+        let content = pdf_editor
+            .combine(std::slice::from_ref(&part))
+            .await
+            .map_err(map_editor_error)?;
+        files.push(upload_files::FileUpload {
+            file_name,
+            content: Box::pin(stream::once(future::ready(Ok(content)))),
+        });
+    }
+
+    let file_names: Vec<String> = files.iter().map(|x| x.file_name.clone()).collect();
+    let results = upload_files::execute(
+        user_repository,
+        google_drive_service,
+        upload_files::Payload {
+            files,
+            user_id: payload.user_id,
+            allowed_types: payload.allowed_types,
+            parent_id: payload
+                .folder_id
+                .or_else(|| file.parents.as_ref().and_then(|x| x.first().cloned())),
+            drive_id: None,
+            conflict_policy: payload.conflict_policy,
+            concurrency: payload.concurrency,
+        },
+    )
+    .await;
+    Ok(file_names.into_iter().zip(results).collect())
+}
+
+fn map_editor_error(err: pdf_editor::Error) -> Error {
+    match err {
+        pdf_editor::Error::InvalidDocument(e) => Error::InvalidRequest(e),
+        err => Error::ConnectionError(err.to_string()),
+    }
+}
@@ -0,0 +1,88 @@
+use std::fmt::Display;
+
+use bytes::Bytes;
+
+use crate::{
+    application::{
+        ports::{
+            google_drive_service::{self, GoogleDriveServiceTrait},
+            pdf_reader::{self, PdfReaderTrait},
+            user_repository::{self, UserRepositoryTrait},
+        },
+        usecases::google_session,
+    },
+    domain::value_objects::{file_info::FileInfo, id::Id},
+};
+
+pub enum Error {
+    NotFound(String),
+    InvalidDocument(String),
+    GoogleUnauthenticated,
+    ConnectionError(String),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::NotFound(e) => write!(f, "Not found: {}", e),
+            Error::InvalidDocument(e) => write!(f, "Invalid document: {}", e),
+            Error::GoogleUnauthenticated => {
+                write!(f, "Google session expired, please log in again")
+            }
+            Error::ConnectionError(e) => write!(f, "Connection error: {}", e),
+        }
+    }
+}
+
+pub struct Payload {
+    pub file_id: String,
+    pub user_id: Id,
+    /// Content of the file, as it was just uploaded
+    pub content: Bytes,
+}
+
+/// Stores the page count and title of a PDF as app properties of its Drive file, so
+/// listings can show them without opening it.
+pub async fn execute(
+    user_repository: &impl UserRepositoryTrait,
+    google_drive_service: &impl GoogleDriveServiceTrait,
+    pdf_reader: &impl PdfReaderTrait,
+    payload: Payload,
+) -> Result<FileInfo, Error> {
+    let info = pdf_reader
+        .info(payload.content)
+        .await
+        .map_err(|err| match err {
+            pdf_reader::Error::InvalidDocument(e) => Error::InvalidDocument(e),
+            err @ pdf_reader::Error::Encrypted => Error::InvalidDocument(err.to_string()),
+            err => Error::ConnectionError(err.to_string()),
+        })?;
+
+    let mut user = match user_repository.find_by_id(payload.user_id).await {
+        Ok(user) => user,
+        Err(user_repository::Error::NotFound) => {
+            return Err(Error::NotFound("User not found".to_string()))
+        }
+        Err(err) => return Err(Error::ConnectionError(err.to_string())),
+    };
+
+    let file_id = payload.file_id.as_str();
+    let properties = &info.app_properties();
+    match google_session::with_access_token(
+        user_repository,
+        google_drive_service,
+        &mut user,
+        |access_token| google_drive_service.set_app_properties(access_token, file_id, properties),
+    )
+    .await
+    {
+        Ok(file) => Ok(file),
+        Err(google_session::Error::Google(google_drive_service::Error::GoogleUnauthenticated)) => {
+            Err(Error::GoogleUnauthenticated)
+        }
+        Err(google_session::Error::Google(google_drive_service::Error::NotFound(e))) => {
+            Err(Error::NotFound(e))
+        }
+        Err(err) => Err(Error::ConnectionError(err.to_string())),
+    }
+}
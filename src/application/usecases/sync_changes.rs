@@ -0,0 +1,635 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+};
+
+use chrono::Utc;
+use futures::{stream, StreamExt};
+use serde::Serialize;
+
+use crate::{
+    application::{
+        ports::{
+            change_cursor_repository::{self, ChangeCursorRepositoryTrait},
+            file_parents_repository::FileParentsRepositoryTrait,
+            google_drive_service::{self, GoogleDriveServiceTrait},
+            user_repository::{self, UserRepositoryTrait},
+            webhook_repository::WebhookRepositoryTrait,
+            webhook_sender::{Delivery, WebhookSenderTrait},
+        },
+        usecases::google_session,
+    },
+    domain::{
+        entities::{change_cursor::ChangeCursor, user::User, webhook::Webhook},
+        value_objects::{file_change::FileChange, file_info::FileField, id::Id},
+    },
+};
+
+pub enum Error {
+    NotFound(String),
+    GoogleUnauthenticated,
+    ConnectionError(String),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::NotFound(e) => write!(f, "Not found: {}", e),
+            Error::GoogleUnauthenticated => {
+                write!(f, "Google session expired, please log in again")
+            }
+            Error::ConnectionError(e) => write!(f, "Connection error: {}", e),
+        }
+    }
+}
+
+google_session::impl_from_session_error!(Error);
+
+pub struct Payload {
+    pub user_id: Id,
+    /// Webhooks called at the same time
+    pub concurrency: usize,
+}
+
+/// What a sync did, for logging.
+#[derive(Debug, Default)]
+pub struct Summary {
+    pub changes: usize,
+    pub delivered: usize,
+    pub failed: usize,
+}
+
+/// Body of a webhook call.
+#[derive(Serialize)]
+struct WebhookEvent<'a> {
+    webhook_id: Id,
+    changes: Vec<&'a FileChange>,
+}
+
+/// Reads the user's new Drive changes and sends them to their webhooks.
+///
+/// A change is sent to a webhook watching folders when the file is inside one of them, at
+/// any depth, before or after the change. The parents of every changed file are stored,
+/// so a file that is removed or moved out can still be placed where it was.
+///
+/// The cursor is moved past the changes before they are sent, so a batch is sent once
+/// even when a poll and a push notification sync the same user at the same time. A
+/// webhook that still fails after its retries misses the batch.
+pub async fn execute(
+    user_repository: &impl UserRepositoryTrait,
+    webhook_repository: &impl WebhookRepositoryTrait,
+    change_cursor_repository: &impl ChangeCursorRepositoryTrait,
+    file_parents_repository: &impl FileParentsRepositoryTrait,
+    google_drive_service: &impl GoogleDriveServiceTrait,
+    webhook_sender: &impl WebhookSenderTrait,
+    payload: Payload,
+) -> Result<Summary, Error> {
+    let cursor = match change_cursor_repository.find_by_user(payload.user_id).await {
+        Ok(cursor) => cursor,
+        Err(change_cursor_repository::Error::NotFound) => {
+            return Err(Error::NotFound("Change cursor not found".to_string()))
+        }
+        Err(err) => return Err(Error::ConnectionError(err.to_string())),
+    };
+    let webhooks = webhook_repository
+        .find_by_user(payload.user_id)
+        .await
+        .map_err(|err| Error::ConnectionError(err.to_string()))?;
+    if webhooks.is_empty() {
+        return Ok(Summary::default());
+    }
+
+    let mut user = match user_repository.find_by_id(payload.user_id).await {
+        Ok(user) => user,
+        Err(user_repository::Error::NotFound) => {
+            return Err(Error::NotFound("User not found".to_string()))
+        }
+        Err(err) => return Err(Error::ConnectionError(err.to_string())),
+    };
+
+    let (changes, page_token) = google_session::with_access_token(
+        user_repository,
+        google_drive_service,
+        &mut user,
+        |access_token| list_changes(google_drive_service, access_token, &cursor.page_token),
+    )
+    .await?;
+    if page_token == cursor.page_token {
+        return Ok(Summary::default());
+    }
+
+    let file_ids: Vec<String> = changes.iter().map(|x| x.file_id.clone()).collect();
+    let previous = file_parents_repository
+        .find(user.id, &file_ids)
+        .await
+        .map_err(|err| Error::ConnectionError(err.to_string()))?;
+    // Parents after the changes, `None` for the files that are gone. The last change of a
+    // file wins
+    let current: HashMap<String, Option<Vec<String>>> = changes
+        .iter()
+        .map(|x| {
+            (
+                x.file_id.clone(),
+                x.file.as_ref().map(|_| x.parents().to_vec()),
+            )
+        })
+        .collect();
+
+    let mut tree: HashMap<String, Vec<String>> = current
+        .iter()
+        .filter_map(|(file_id, parents)| Some((file_id.clone(), parents.clone()?)))
+        .collect();
+    let mut fetched = HashMap::new();
+    if webhooks.iter().any(|x| !x.folder_ids.is_empty()) {
+        let parents = changes
+            .iter()
+            .flat_map(|x| {
+                previous
+                    .get(&x.file_id)
+                    .into_iter()
+                    .flatten()
+                    .chain(x.parents())
+            })
+            .cloned()
+            .collect();
+        fetched = resolve_ancestors(
+            user_repository,
+            file_parents_repository,
+            google_drive_service,
+            &mut user,
+            &mut tree,
+            parents,
+        )
+        .await?;
+    }
+    let folders: Vec<HashSet<String>> = changes
+        .iter()
+        .map(|x| {
+            let parents = previous.get(&x.file_id).into_iter().flatten();
+            folders_above(&tree, parents.chain(x.parents()))
+        })
+        .collect();
+
+    let advanced = ChangeCursor {
+        page_token,
+        updated_at: Utc::now(),
+        ..cursor.clone()
+    };
+    match change_cursor_repository
+        .advance(advanced, &cursor.page_token)
+        .await
+    {
+        Ok(_) => (),
+        // Another sync read the same changes first and sends them
+        Err(change_cursor_repository::Error::Outdated) => return Ok(Summary::default()),
+        Err(err) => return Err(Error::ConnectionError(err.to_string())),
+    }
+
+    // Kept for the next changes, losing them only makes those harder to place
+    let mut saved = fetched;
+    let mut removed = Vec::new();
+    for (file_id, parents) in current {
+        match parents {
+            Some(parents) => {
+                saved.insert(file_id, parents);
+            }
+            None => removed.push(file_id),
+        }
+    }
+    if let Err(err) = file_parents_repository.save(user.id, saved).await {
+        eprintln!("Error storing file parents: {}", err);
+    }
+    if !removed.is_empty() {
+        if let Err(err) = file_parents_repository.delete(user.id, &removed).await {
+            eprintln!("Error deleting file parents: {}", err);
+        }
+    }
+
+    let deliveries: Vec<Delivery> = webhooks
+        .iter()
+        .filter_map(|webhook| delivery(webhook, &changes, &folders))
+        .collect();
+    let sends: Vec<_> = deliveries
+        .iter()
+        .map(|delivery| webhook_sender.send(delivery))
+        .collect();
+    let results: Vec<_> = stream::iter(sends)
+        .buffer_unordered(payload.concurrency.max(1))
+        .collect()
+        .await;
+
+    let failed = results.iter().filter(|x| x.is_err()).count();
+    Ok(Summary {
+        changes: changes.len(),
+        delivered: results.len() - failed,
+        failed,
+    })
+}
+
+/// Every change after `page_token`, and the token to start from next time.
+async fn list_changes(
+    google_drive_service: &impl GoogleDriveServiceTrait,
+    access_token: String,
+    page_token: &str,
+) -> Result<(Vec<FileChange>, String), google_drive_service::Error> {
+    let mut page_token = page_token.to_string();
+    let mut changes = Vec::new();
+    loop {
+        let page = google_drive_service
+            .list_changes(access_token.clone(), &page_token)
+            .await?;
+        changes.extend(page.changes);
+        match (page.next_page_token, page.new_start_page_token) {
+            (Some(next_page_token), _) => page_token = next_page_token,
+            (None, Some(new_start_page_token)) => return Ok((changes, new_start_page_token)),
+            (None, None) => return Ok((changes, page_token)),
+        }
+    }
+}
+
+/// Learns the parents of every folder above `folder_ids` into `tree`, from what is stored
+/// and otherwise from Drive. Returns the ones read from Drive, to be stored.
+async fn resolve_ancestors(
+    user_repository: &impl UserRepositoryTrait,
+    file_parents_repository: &impl FileParentsRepositoryTrait,
+    google_drive_service: &impl GoogleDriveServiceTrait,
+    user: &mut User,
+    tree: &mut HashMap<String, Vec<String>>,
+    mut folder_ids: Vec<String>,
+) -> Result<HashMap<String, Vec<String>>, Error> {
+    let mut fetched = HashMap::new();
+    let mut seen = HashSet::new();
+    while !folder_ids.is_empty() {
+        folder_ids.retain(|x| seen.insert(x.clone()));
+        let unknown: Vec<String> = folder_ids
+            .iter()
+            .filter(|x| !tree.contains_key(*x))
+            .cloned()
+            .collect();
+        if !unknown.is_empty() {
+            let stored = file_parents_repository
+                .find(user.id, &unknown)
+                .await
+                .map_err(|err| Error::ConnectionError(err.to_string()))?;
+            tree.extend(stored);
+        }
+        let missing: Vec<String> = unknown
+            .into_iter()
+            .filter(|x| !tree.contains_key(x))
+            .collect();
+        for folder_id in &missing {
+            let parents = google_session::with_access_token(
+                user_repository,
+                google_drive_service,
+                user,
+                |access_token| folder_parents(google_drive_service, access_token, folder_id),
+            )
+            .await?;
+            tree.insert(folder_id.clone(), parents.clone());
+            fetched.insert(folder_id.clone(), parents);
+        }
+
+        folder_ids = folder_ids
+            .iter()
+            .flat_map(|x| tree.get(x).into_iter().flatten())
+            .cloned()
+            .collect();
+    }
+    Ok(fetched)
+}
+
+async fn folder_parents(
+    google_drive_service: &impl GoogleDriveServiceTrait,
+    access_token: String,
+    folder_id: &str,
+) -> Result<Vec<String>, google_drive_service::Error> {
+    let fields = [FileField::Id, FileField::Parents];
+    match google_drive_service
+        .get_file_fields(access_token, folder_id, &fields)
+        .await
+    {
+        Ok(folder) => Ok(folder.parents.unwrap_or_default()),
+        // Gone or out of the user's reach, there is nothing above it to look at
+        Err(google_drive_service::Error::NotFound(_)) => Ok(Vec::new()),
+        Err(err) => Err(err),
+    }
+}
+
+/// The folders and every folder above them, as far as `tree` knows.
+fn folders_above<'a>(
+    tree: &'a HashMap<String, Vec<String>>,
+    folder_ids: impl Iterator<Item = &'a String>,
+) -> HashSet<String> {
+    let mut folders = HashSet::new();
+    let mut pending: Vec<&String> = folder_ids.collect();
+    while let Some(folder_id) = pending.pop() {
+        if folders.insert(folder_id.clone()) {
+            pending.extend(tree.get(folder_id).into_iter().flatten());
+        }
+    }
+    folders
+}
+
+/// The signed call telling the webhook about the changes it watches, if there are any.
+/// `folders` has the folders above each change.
+fn delivery(
+    webhook: &Webhook,
+    changes: &[FileChange],
+    folders: &[HashSet<String>],
+) -> Option<Delivery> {
+    let changes: Vec<&FileChange> = changes
+        .iter()
+        .zip(folders)
+        .filter(|(_, folders)| webhook.wants(folders))
+        .map(|(change, _)| change)
+        .collect();
+    if changes.is_empty() {
+        return None;
+    }
+
+    let body = serde_json::to_vec(&WebhookEvent {
+        webhook_id: webhook.id,
+        changes,
+    })
+    .ok()?;
+    let timestamp = Utc::now().timestamp();
+    Some(Delivery {
+        url: webhook.url.clone(),
+        signature: webhook.sign(timestamp, &body),
+        timestamp,
+        body,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        application::ports::mocks::{
+            self, MockChangeCursorRepository, MockFileParentsRepository, MockGoogleDriveService,
+            MockUserRepository, MockWebhookRepository, MockWebhookSender,
+        },
+        domain::value_objects::{
+            file_change::ChangesPage,
+            file_info::{FileInfo, FOLDER_MIME_TYPE},
+        },
+    };
+
+    fn change(file_id: &str, folder_id: &str) -> FileChange {
+        let file = FileInfo {
+            id: file_id.to_string(),
+            parents: Some(vec![folder_id.to_string()]),
+            ..Default::default()
+        };
+        FileChange::new(file_id.to_string(), false, None, Some(file))
+    }
+
+    fn change_pages() -> MockGoogleDriveService {
+        let google_drive_service = MockGoogleDriveService::default();
+        let pages = [
+            (
+                "1",
+                ChangesPage {
+                    changes: vec![change("a", "inbox")],
+                    next_page_token: Some("2".to_string()),
+                    new_start_page_token: None,
+                },
+            ),
+            (
+                "2",
+                ChangesPage {
+                    changes: vec![change("b", "archive")],
+                    next_page_token: None,
+                    new_start_page_token: Some("3".to_string()),
+                },
+            ),
+        ];
+        *google_drive_service.change_pages.lock().unwrap() = pages
+            .into_iter()
+            .map(|(token, page)| (token.to_string(), page))
+            .collect();
+        google_drive_service
+    }
+
+    #[tokio::test]
+    async fn test_cursor_advances_past_the_changes() {
+        let user = mocks::user();
+        let user_id = user.id;
+        let user_repository = MockUserRepository::with_user(user);
+        let webhook_repository = MockWebhookRepository::with_webhooks(vec![Webhook::new(
+            user_id,
+            "https://example.com/hook".to_string(),
+            vec![],
+        )]);
+        let change_cursor_repository =
+            MockChangeCursorRepository::with_cursor(ChangeCursor::new(user_id, "1".to_string()));
+        let file_parents_repository = MockFileParentsRepository::default();
+        let google_drive_service = change_pages();
+        let webhook_sender = MockWebhookSender::default();
+        let sync = || {
+            execute(
+                &user_repository,
+                &webhook_repository,
+                &change_cursor_repository,
+                &file_parents_repository,
+                &google_drive_service,
+                &webhook_sender,
+                Payload {
+                    user_id,
+                    concurrency: 2,
+                },
+            )
+        };
+
+        let summary = sync().await.ok().unwrap();
+        assert_eq!(summary.changes, 2);
+        assert_eq!(summary.delivered, 1);
+        assert_eq!(
+            change_cursor_repository.page_token(user_id).as_deref(),
+            Some("3")
+        );
+
+        // Nothing new on the next sync, and nothing is sent again
+        let summary = sync().await.ok().unwrap();
+        assert_eq!(summary.changes, 0);
+        assert_eq!(webhook_sender.deliveries.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_webhooks_get_their_signed_changes() {
+        let user = mocks::user();
+        let user_id = user.id;
+        let all = Webhook::new(user_id, "https://example.com/all".to_string(), vec![]);
+        let inbox = Webhook::new(
+            user_id,
+            "https://example.com/inbox".to_string(),
+            vec!["inbox".to_string()],
+        );
+        let failing = Webhook::new(user_id, "https://example.com/down".to_string(), vec![]);
+        let webhook_sender = MockWebhookSender {
+            failing_url: Some(failing.url.clone()),
+            ..Default::default()
+        };
+
+        let summary = execute(
+            &MockUserRepository::with_user(user),
+            &MockWebhookRepository::with_webhooks(vec![all.clone(), inbox.clone(), failing]),
+            &MockChangeCursorRepository::with_cursor(ChangeCursor::new(user_id, "1".to_string())),
+            &MockFileParentsRepository::default(),
+            &change_pages(),
+            &webhook_sender,
+            Payload {
+                user_id,
+                concurrency: 2,
+            },
+        )
+        .await
+        .ok()
+        .unwrap();
+        assert_eq!(summary.delivered, 2);
+        assert_eq!(summary.failed, 1);
+
+        let deliveries = webhook_sender.deliveries.lock().unwrap();
+        for (webhook, file_ids) in [(&all, vec!["a", "b"]), (&inbox, vec!["a"])] {
+            let delivery = deliveries.iter().find(|x| x.url == webhook.url).unwrap();
+            assert_eq!(
+                delivery.signature,
+                webhook.sign(delivery.timestamp, &delivery.body)
+            );
+
+            let body: serde_json::Value = serde_json::from_slice(&delivery.body).unwrap();
+            assert_eq!(body["webhook_id"], webhook.id.to_string());
+            let sent: Vec<_> = body["changes"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|x| x["file_id"].as_str().unwrap())
+                .collect();
+            assert_eq!(sent, file_ids);
+        }
+    }
+
+    /// Syncs a single page of changes for a webhook watching `inbox`, and returns the ids
+    /// of the files it was told about.
+    async fn sync_inbox(
+        user_id: Id,
+        changes: Vec<FileChange>,
+        google_drive_service: MockGoogleDriveService,
+        file_parents_repository: &MockFileParentsRepository,
+    ) -> Vec<String> {
+        let mut user = mocks::user();
+        user.id = user_id;
+        let inbox = Webhook::new(
+            user_id,
+            "https://example.com/inbox".to_string(),
+            vec!["inbox".to_string()],
+        );
+        google_drive_service.change_pages.lock().unwrap().insert(
+            "1".to_string(),
+            ChangesPage {
+                changes,
+                next_page_token: None,
+                new_start_page_token: Some("2".to_string()),
+            },
+        );
+        let webhook_sender = MockWebhookSender::default();
+
+        execute(
+            &MockUserRepository::with_user(user),
+            &MockWebhookRepository::with_webhooks(vec![inbox]),
+            &MockChangeCursorRepository::with_cursor(ChangeCursor::new(user_id, "1".to_string())),
+            file_parents_repository,
+            &google_drive_service,
+            &webhook_sender,
+            Payload {
+                user_id,
+                concurrency: 2,
+            },
+        )
+        .await
+        .ok()
+        .unwrap();
+
+        let deliveries = webhook_sender.deliveries.lock().unwrap();
+        deliveries
+            .iter()
+            .flat_map(|delivery| {
+                let body: serde_json::Value = serde_json::from_slice(&delivery.body).unwrap();
+                body["changes"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|x| x["file_id"].as_str().unwrap().to_string())
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_removed_file_of_watched_folder() {
+        let user_id = mocks::user().id;
+        let file_parents_repository =
+            MockFileParentsRepository::with_parents(user_id, &[("a", &["inbox"])]);
+        let removed = FileChange::new("a".to_string(), true, None, None);
+
+        let sent = sync_inbox(
+            user_id,
+            vec![removed, change("b", "archive")],
+            MockGoogleDriveService::default(),
+            &file_parents_repository,
+        )
+        .await;
+
+        assert_eq!(sent, ["a"]);
+        assert_eq!(file_parents_repository.parents(user_id, "a"), None);
+    }
+
+    #[tokio::test]
+    async fn test_file_moved_out_of_watched_folder() {
+        let user_id = mocks::user().id;
+        let file_parents_repository =
+            MockFileParentsRepository::with_parents(user_id, &[("a", &["inbox"])]);
+
+        let sent = sync_inbox(
+            user_id,
+            vec![change("a", "archive")],
+            MockGoogleDriveService::default(),
+            &file_parents_repository,
+        )
+        .await;
+
+        assert_eq!(sent, ["a"]);
+        assert_eq!(
+            file_parents_repository.parents(user_id, "a"),
+            Some(vec!["archive".to_string()])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_change_in_subfolder_of_watched_folder() {
+        let user_id = mocks::user().id;
+        let file_parents_repository = MockFileParentsRepository::default();
+        let subfolder = FileInfo {
+            id: "invoices".to_string(),
+            file_type: FOLDER_MIME_TYPE.to_string(),
+            parents: Some(vec!["inbox".to_string()]),
+            ..Default::default()
+        };
+        let google_drive_service = MockGoogleDriveService::with_files(vec![subfolder]);
+
+        let sent = sync_inbox(
+            user_id,
+            vec![change("a", "invoices"), change("b", "archive")],
+            google_drive_service,
+            &file_parents_repository,
+        )
+        .await;
+
+        assert_eq!(sent, ["a"]);
+        // The subfolder read from Drive is kept for the next changes
+        assert_eq!(
+            file_parents_repository.parents(user_id, "invoices"),
+            Some(vec!["inbox".to_string()])
+        );
+    }
+}
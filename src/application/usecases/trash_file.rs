@@ -0,0 +1,101 @@
+use std::fmt::Display;
+
+use crate::{
+    application::{
+        ports::{
+            google_drive_service::GoogleDriveServiceTrait,
+            user_repository::{self, UserRepositoryTrait},
+        },
+        usecases::{drive_scope, google_session},
+    },
+    domain::value_objects::{file_info::FileInfo, id::Id},
+};
+
+pub enum Error {
+    NotFound(String),
+    GoogleUnauthenticated,
+    ConnectionError(String),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::NotFound(e) => write!(f, "Not found: {}", e),
+            Error::GoogleUnauthenticated => {
+                write!(f, "Google session expired, please log in again")
+            }
+            Error::ConnectionError(e) => write!(f, "Connection error: {}", e),
+        }
+    }
+}
+
+google_session::impl_from_session_error!(Error);
+
+pub struct Payload {
+    pub file_id: String,
+    pub drive_id: Option<String>,
+    pub user_id: Id,
+}
+
+pub async fn execute(
+    user_repository: &impl UserRepositoryTrait,
+    google_drive_service: &impl GoogleDriveServiceTrait,
+    payload: Payload,
+) -> Result<FileInfo, Error> {
+    let mut user = match user_repository.find_by_id(payload.user_id).await {
+        Ok(user) => user,
+        Err(user_repository::Error::NotFound) => {
+            return Err(Error::NotFound("User not found".to_string()))
+        }
+        Err(err) => return Err(Error::ConnectionError(err.to_string())),
+    };
+
+    drive_scope::check(
+        user_repository,
+        google_drive_service,
+        &mut user,
+        &payload.file_id,
+        payload.drive_id.as_deref(),
+    )
+    .await?;
+
+    google_session::with_access_token(
+        user_repository,
+        google_drive_service,
+        &mut user,
+        |access_token| google_drive_service.trash_file(access_token, &payload.file_id),
+    )
+    .await
+    .map_err(Error::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::application::ports::mocks::{self, MockGoogleDriveService, MockUserRepository};
+
+    #[tokio::test]
+    async fn test_file_of_another_drive() {
+        let user = mocks::user();
+        let user_id = user.id;
+        let google_drive_service = MockGoogleDriveService::with_files(vec![FileInfo {
+            id: "file".to_string(),
+            drive_id: Some("other".to_string()),
+            ..Default::default()
+        }]);
+
+        // The mock can't trash files, so getting past the check would panic
+        let result = execute(
+            &MockUserRepository::with_user(user),
+            &google_drive_service,
+            Payload {
+                file_id: "file".to_string(),
+                drive_id: Some("drive".to_string()),
+                user_id,
+            },
+        )
+        .await;
+
+        assert!(matches!(result, Err(Error::NotFound(_))));
+    }
+}
@@ -0,0 +1,504 @@
+use std::{
+    fmt::Display,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use bytes::Bytes;
+use futures::{future, stream, StreamExt};
+
+use crate::{
+    application::{
+        ports::{
+            google_drive_service::{self, ByteStream, GoogleDriveServiceTrait},
+            user_repository::{self, UserRepositoryTrait},
+        },
+        usecases::google_session,
+    },
+    domain::{
+        entities::user::User,
+        value_objects::{
+            conflict_policy::{free_name, ConflictPolicy},
+            file_info::{FileField, FileInfo},
+            file_query::{FileQuery, MAX_PAGE_SIZE},
+            file_type::{FileTypePolicy, SIGNATURE_LEN},
+            id::Id,
+            shared_drive::folder_or_root,
+        },
+    },
+};
+
+#[derive(Clone)]
+pub enum Error {
+    NotFound(String),
+    UnsupportedFileType(String),
+    TooLarge(String),
+    Conflict(String),
+    QuotaExceeded(String),
+    GoogleUnauthenticated,
+    ConnectionError(String),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::NotFound(e) => write!(f, "Not found: {}", e),
+            Error::UnsupportedFileType(e) => write!(f, "Unsupported file type: {}", e),
+            Error::TooLarge(e) => write!(f, "File too large: {}", e),
+            Error::Conflict(e) => write!(f, "Conflict: {}", e),
+            Error::QuotaExceeded(e) => write!(f, "Storage quota exceeded: {}", e),
+            Error::GoogleUnauthenticated => {
+                write!(f, "Google session expired, please log in again")
+            }
+            Error::ConnectionError(e) => write!(f, "Connection error: {}", e),
+        }
+    }
+}
+
+pub struct Payload {
+    pub file_name: String,
+    pub user_id: Id,
+    pub content: ByteStream,
+    pub allowed_types: FileTypePolicy,
+    /// Folder the file is created in, the root of `drive_id` or of the user's Drive when
+    /// `None`
+    pub parent_id: Option<String>,
+    pub drive_id: Option<String>,
+    pub conflict_policy: ConflictPolicy,
+    /// Space left in the folder's Drive, looked up when `None`
+    pub space: Option<Space>,
+}
+
+/// Space left for new files where they are uploaded. A batch looks it up once with
+/// `available_space` and passes it to each upload.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Space {
+    /// Bytes that can still be stored, `None` when there is no limit to check
+    pub remaining: Option<u64>,
+}
+
+pub async fn execute(
+    user_repository: &impl UserRepositoryTrait,
+    google_drive_service: &impl GoogleDriveServiceTrait,
+    payload: Payload,
+) -> Result<FileInfo, Error> {
+    // The type comes from the extension and is confirmed by the content, the client's
+    // Content-Type is not trusted
+    let file_type = match payload.allowed_types.find_by_file_name(&payload.file_name) {
+        Some(file_type) => file_type,
+        None => {
+            return Err(Error::UnsupportedFileType(format!(
+                "{} is not an allowed file type",
+                payload.file_name
+            )))
+        }
+    };
+
+    let (prefix, content) = read_prefix(payload.content, SIGNATURE_LEN)
+        .await
+        .map_err(|err| Error::ConnectionError(err.to_string()))?;
+    if !file_type.matches_content(&prefix) {
+        return Err(Error::UnsupportedFileType(format!(
+            "Content of {} is not {}",
+            payload.file_name, file_type.mime_type
+        )));
+    }
+
+    let mut user = find_user(user_repository, payload.user_id).await?;
+    let parent_id = folder_or_root(payload.parent_id, payload.drive_id.as_deref());
+
+    // Checked up front so a full Drive gets a clear answer, and the content is cut short
+    // at the remaining space rather than failing at Drive once it has all been sent
+    let space = match payload.space {
+        Some(space) => space,
+        None => {
+            get_space(
+                user_repository,
+                google_drive_service,
+                &mut user,
+                parent_id.as_deref(),
+            )
+            .await?
+        }
+    };
+    let remaining = space.remaining;
+    if remaining == Some(0) {
+        return Err(quota_exceeded(&payload.file_name));
+    }
+    let max_size = remaining.map_or(file_type.max_size, |x| x.min(file_type.max_size));
+
+    let folder_id = parent_id.as_deref().unwrap_or("root");
+    let mut file_name = payload.file_name.clone();
+    let mut replaced_id = None;
+    if payload.conflict_policy != ConflictPolicy::KeepBoth {
+        let namesakes = google_session::with_access_token(
+            user_repository,
+            google_drive_service,
+            &mut user,
+            |access_token| {
+                list_namesakes(google_drive_service, access_token, folder_id, &file_name)
+            },
+        )
+        .await
+        .map_err(map_error)?;
+        let existing = namesakes
+            .iter()
+            .find(|x| x.name == file_name && !x.is_folder());
+
+        match (payload.conflict_policy, existing) {
+            (ConflictPolicy::Fail, Some(_)) => {
+                return Err(Error::Conflict(format!("{} already exists", file_name)))
+            }
+            (ConflictPolicy::Replace, Some(existing)) => replaced_id = Some(existing.id.clone()),
+            (ConflictPolicy::Rename, Some(_)) => {
+                let taken: Vec<String> = namesakes.into_iter().map(|x| x.name).collect();
+                file_name = free_name(&file_name, &taken);
+            }
+            _ => {}
+        }
+    }
+
+    // Only opening the session needs the access token, so it is the only step that can
+    // be retried after a refresh; the content stream can be read just once.
+    let file_name = &file_name;
+    let replaced_id = replaced_id.as_deref();
+    let mime_type = file_type.mime_type.as_str();
+    let parent_id = parent_id.as_deref();
+    let session_uri = google_session::with_access_token(
+        user_repository,
+        google_drive_service,
+        &mut user,
+        |access_token| async move {
+            match replaced_id {
+                Some(file_id) => {
+                    google_drive_service
+                        .create_update_session(access_token, file_id, mime_type)
+                        .await
+                }
+                None => {
+                    google_drive_service
+                        .create_upload_session(access_token, file_name, mime_type, parent_id)
+                        .await
+                }
+            }
+        },
+    )
+    .await
+    .map_err(map_error)?;
+
+    let exceeded = Arc::new(AtomicBool::new(false));
+    let content = limit_size(
+        stream::once(future::ready(Ok(prefix)))
+            .chain(content)
+            .boxed(),
+        max_size,
+        exceeded.clone(),
+    );
+
+    match google_drive_service
+        .upload_file_content(session_uri, content)
+        .await
+    {
+        Ok(file) => Ok(file),
+        Err(_) if exceeded.load(Ordering::Relaxed) && max_size < file_type.max_size => {
+            Err(quota_exceeded(&payload.file_name))
+        }
+        Err(_) if exceeded.load(Ordering::Relaxed) => Err(Error::TooLarge(format!(
+            "{} files can be up to {} bytes",
+            file_type.mime_type, file_type.max_size
+        ))),
+        Err(google_drive_service::Error::QuotaExceeded(_)) => {
+            Err(quota_exceeded(&payload.file_name))
+        }
+        Err(err) => Err(Error::ConnectionError(err.to_string())),
+    }
+}
+
+/// Space left for files uploaded to `parent_id`, the root of the user's Drive when `None`.
+pub async fn available_space(
+    user_repository: &impl UserRepositoryTrait,
+    google_drive_service: &impl GoogleDriveServiceTrait,
+    user_id: Id,
+    parent_id: Option<&str>,
+) -> Result<Space, Error> {
+    let mut user = find_user(user_repository, user_id).await?;
+    get_space(user_repository, google_drive_service, &mut user, parent_id).await
+}
+
+async fn find_user(user_repository: &impl UserRepositoryTrait, user_id: Id) -> Result<User, Error> {
+    match user_repository.find_by_id(user_id).await {
+        Ok(user) => Ok(user),
+        Err(user_repository::Error::NotFound) => Err(Error::NotFound("User not found".to_string())),
+        Err(err) => Err(Error::ConnectionError(err.to_string())),
+    }
+}
+
+async fn get_space(
+    user_repository: &impl UserRepositoryTrait,
+    google_drive_service: &impl GoogleDriveServiceTrait,
+    user: &mut User,
+    parent_id: Option<&str>,
+) -> Result<Space, Error> {
+    // Files in a shared drive count against the drive's storage, not the user's quota
+    if let Some(parent_id) = parent_id {
+        let parent = google_session::with_access_token(
+            user_repository,
+            google_drive_service,
+            user,
+            |access_token| {
+                google_drive_service.get_file_fields(
+                    access_token,
+                    parent_id,
+                    &[FileField::Id, FileField::DriveId],
+                )
+            },
+        )
+        .await
+        .map_err(map_error)?;
+        if parent.drive_id.is_some() {
+            return Ok(Space { remaining: None });
+        }
+    }
+
+    let about = google_session::with_access_token(
+        user_repository,
+        google_drive_service,
+        user,
+        |access_token| google_drive_service.get_about(access_token),
+    )
+    .await
+    .map_err(map_error)?;
+    Ok(Space {
+        remaining: about.storage_quota.remaining(),
+    })
+}
+
+fn quota_exceeded(file_name: &str) -> Error {
+    Error::QuotaExceeded(format!(
+        "there is not enough space left in Google Drive for {}",
+        file_name
+    ))
+}
+
+/// Files in the folder whose name starts like `file_name`, which includes the ones named
+/// after it with a ` (n)` suffix. Trashed files don't count.
+async fn list_namesakes(
+    google_drive_service: &impl GoogleDriveServiceTrait,
+    access_token: String,
+    folder_id: &str,
+    file_name: &str,
+) -> Result<Vec<FileInfo>, google_drive_service::Error> {
+    let stem = match file_name.rsplit_once('.') {
+        Some((stem, _)) if !stem.is_empty() => stem,
+        _ => file_name,
+    };
+    let mut query = FileQuery {
+        folder_id: folder_id.to_string(),
+        name_contains: Some(stem.to_string()),
+        page_size: Some(MAX_PAGE_SIZE),
+        ..Default::default()
+    };
+    let mut files = Vec::new();
+    loop {
+        let page = google_drive_service
+            .list_files(access_token.clone(), &query)
+            .await?;
+        files.extend(
+            page.files
+                .into_iter()
+                .filter(|x| !x.trashed.unwrap_or(false)),
+        );
+        match page.next_page_token {
+            Some(page_token) => query.page_token = Some(page_token),
+            None => return Ok(files),
+        }
+    }
+}
+
+fn map_error(err: google_session::Error) -> Error {
+    match err {
+        google_session::Error::Google(google_drive_service::Error::GoogleUnauthenticated) => {
+            Error::GoogleUnauthenticated
+        }
+        google_session::Error::Google(google_drive_service::Error::NotFound(e)) => {
+            Error::NotFound(e)
+        }
+        google_session::Error::Google(google_drive_service::Error::QuotaExceeded(_)) => {
+            Error::QuotaExceeded("Google Drive storage is full".to_string())
+        }
+        err => Error::ConnectionError(err.to_string()),
+    }
+}
+
+/// Reads at least `len` bytes from the start of the stream, or all of it when shorter.
+/// Returns them along with the rest of the stream.
+async fn read_prefix(
+    mut content: ByteStream,
+    len: usize,
+) -> Result<(Bytes, ByteStream), google_drive_service::Error> {
+    let mut prefix = Vec::with_capacity(len);
+    while prefix.len() < len {
+        match content.next().await {
+            Some(chunk) => prefix.extend_from_slice(&chunk?),
+            None => break,
+        }
+    }
+    Ok((Bytes::from(prefix), content))
+}
+
+/// Fails the stream once more than `max_size` bytes went through it, setting `exceeded`.
+fn limit_size(content: ByteStream, max_size: u64, exceeded: Arc<AtomicBool>) -> ByteStream {
+    let mut total: u64 = 0;
+    Box::pin(content.map(move |chunk| {
+        let chunk = chunk?;
+        total += chunk.len() as u64;
+        if total > max_size {
+            exceeded.store(true, Ordering::Relaxed);
+            return Err(google_drive_service::Error::Unknown(
+                "Upload size limit exceeded".to_string(),
+            ));
+        }
+        Ok(chunk)
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::TryStreamExt;
+
+    use super::*;
+    use crate::{
+        application::ports::mocks::{self, MockGoogleDriveService, MockUserRepository},
+        domain::value_objects::file_info::FOLDER_MIME_TYPE,
+    };
+
+    fn chunks(chunks: &[&'static [u8]]) -> ByteStream {
+        let chunks: Vec<_> = chunks.iter().map(|x| Ok(Bytes::from_static(x))).collect();
+        Box::pin(stream::iter(chunks))
+    }
+
+    fn folder(drive_id: Option<&str>) -> FileInfo {
+        FileInfo {
+            id: "folder".to_string(),
+            file_type: FOLDER_MIME_TYPE.to_string(),
+            drive_id: drive_id.map(|x| x.to_string()),
+            ..Default::default()
+        }
+    }
+
+    async fn upload(
+        google_drive_service: &MockGoogleDriveService,
+        content: &'static [u8],
+    ) -> Result<FileInfo, Error> {
+        let user = mocks::user();
+        let user_id = user.id;
+        execute(
+            &MockUserRepository::with_user(user),
+            google_drive_service,
+            Payload {
+                file_name: "report.pdf".to_string(),
+                user_id,
+                content: chunks(&[content]),
+                allowed_types: FileTypePolicy::parse("application/pdf:pdf", 1024).unwrap(),
+                parent_id: Some("folder".to_string()),
+                drive_id: None,
+                conflict_policy: ConflictPolicy::KeepBoth,
+                space: None,
+            },
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_quota_exceeded_while_sending() {
+        // 6 bytes left, the content is 12
+        let google_drive_service =
+            MockGoogleDriveService::with_files(vec![folder(None)]).with_storage(Some(100), 94);
+
+        let result = upload(&google_drive_service, b"%PDF-1.7 abc").await;
+
+        assert!(matches!(result, Err(Error::QuotaExceeded(_))));
+        assert!(google_drive_service.file_names("folder").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_quota_full() {
+        let google_drive_service =
+            MockGoogleDriveService::with_files(vec![folder(None)]).with_storage(Some(100), 100);
+
+        let result = upload(&google_drive_service, b"%PDF-1.7").await;
+
+        assert!(matches!(result, Err(Error::QuotaExceeded(_))));
+    }
+
+    #[tokio::test]
+    async fn test_shared_drive_skips_quota() {
+        let google_drive_service = MockGoogleDriveService::with_files(vec![folder(Some("drive"))])
+            .with_storage(Some(100), 100);
+
+        let result = upload(&google_drive_service, b"%PDF-1.7").await;
+
+        assert!(result.is_ok());
+        assert_eq!(google_drive_service.about_calls.load(Ordering::Relaxed), 0);
+    }
+
+    #[tokio::test]
+    async fn test_upload_to_drive_root() {
+        let user = mocks::user();
+        let user_id = user.id;
+        let drive = FileInfo {
+            id: "drive".to_string(),
+            file_type: FOLDER_MIME_TYPE.to_string(),
+            drive_id: Some("drive".to_string()),
+            ..Default::default()
+        };
+        let google_drive_service = MockGoogleDriveService::with_files(vec![drive]);
+
+        let result = execute(
+            &MockUserRepository::with_user(user),
+            &google_drive_service,
+            Payload {
+                file_name: "report.pdf".to_string(),
+                user_id,
+                content: chunks(&[b"%PDF-1.7"]),
+                allowed_types: FileTypePolicy::parse("application/pdf:pdf", 1024).unwrap(),
+                parent_id: None,
+                drive_id: Some("drive".to_string()),
+                conflict_policy: ConflictPolicy::KeepBoth,
+                space: None,
+            },
+        )
+        .await;
+
+        assert!(result.is_ok());
+        assert_eq!(google_drive_service.file_names("drive"), vec!["report.pdf"]);
+        assert!(google_drive_service.file_names("root").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_read_prefix() {
+        let (prefix, rest) = read_prefix(chunks(&[b"%P", b"DF-1", b".7"]), 4)
+            .await
+            .unwrap();
+        assert_eq!(&prefix[..], b"%PDF-1");
+        let rest: Vec<Bytes> = rest.try_collect().await.unwrap();
+        assert_eq!(rest, vec![Bytes::from_static(b".7")]);
+
+        let (prefix, _) = read_prefix(chunks(&[b"ab"]), 4).await.unwrap();
+        assert_eq!(&prefix[..], b"ab");
+    }
+
+    #[tokio::test]
+    async fn test_limit_size() {
+        let exceeded = Arc::new(AtomicBool::new(false));
+        let content = limit_size(chunks(&[b"abc", b"de"]), 5, exceeded.clone());
+        assert!(content.try_collect::<Vec<_>>().await.is_ok());
+        assert!(!exceeded.load(Ordering::Relaxed));
+
+        let content = limit_size(chunks(&[b"abc", b"def"]), 5, exceeded.clone());
+        assert!(content.try_collect::<Vec<_>>().await.is_err());
+        assert!(exceeded.load(Ordering::Relaxed));
+    }
+}
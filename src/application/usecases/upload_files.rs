@@ -0,0 +1,286 @@
+use futures::{stream, StreamExt};
+
+use crate::{
+    application::{
+        ports::{
+            google_drive_service::{ByteStream, GoogleDriveServiceTrait},
+            user_repository::UserRepositoryTrait,
+        },
+        usecases::upload_file,
+    },
+    domain::value_objects::{
+        conflict_policy::ConflictPolicy, file_info::FileInfo, file_type::FileTypePolicy, id::Id,
+        shared_drive::folder_or_root,
+    },
+};
+
+pub struct FileUpload {
+    pub file_name: String,
+    pub content: ByteStream,
+}
+
+pub struct Payload {
+    pub files: Vec<FileUpload>,
+    pub user_id: Id,
+    pub allowed_types: FileTypePolicy,
+    /// Folder the files are created in, the root of `drive_id` or of the user's Drive when
+    /// `None`
+    pub parent_id: Option<String>,
+    pub drive_id: Option<String>,
+    pub conflict_policy: ConflictPolicy,
+    /// Files sent to Drive at the same time
+    pub concurrency: usize,
+}
+
+/// Files uploaded one after another, with their position in the batch.
+type UploadGroup = Vec<(usize, FileUpload)>;
+
+/// Uploads every file like `upload_file` does. A failed file doesn't stop the others, the
+/// results are in the same order as the files.
+///
+/// Unless the policy is `KeepBoth`, files whose names can collide are uploaded one after
+/// another so each sees the others in Drive, and with `Fail` a name repeated in the batch
+/// fails without being sent.
+pub async fn execute(
+    user_repository: &impl UserRepositoryTrait,
+    google_drive_service: &impl GoogleDriveServiceTrait,
+    payload: Payload,
+) -> Vec<Result<FileInfo, upload_file::Error>> {
+    // Every file goes to the same folder, so the space left there is looked up once
+    let parent_id = folder_or_root(payload.parent_id, payload.drive_id.as_deref());
+    let space = match upload_file::available_space(
+        user_repository,
+        google_drive_service,
+        payload.user_id,
+        parent_id.as_deref(),
+    )
+    .await
+    {
+        Ok(space) => space,
+        Err(err) => return payload.files.iter().map(|_| Err(err.clone())).collect(),
+    };
+
+    let conflict_policy = payload.conflict_policy;
+    let mut results: Vec<Option<Result<FileInfo, upload_file::Error>>> =
+        payload.files.iter().map(|_| None).collect();
+    let mut groups: Vec<(Option<String>, UploadGroup)> = Vec::new();
+    let mut file_names: Vec<String> = Vec::new();
+    for (index, file) in payload.files.into_iter().enumerate() {
+        if conflict_policy == ConflictPolicy::Fail && file_names.contains(&file.file_name) {
+            results[index] = Some(Err(upload_file::Error::Conflict(format!(
+                "{} is more than once in the batch",
+                file.file_name
+            ))));
+            continue;
+        }
+        file_names.push(file.file_name.clone());
+
+        let family =
+            (conflict_policy != ConflictPolicy::KeepBoth).then(|| name_family(&file.file_name));
+        match groups
+            .iter_mut()
+            .find(|(x, _)| family.is_some() && *x == family)
+        {
+            Some((_, group)) => group.push((index, file)),
+            None => groups.push((family, vec![(index, file)])),
+        }
+    }
+
+    let allowed_types = &payload.allowed_types;
+    let parent_id = &parent_id;
+    let uploads: Vec<_> = groups
+        .into_iter()
+        .map(|(_, group)| async move {
+            let mut results = Vec::with_capacity(group.len());
+            for (index, file) in group {
+                let result = upload_file::execute(
+                    user_repository,
+                    google_drive_service,
+                    upload_file::Payload {
+                        file_name: file.file_name,
+                        user_id: payload.user_id,
+                        content: file.content,
+                        allowed_types: allowed_types.clone(),
+                        parent_id: parent_id.clone(),
+                        drive_id: None,
+                        conflict_policy,
+                        space: Some(space),
+                    },
+                )
+                .await;
+                results.push((index, result));
+            }
+            results
+        })
+        .collect();
+
+    let uploaded: Vec<_> = stream::iter(uploads)
+        .buffered(payload.concurrency.max(1))
+        .collect()
+        .await;
+    for (index, result) in uploaded.into_iter().flatten() {
+        results[index] = Some(result);
+    }
+    results.into_iter().flatten().collect()
+}
+
+/// The name without the ` (n)` a rename adds, so `report.pdf` and `report (1).pdf` fall in
+/// the same family.
+fn name_family(file_name: &str) -> String {
+    let (stem, extension) = match file_name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => (stem, Some(extension)),
+        _ => (file_name, None),
+    };
+    let stem = match stem.strip_suffix(')').and_then(|x| x.rsplit_once(" (")) {
+        Some((base, n)) if !n.is_empty() && n.bytes().all(|x| x.is_ascii_digit()) => base,
+        _ => stem,
+    };
+    match extension {
+        Some(extension) => format!("{}.{}", stem, extension),
+        None => stem.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::Ordering;
+
+    use bytes::Bytes;
+
+    use super::*;
+    use crate::{
+        application::ports::mocks::{self, MockGoogleDriveService, MockUserRepository},
+        domain::value_objects::file_info::FOLDER_MIME_TYPE,
+    };
+
+    fn pdf(file_name: &str) -> FileUpload {
+        FileUpload {
+            file_name: file_name.to_string(),
+            content: Box::pin(stream::iter([Ok(Bytes::from_static(b"%PDF-1.7"))])),
+        }
+    }
+
+    fn folder() -> FileInfo {
+        FileInfo {
+            id: "folder".to_string(),
+            name: "Uploads".to_string(),
+            file_type: FOLDER_MIME_TYPE.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn existing(id: &str, name: &str) -> FileInfo {
+        FileInfo {
+            id: id.to_string(),
+            name: name.to_string(),
+            file_type: "application/pdf".to_string(),
+            parents: Some(vec!["folder".to_string()]),
+            ..Default::default()
+        }
+    }
+
+    async fn upload(
+        google_drive_service: &MockGoogleDriveService,
+        files: Vec<FileUpload>,
+        conflict_policy: ConflictPolicy,
+    ) -> Vec<Result<FileInfo, upload_file::Error>> {
+        let user = mocks::user();
+        let user_id = user.id;
+        execute(
+            &MockUserRepository::with_user(user),
+            google_drive_service,
+            Payload {
+                files,
+                user_id,
+                allowed_types: FileTypePolicy::parse("application/pdf:pdf", 1024).unwrap(),
+                parent_id: Some("folder".to_string()),
+                drive_id: None,
+                conflict_policy,
+                concurrency: 4,
+            },
+        )
+        .await
+    }
+
+    #[test]
+    fn test_name_family() {
+        assert_eq!(name_family("report.pdf"), "report.pdf");
+        assert_eq!(name_family("report (2).pdf"), "report.pdf");
+        assert_eq!(name_family("notes (1)"), "notes");
+        assert_eq!(name_family("report (draft).pdf"), "report (draft).pdf");
+        assert_eq!(name_family(".env"), ".env");
+    }
+
+    #[tokio::test]
+    async fn test_rename_within_batch() {
+        let google_drive_service =
+            MockGoogleDriveService::with_files(vec![folder(), existing("a", "report.pdf")]);
+        let files = vec![pdf("report.pdf"), pdf("report.pdf"), pdf("invoice.pdf")];
+
+        let results = upload(&google_drive_service, files, ConflictPolicy::Rename).await;
+
+        let names: Vec<_> = results.into_iter().map(|x| x.ok().unwrap().name).collect();
+        assert_eq!(names, ["report (1).pdf", "report (2).pdf", "invoice.pdf"]);
+        assert_eq!(google_drive_service.file_names("folder").len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_fail_within_batch() {
+        let google_drive_service = MockGoogleDriveService::with_files(vec![folder()]);
+        let files = vec![pdf("report.pdf"), pdf("report.pdf")];
+
+        let results = upload(&google_drive_service, files, ConflictPolicy::Fail).await;
+
+        assert!(results[0].is_ok());
+        assert!(matches!(results[1], Err(upload_file::Error::Conflict(_))));
+        assert_eq!(google_drive_service.file_names("folder"), ["report.pdf"]);
+    }
+
+    #[tokio::test]
+    async fn test_replace_within_batch() {
+        let google_drive_service = MockGoogleDriveService::with_files(vec![folder()]);
+        let files = vec![pdf("report.pdf"), pdf("report.pdf")];
+
+        let results = upload(&google_drive_service, files, ConflictPolicy::Replace).await;
+
+        let ids: Vec<_> = results.into_iter().map(|x| x.ok().unwrap().id).collect();
+        assert_eq!(ids[0], ids[1]);
+        assert_eq!(google_drive_service.file_names("folder"), ["report.pdf"]);
+    }
+
+    #[tokio::test]
+    async fn test_quota_looked_up_once() {
+        let google_drive_service =
+            MockGoogleDriveService::with_files(vec![folder()]).with_storage(Some(1024), 0);
+        let files = vec![pdf("a.pdf"), pdf("b.pdf"), pdf("c.pdf")];
+
+        let results = upload(&google_drive_service, files, ConflictPolicy::KeepBoth).await;
+
+        assert!(results.iter().all(|x| x.is_ok()));
+        assert_eq!(google_drive_service.about_calls.load(Ordering::Relaxed), 1);
+    }
+
+    #[tokio::test]
+    async fn test_results_per_file() {
+        let google_drive_service = MockGoogleDriveService::with_files(vec![folder()]);
+        let text = FileUpload {
+            file_name: "fake.pdf".to_string(),
+            content: Box::pin(stream::iter([Ok(Bytes::from_static(b"plain text"))])),
+        };
+        let files = vec![pdf("a.pdf"), pdf("notes.txt"), text, pdf("b.pdf")];
+
+        let results = upload(&google_drive_service, files, ConflictPolicy::KeepBoth).await;
+
+        assert_eq!(results.len(), 4);
+        assert_eq!(results[0].as_ref().ok().unwrap().name, "a.pdf");
+        assert!(matches!(
+            results[1],
+            Err(upload_file::Error::UnsupportedFileType(_))
+        ));
+        assert!(matches!(
+            results[2],
+            Err(upload_file::Error::UnsupportedFileType(_))
+        ));
+        assert_eq!(results[3].as_ref().ok().unwrap().name, "b.pdf");
+    }
+}
@@ -0,0 +1,130 @@
+use std::fmt::Display;
+
+use chrono::Utc;
+
+use crate::{
+    application::{
+        ports::{
+            google_drive_service::{ByteStream, GoogleDriveServiceTrait},
+            upload_request_repository::{self, UploadRequestRepositoryTrait},
+            user_repository::UserRepositoryTrait,
+        },
+        usecases::upload_file,
+    },
+    domain::{
+        entities::{
+            token_data::TokenDataError,
+            upload_request::{UploadRecord, UploadRequest},
+        },
+        value_objects::{conflict_policy::ConflictPolicy, file_type::FileTypePolicy},
+    },
+};
+
+pub enum Error {
+    InvalidLink(String),
+    /// The request expired or has no uploads left
+    Unavailable(String),
+    Upload(upload_file::Error),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::InvalidLink(e) => write!(f, "Invalid link: {}", e),
+            Error::Unavailable(e) => write!(f, "Link unavailable: {}", e),
+            Error::Upload(e) => e.fmt(f),
+        }
+    }
+}
+
+pub struct Payload {
+    pub token: String,
+    pub file_name: String,
+    pub content: ByteStream,
+    pub uploader_name: Option<String>,
+    pub uploader_email: Option<String>,
+    /// Types the server accepts, narrowed down further by the request
+    pub allowed_types: FileTypePolicy,
+}
+
+pub async fn execute(
+    user_repository: &impl UserRepositoryTrait,
+    upload_request_repository: &impl UploadRequestRepositoryTrait,
+    google_drive_service: &impl GoogleDriveServiceTrait,
+    secret: &[u8],
+    payload: Payload,
+) -> Result<UploadRecord, Error> {
+    let request_id = match UploadRequest::id_from_token(&payload.token, secret) {
+        Ok(request_id) => request_id,
+        Err(TokenDataError::ExpiredToken) => {
+            return Err(Error::Unavailable("link expired".to_string()))
+        }
+        Err(TokenDataError::InvalidData) => {
+            return Err(Error::InvalidLink("bad signature".to_string()))
+        }
+    };
+
+    let upload_request = match upload_request_repository.reserve_upload(request_id).await {
+        Ok(upload_request) => upload_request,
+        Err(upload_request_repository::Error::NotFound) => {
+            return Err(Error::InvalidLink("unknown link".to_string()))
+        }
+        Err(upload_request_repository::Error::Unavailable) => {
+            return Err(Error::Unavailable(
+                "link expired or no uploads left".to_string(),
+            ))
+        }
+        Err(err) => {
+            return Err(Error::Upload(upload_file::Error::ConnectionError(
+                err.to_string(),
+            )))
+        }
+    };
+
+    let allowed_types = upload_request.file_types(&payload.allowed_types);
+    let result = upload_file::execute(
+        user_repository,
+        google_drive_service,
+        upload_file::Payload {
+            file_name: payload.file_name,
+            user_id: upload_request.user_id,
+            content: payload.content,
+            allowed_types,
+            parent_id: Some(upload_request.folder_id.clone()),
+            drive_id: None,
+            conflict_policy: ConflictPolicy::KeepBoth,
+            space: None,
+        },
+    )
+    .await;
+
+    let file = match result {
+        Ok(file) => file,
+        Err(err) => {
+            // A failed upload doesn't use up the request
+            if let Err(err) = upload_request_repository
+                .release_upload(upload_request.id)
+                .await
+            {
+                eprintln!("Error releasing upload: {}", err);
+            }
+            return Err(Error::Upload(err));
+        }
+    };
+
+    let upload = UploadRecord {
+        file_id: file.id,
+        file_name: file.name,
+        mime_type: file.file_type,
+        size: file.size.unwrap_or_default(),
+        uploader_name: payload.uploader_name,
+        uploader_email: payload.uploader_email,
+        uploaded_at: Utc::now(),
+    };
+    upload_request_repository
+        .record_upload(upload_request.id, upload.clone())
+        .await
+        .map_err(|err| Error::Upload(upload_file::Error::ConnectionError(err.to_string())))?;
+
+    Ok(upload)
+}
@@ -0,0 +1,48 @@
+use std::fmt::Display;
+
+use crate::{
+    application::ports::change_cursor_repository::{self, ChangeCursorRepositoryTrait},
+    domain::value_objects::id::Id,
+};
+
+pub enum Error {
+    NotFound(String),
+    ConnectionError(String),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::NotFound(e) => write!(f, "Not found: {}", e),
+            Error::ConnectionError(e) => write!(f, "Connection error: {}", e),
+        }
+    }
+}
+
+pub struct Payload {
+    pub channel_id: String,
+    pub resource_id: String,
+}
+
+/// Finds the user a Drive push notification is about. Channel ids are random and only
+/// known to Drive, and the resource id has to match the one Drive gave for the channel.
+pub async fn execute(
+    change_cursor_repository: &impl ChangeCursorRepositoryTrait,
+    payload: Payload,
+) -> Result<Id, Error> {
+    let cursor = match change_cursor_repository
+        .find_by_channel(&payload.channel_id)
+        .await
+    {
+        Ok(cursor) => cursor,
+        Err(change_cursor_repository::Error::NotFound) => {
+            return Err(Error::NotFound("Channel not found".to_string()))
+        }
+        Err(err) => return Err(Error::ConnectionError(err.to_string())),
+    };
+
+    match cursor.channel {
+        Some(channel) if channel.resource_id == payload.resource_id => Ok(cursor.user_id),
+        _ => Err(Error::NotFound("Channel not found".to_string())),
+    }
+}
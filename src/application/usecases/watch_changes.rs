@@ -0,0 +1,106 @@
+use std::fmt::Display;
+
+use chrono::{Duration, Utc};
+use uuid::Uuid;
+
+use crate::{
+    application::{
+        ports::{
+            change_cursor_repository::{self, ChangeCursorRepositoryTrait},
+            google_drive_service::{ChannelRequest, GoogleDriveServiceTrait},
+            user_repository::{self, UserRepositoryTrait},
+        },
+        usecases::google_session,
+    },
+    domain::{entities::change_cursor::ChangeCursor, value_objects::id::Id},
+};
+
+pub enum Error {
+    NotFound(String),
+    GoogleUnauthenticated,
+    ConnectionError(String),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::NotFound(e) => write!(f, "Not found: {}", e),
+            Error::GoogleUnauthenticated => {
+                write!(f, "Google session expired, please log in again")
+            }
+            Error::ConnectionError(e) => write!(f, "Connection error: {}", e),
+        }
+    }
+}
+
+google_session::impl_from_session_error!(Error);
+
+pub struct Payload {
+    pub user_id: Id,
+    /// Public URL of the route Drive notifications are received on
+    pub address: String,
+    /// How long each channel is asked to last, Drive caps it at a week
+    pub ttl: Duration,
+    /// Channels expiring sooner than this are replaced
+    pub renew_before: Duration,
+}
+
+/// Opens a push notification channel on the user's change log, or replaces the one they
+/// have when it is about to expire. Polling keeps the changes flowing if Drive can't reach
+/// the address.
+pub async fn execute(
+    user_repository: &impl UserRepositoryTrait,
+    change_cursor_repository: &impl ChangeCursorRepositoryTrait,
+    google_drive_service: &impl GoogleDriveServiceTrait,
+    payload: Payload,
+) -> Result<ChangeCursor, Error> {
+    let mut cursor = match change_cursor_repository.find_by_user(payload.user_id).await {
+        Ok(cursor) => cursor,
+        Err(change_cursor_repository::Error::NotFound) => {
+            return Err(Error::NotFound("Change cursor not found".to_string()))
+        }
+        Err(err) => return Err(Error::ConnectionError(err.to_string())),
+    };
+    if !cursor.needs_channel(payload.renew_before) {
+        return Ok(cursor);
+    }
+
+    let mut user = match user_repository.find_by_id(payload.user_id).await {
+        Ok(user) => user,
+        Err(user_repository::Error::NotFound) => {
+            return Err(Error::NotFound("User not found".to_string()))
+        }
+        Err(err) => return Err(Error::ConnectionError(err.to_string())),
+    };
+
+    let request = ChannelRequest {
+        id: Uuid::new_v4().to_string(),
+        address: payload.address,
+        expires_at: Utc::now() + payload.ttl,
+    };
+    let channel = google_session::with_access_token(
+        user_repository,
+        google_drive_service,
+        &mut user,
+        |access_token| {
+            google_drive_service.watch_changes(access_token, &cursor.page_token, &request)
+        },
+    )
+    .await?;
+
+    // The old channel overlaps the new one until it is stopped, and expires soon anyway
+    if let Some(old_channel) = cursor.channel.replace(channel) {
+        let _ = google_session::with_access_token(
+            user_repository,
+            google_drive_service,
+            &mut user,
+            |access_token| google_drive_service.stop_channel(access_token, &old_channel),
+        )
+        .await;
+    }
+
+    change_cursor_repository
+        .update_channel(cursor)
+        .await
+        .map_err(|err| Error::ConnectionError(err.to_string()))
+}
@@ -0,0 +1,51 @@
+use chrono::{DateTime, Duration, Utc};
+
+/// A Google login that was started but has not come back through the callback yet.
+#[derive(PartialEq, Clone, Debug)]
+pub struct AuthRequest {
+    /// OAuth `state` parameter, also used as the identifier
+    pub state: String,
+    pub pkce_verifier: String,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub used_at: Option<DateTime<Utc>>,
+}
+
+impl AuthRequest {
+    pub fn new(state: String, pkce_verifier: String, ttl: Duration) -> Self {
+        let created_at = Utc::now();
+        Self {
+            state,
+            pkce_verifier,
+            created_at,
+            expires_at: created_at + ttl,
+            used_at: None,
+        }
+    }
+
+    pub fn is_expired(&self) -> bool {
+        Utc::now() >= self.expires_at
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_expired() {
+        let auth_request = AuthRequest::new(
+            "state".to_string(),
+            "verifier".to_string(),
+            Duration::minutes(10),
+        );
+        assert!(!auth_request.is_expired());
+
+        let auth_request = AuthRequest::new(
+            "state".to_string(),
+            "verifier".to_string(),
+            Duration::seconds(-1),
+        );
+        assert!(auth_request.is_expired());
+    }
+}
@@ -0,0 +1,41 @@
+use chrono::{DateTime, Duration, Utc};
+
+use crate::domain::value_objects::id::Id;
+
+/// Where a user's Drive change log was last read up to.
+#[derive(PartialEq, Clone, Debug)]
+pub struct ChangeCursor {
+    pub user_id: Id,
+    /// Drive page token of the first change not handled yet
+    pub page_token: String,
+    pub updated_at: DateTime<Utc>,
+    /// Push notification channel Drive calls when the log grows
+    pub channel: Option<WatchChannel>,
+}
+
+#[derive(PartialEq, Clone, Debug)]
+pub struct WatchChannel {
+    pub id: String,
+    /// Id Drive gives to the watched resource, needed to stop the channel
+    pub resource_id: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl ChangeCursor {
+    pub fn new(user_id: Id, page_token: String) -> Self {
+        Self {
+            user_id,
+            page_token,
+            updated_at: Utc::now(),
+            channel: None,
+        }
+    }
+
+    /// Whether the push channel is missing or expires within `margin`.
+    pub fn needs_channel(&self, margin: Duration) -> bool {
+        match &self.channel {
+            Some(channel) => channel.expires_at - margin <= Utc::now(),
+            None => true,
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use chrono::{DateTime, TimeZone, Utc};
+use jsonwebtoken as jwt;
+use jwt::{Algorithm, DecodingKey, EncodingKey, Validation};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::token_data::TokenDataError;
+use crate::domain::value_objects::id::Id;
+
+/// What a public link lets its holder do. Tokens only work for the purpose they were
+/// signed for, so a download link can't be used to upload and the other way around.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkPurpose {
+    Download,
+    Upload,
+}
+
+/// Claims of the signed token a public link is handed out as.
+#[derive(Debug, Deserialize, Serialize)]
+struct LinkClaims {
+    /// timestamp
+    exp: i64,
+    link_id: Uuid,
+    purpose: LinkPurpose,
+}
+
+/// Signs a token for the link that is valid until `expires_at`.
+pub fn sign(purpose: LinkPurpose, link_id: Id, expires_at: DateTime<Utc>, secret: &[u8]) -> String {
+    let claims = LinkClaims {
+        exp: expires_at.timestamp(),
+        link_id: link_id.into(),
+        purpose,
+    };
+    let encoding_key = EncodingKey::from_base64_secret(std::str::from_utf8(secret).unwrap());
+    jwt::encode(&jwt::Header::default(), &claims, &encoding_key.unwrap()).expect("jwt")
+}
+
+/// Id of the link a token was signed for, if the signature is valid, it has not expired
+/// and it was signed for `purpose`.
+pub fn verify(purpose: LinkPurpose, token: &str, secret: &[u8]) -> Result<Id, TokenDataError> {
+    let decoding_key = DecodingKey::from_base64_secret(std::str::from_utf8(secret).unwrap())
+        .map_err(|_| TokenDataError::InvalidData)?;
+    let claims =
+        jwt::decode::<LinkClaims>(token, &decoding_key, &Validation::new(Algorithm::HS256))
+            .map_err(|err| match err.kind() {
+                jwt::errors::ErrorKind::ExpiredSignature => TokenDataError::ExpiredToken,
+                _ => TokenDataError::InvalidData,
+            })?
+            .claims;
+
+    if claims.purpose != purpose {
+        return Err(TokenDataError::InvalidData);
+    }
+    match Utc.timestamp_opt(claims.exp, 0).single() {
+        Some(expiration) if Utc::now() <= expiration => {
+            Id::try_from(claims.link_id).map_err(|_| TokenDataError::InvalidData)
+        }
+        Some(_) => Err(TokenDataError::ExpiredToken),
+        None => Err(TokenDataError::InvalidData),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+    use crate::domain::entities::token_data::TokenData;
+
+    const SECRET: &[u8] = b"8Xui8SN4mI+7egV/9dlfYYLGQJeEx4+DwmSQLwDVXJg=";
+
+    #[test]
+    fn test_expired_token() {
+        let token = sign(
+            LinkPurpose::Download,
+            Id::new(),
+            Utc::now() - Duration::hours(1),
+            SECRET,
+        );
+        assert!(matches!(
+            verify(LinkPurpose::Download, &token, SECRET),
+            Err(TokenDataError::ExpiredToken)
+        ));
+    }
+
+    #[test]
+    fn test_token_purpose() {
+        let link_id = Id::new();
+        let token = sign(
+            LinkPurpose::Upload,
+            link_id,
+            Utc::now() + Duration::hours(1),
+            SECRET,
+        );
+        assert_eq!(
+            verify(LinkPurpose::Upload, &token, SECRET).unwrap(),
+            link_id
+        );
+        assert!(verify(LinkPurpose::Download, &token, SECRET).is_err());
+    }
+
+    #[test]
+    fn test_session_token_is_not_a_link() {
+        let token = TokenData::new(&Id::new()).token(SECRET);
+        assert!(verify(LinkPurpose::Download, &token, SECRET).is_err());
+    }
+}
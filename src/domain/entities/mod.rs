@@ -1,2 +1,9 @@
+pub mod auth_request;
+pub mod change_cursor;
+pub mod link_token;
+pub mod share_link;
+pub mod thumbnail;
+pub mod token_data;
+pub mod upload_request;
 pub mod user;
-pub mod token_data;
\ No newline at end of file
+pub mod webhook;
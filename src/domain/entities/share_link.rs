@@ -0,0 +1,68 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+
+use super::{
+    link_token::{self, LinkPurpose},
+    token_data::TokenDataError,
+};
+use crate::domain::value_objects::id::Id;
+
+/// Public link to download a Drive file with the credentials of the user who shared it.
+#[derive(PartialEq, Clone, Debug, Serialize)]
+pub struct ShareLink {
+    pub id: Id,
+    /// User whose Google account serves the file
+    pub user_id: Id,
+    pub file_id: String,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    /// Downloads allowed through the link, unlimited when `None`
+    pub max_downloads: Option<u32>,
+    pub download_count: u32,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+impl ShareLink {
+    pub fn new(user_id: Id, file_id: String, ttl: Duration, max_downloads: Option<u32>) -> Self {
+        let created_at = Utc::now();
+        Self {
+            id: Id::new(),
+            user_id,
+            file_id,
+            created_at,
+            expires_at: created_at + ttl,
+            max_downloads,
+            download_count: 0,
+            revoked_at: None,
+        }
+    }
+
+    /// Signed token that identifies the link until it expires.
+    pub fn token(&self, secret: &[u8]) -> String {
+        link_token::sign(LinkPurpose::Download, self.id, self.expires_at, secret)
+    }
+
+    /// Id of the link a token was signed for, if the signature is valid and it has not expired.
+    pub fn id_from_token(token: &str, secret: &[u8]) -> Result<Id, TokenDataError> {
+        link_token::verify(LinkPurpose::Download, token, secret)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &[u8] = b"8Xui8SN4mI+7egV/9dlfYYLGQJeEx4+DwmSQLwDVXJg=";
+
+    #[test]
+    fn test_token_round_trip() {
+        let link = ShareLink::new(Id::new(), "file".to_string(), Duration::hours(1), Some(1));
+        let token = link.token(SECRET);
+
+        assert_eq!(ShareLink::id_from_token(&token, SECRET).unwrap(), link.id);
+        assert!(
+            ShareLink::id_from_token(&token, b"c2VjcmV0LWZvci1hLWRpZmZlcmVudC1zZXJ2ZXItMTIz")
+                .is_err()
+        );
+    }
+}
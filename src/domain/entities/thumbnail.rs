@@ -0,0 +1,58 @@
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+
+/// Width of a thumbnail when the client doesn't ask for one, in pixels.
+pub const DEFAULT_WIDTH: u32 = 220;
+pub const MIN_WIDTH: u32 = 16;
+pub const MAX_WIDTH: u32 = 1600;
+
+/// Preview image of a file at a given width. It stays valid as long as the content of
+/// the file has the checksum it was made from.
+#[derive(Debug, Clone)]
+pub struct Thumbnail {
+    pub file_id: String,
+    pub md5_checksum: String,
+    pub width: u32,
+    pub content_type: String,
+    pub content: Bytes,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Thumbnail {
+    pub fn new(
+        file_id: String,
+        md5_checksum: String,
+        width: u32,
+        content_type: String,
+        content: Bytes,
+    ) -> Self {
+        Self {
+            file_id,
+            md5_checksum,
+            width,
+            content_type,
+            content,
+            created_at: Utc::now(),
+        }
+    }
+
+    /// Width to render at for the one requested, kept within bounds.
+    pub fn width(requested: Option<u32>) -> u32 {
+        requested
+            .unwrap_or(DEFAULT_WIDTH)
+            .clamp(MIN_WIDTH, MAX_WIDTH)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_width() {
+        assert_eq!(Thumbnail::width(None), DEFAULT_WIDTH);
+        assert_eq!(Thumbnail::width(Some(480)), 480);
+        assert_eq!(Thumbnail::width(Some(0)), MIN_WIDTH);
+        assert_eq!(Thumbnail::width(Some(10_000)), MAX_WIDTH);
+    }
+}
@@ -33,22 +33,22 @@ impl TokenData {
         jwt::encode(&jwt::Header::default(), self, &encoding_key.unwrap()).expect("jwt")
     }
 
-    pub fn from_token(token: &String, secret: &[u8]) -> Result<Self, TokenDataError> {
+    pub fn from_token(token: &str, secret: &[u8]) -> Result<Self, TokenDataError> {
         if let Some(auth) = decode_token(token, secret) {
             if let Some(expiration) = Utc.timestamp_opt(auth.exp, 0).single() {
                 if Utc::now() <= expiration {
                     Ok(auth)
                 } else {
                     println!("token error: Expired token");
-                    return Err(TokenDataError::ExpiredToken);
+                    Err(TokenDataError::ExpiredToken)
                 }
             } else {
                 println!("token error: Invalid expiration timestamp");
-                return Err(TokenDataError::InvalidData);
+                Err(TokenDataError::InvalidData)
             }
         } else {
             println!("token error: Invalid token");
-            return Err(TokenDataError::InvalidData);
+            Err(TokenDataError::InvalidData)
         }
     }
 }
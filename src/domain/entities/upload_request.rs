@@ -0,0 +1,82 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+
+use super::{
+    link_token::{self, LinkPurpose},
+    token_data::TokenDataError,
+};
+use crate::domain::value_objects::{file_type::FileTypePolicy, id::Id};
+
+/// Public link that lets people without an account upload files into a user's Drive folder.
+#[derive(PartialEq, Clone, Debug, Serialize)]
+pub struct UploadRequest {
+    pub id: Id,
+    /// User whose Google account receives the files
+    pub user_id: Id,
+    pub folder_id: String,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    /// Files that can be uploaded through the link, unlimited when `None`
+    pub max_files: Option<u32>,
+    /// Uploads started through the link, counting the ones still in progress
+    pub file_count: u32,
+    /// MIME types accepted, every type the server allows when empty
+    pub allowed_types: Vec<String>,
+    /// Largest file accepted, in bytes, on top of the server's own limits
+    pub max_size: Option<u64>,
+    pub uploads: Vec<UploadRecord>,
+}
+
+/// A file uploaded through an upload request.
+#[derive(PartialEq, Clone, Debug, Serialize)]
+pub struct UploadRecord {
+    pub file_id: String,
+    pub file_name: String,
+    pub mime_type: String,
+    pub size: u64,
+    /// Name and email the uploader gave, they are not verified
+    pub uploader_name: Option<String>,
+    pub uploader_email: Option<String>,
+    pub uploaded_at: DateTime<Utc>,
+}
+
+impl UploadRequest {
+    pub fn new(
+        user_id: Id,
+        folder_id: String,
+        ttl: Duration,
+        max_files: Option<u32>,
+        allowed_types: Vec<String>,
+        max_size: Option<u64>,
+    ) -> Self {
+        let created_at = Utc::now();
+        Self {
+            id: Id::new(),
+            user_id,
+            folder_id,
+            created_at,
+            expires_at: created_at + ttl,
+            max_files,
+            file_count: 0,
+            allowed_types,
+            max_size,
+            uploads: Vec::new(),
+        }
+    }
+
+    /// Signed token that identifies the request until it expires.
+    pub fn token(&self, secret: &[u8]) -> String {
+        link_token::sign(LinkPurpose::Upload, self.id, self.expires_at, secret)
+    }
+
+    /// Id of the request a token was signed for, if the signature is valid and it has not
+    /// expired.
+    pub fn id_from_token(token: &str, secret: &[u8]) -> Result<Id, TokenDataError> {
+        link_token::verify(LinkPurpose::Upload, token, secret)
+    }
+
+    /// The file types the server allows narrowed down to the ones this request accepts.
+    pub fn file_types(&self, allowed_types: &FileTypePolicy) -> FileTypePolicy {
+        allowed_types.restrict(&self.allowed_types, self.max_size)
+    }
+}
@@ -1,7 +1,11 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
 
-use crate::domain::value_objects::{email::Email, id::Id};
+use crate::domain::value_objects::{email::Email, google_token::GoogleToken, id::Id};
+
+/// Access tokens this close to their expiry are refreshed before use, so a request
+/// started right before the deadline does not fail halfway.
+const EXPIRY_MARGIN_SECS: i64 = 60;
 
 #[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
 pub struct User {
@@ -9,18 +13,74 @@ pub struct User {
     pub id: Id,
     pub email: Email,
     pub access_token: String,
+    #[serde(default)]
+    pub refresh_token: Option<String>,
+    #[serde(default)]
+    pub access_token_expires_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
 impl User {
-    pub fn new(email: Email, access_token: String) -> Self {
+    pub fn new(email: Email, token: GoogleToken) -> Self {
         Self {
             id: Id::new(),
             email,
-            access_token,
+            access_token: token.access_token,
+            refresh_token: token.refresh_token,
+            access_token_expires_at: token.expires_at,
             created_at: Utc::now(),
             updated_at: Utc::now(),
         }
     }
+
+    /// Stores a freshly issued token. Google only sends a refresh token on the first
+    /// consent or on explicit re-consent, so the stored one is kept when none is given.
+    pub fn set_google_token(&mut self, token: GoogleToken) {
+        self.access_token = token.access_token;
+        if token.refresh_token.is_some() {
+            self.refresh_token = token.refresh_token;
+        }
+        self.access_token_expires_at = token.expires_at;
+        self.updated_at = Utc::now();
+    }
+
+    pub fn is_access_token_expired(&self) -> bool {
+        match self.access_token_expires_at {
+            Some(expires_at) => Utc::now() + Duration::seconds(EXPIRY_MARGIN_SECS) >= expires_at,
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_google_token_keeps_refresh_token() {
+        let email = Email::new("name@some.com".to_string()).unwrap();
+        let mut user = User::new(
+            email,
+            GoogleToken::new("first".to_string(), Some("refresh".to_string()), None),
+        );
+
+        user.set_google_token(GoogleToken::new("second".to_string(), None, None));
+
+        assert_eq!(user.access_token, "second");
+        assert_eq!(user.refresh_token, Some("refresh".to_string()));
+    }
+
+    #[test]
+    fn test_is_access_token_expired() {
+        let email = Email::new("name@some.com".to_string()).unwrap();
+        let mut user = User::new(email, GoogleToken::new("token".to_string(), None, None));
+        assert!(!user.is_access_token_expired());
+
+        user.access_token_expires_at = Some(Utc::now() + Duration::seconds(10));
+        assert!(user.is_access_token_expired());
+
+        user.access_token_expires_at = Some(Utc::now() + Duration::hours(1));
+        assert!(!user.is_access_token_expired());
+    }
 }
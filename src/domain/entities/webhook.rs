@@ -0,0 +1,71 @@
+use std::collections::HashSet;
+
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use sha2::Sha256;
+use uuid::Uuid;
+
+use crate::domain::value_objects::id::Id;
+
+/// URL registered by a user to be told about changes to their Drive.
+#[derive(PartialEq, Clone, Debug, Serialize)]
+pub struct Webhook {
+    pub id: Id,
+    pub user_id: Id,
+    pub url: String,
+    /// Only changes to files inside these folders, at any depth, are sent, all of them when
+    /// empty
+    pub folder_ids: Vec<String>,
+    /// Key of the signature sent with every delivery, only shown when the webhook is created
+    #[serde(skip_serializing)]
+    pub secret: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Webhook {
+    pub fn new(user_id: Id, url: String, folder_ids: Vec<String>) -> Self {
+        Self {
+            id: Id::new(),
+            user_id,
+            url,
+            folder_ids,
+            secret: format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple()),
+            created_at: Utc::now(),
+        }
+    }
+
+    /// Whether a change is sent to the webhook, given every folder above the file before
+    /// and after the change.
+    pub fn wants(&self, folders: &HashSet<String>) -> bool {
+        self.folder_ids.is_empty() || self.folder_ids.iter().any(|x| folders.contains(x))
+    }
+
+    /// Hex HMAC-SHA256 of `{timestamp}.{body}`. Signing the timestamp lets receivers
+    /// refuse old deliveries that are sent again.
+    pub fn sign(&self, timestamp: i64, body: &[u8]) -> String {
+        let mut mac = Hmac::<Sha256>::new_from_slice(self.secret.as_bytes())
+            .expect("HMAC takes keys of any size");
+        mac.update(timestamp.to_string().as_bytes());
+        mac.update(b".");
+        mac.update(body);
+        hex::encode(mac.finalize().into_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign() {
+        let mut webhook = Webhook::new(Id::new(), "https://example.com".to_string(), vec![]);
+        webhook.secret = "key".to_string();
+
+        // echo -n '1700000000.{}' | openssl dgst -sha256 -hmac key
+        assert_eq!(
+            webhook.sign(1700000000, b"{}"),
+            "9d713ed406bb7076d4123f0dc2c39d2df5c654ed4b0cd56b52c8b4c940bd63ae"
+        );
+    }
+}
@@ -0,0 +1,83 @@
+/// A range as the client asked for it, before the file size is known.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RangeSpec {
+    /// `first-last`, both inclusive
+    Bounded(u64, u64),
+    /// `first-`, up to the end of the file
+    From(u64),
+    /// `-length`, the last `length` bytes
+    Suffix(u64),
+}
+
+/// A satisfiable range within a file, both ends inclusive.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ByteRange {
+    pub start: u64,
+    pub end: u64,
+}
+
+impl RangeSpec {
+    /// Resolves the range against a file of `size` bytes, `None` when no byte of the
+    /// file falls inside it.
+    pub fn resolve(&self, size: u64) -> Option<ByteRange> {
+        if size == 0 {
+            return None;
+        }
+
+        match *self {
+            RangeSpec::Bounded(start, end) if start < size => Some(ByteRange {
+                start,
+                end: end.min(size - 1),
+            }),
+            RangeSpec::From(start) if start < size => Some(ByteRange {
+                start,
+                end: size - 1,
+            }),
+            RangeSpec::Suffix(length) if length > 0 => Some(ByteRange {
+                start: size.saturating_sub(length),
+                end: size - 1,
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl ByteRange {
+    pub fn len(&self) -> u64 {
+        self.end - self.start + 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve() {
+        assert_eq!(
+            RangeSpec::Bounded(0, 99).resolve(1000),
+            Some(ByteRange { start: 0, end: 99 })
+        );
+        assert_eq!(
+            RangeSpec::Bounded(900, 2000).resolve(1000),
+            Some(ByteRange {
+                start: 900,
+                end: 999
+            })
+        );
+        assert_eq!(
+            RangeSpec::From(500).resolve(1000),
+            Some(ByteRange {
+                start: 500,
+                end: 999
+            })
+        );
+        assert_eq!(
+            RangeSpec::Suffix(2000).resolve(1000),
+            Some(ByteRange { start: 0, end: 999 })
+        );
+        assert_eq!(RangeSpec::Bounded(1000, 1001).resolve(1000), None);
+        assert_eq!(RangeSpec::Suffix(0).resolve(1000), None);
+        assert_eq!(RangeSpec::From(0).resolve(0), None);
+    }
+}
@@ -0,0 +1,51 @@
+use serde::Deserialize;
+
+/// What an upload does when the folder already has a file with the same name.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictPolicy {
+    /// Create another file with the same name, which Drive allows
+    #[default]
+    KeepBoth,
+    /// Store the upload as a new revision of the existing file
+    Replace,
+    /// Create the file under the first free `name (n)`
+    Rename,
+    /// Reject the upload
+    Fail,
+}
+
+/// The first `name (n)` that is not in `taken`, keeping the extension last, such as
+/// `report (2).pdf`. Returns `name` itself when it is free.
+pub fn free_name(name: &str, taken: &[String]) -> String {
+    if !taken.iter().any(|x| x == name) {
+        return name.to_string();
+    }
+
+    let (stem, extension) = match name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => (stem, format!(".{}", extension)),
+        _ => (name, String::new()),
+    };
+    (1..)
+        .map(|n| format!("{} ({}){}", stem, n, extension))
+        .find(|candidate| !taken.contains(candidate))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_free_name() {
+        let taken: Vec<String> = ["report.pdf", "report (1).pdf", "notes", ".env"]
+            .iter()
+            .map(|x| x.to_string())
+            .collect();
+
+        assert_eq!(free_name("invoice.pdf", &taken), "invoice.pdf");
+        assert_eq!(free_name("report.pdf", &taken), "report (2).pdf");
+        assert_eq!(free_name("notes", &taken), "notes (1)");
+        assert_eq!(free_name(".env", &taken), ".env (1)");
+    }
+}
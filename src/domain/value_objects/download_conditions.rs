@@ -0,0 +1,87 @@
+use chrono::{DateTime, Utc};
+
+use super::file_info::FileInfo;
+
+/// Preconditions a client attached to a download to skip it when its copy is current.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DownloadConditions {
+    /// Entity tags the client already has, `*` matches any
+    pub if_none_match: Option<Vec<String>>,
+    pub if_modified_since: Option<DateTime<Utc>>,
+}
+
+impl DownloadConditions {
+    /// Whether the client's copy of `file` is still current. `If-Modified-Since` is only
+    /// looked at when no entity tags were sent.
+    pub fn is_not_modified(&self, file: &FileInfo) -> bool {
+        if let Some(tags) = &self.if_none_match {
+            return match file.entity_tag() {
+                Some(entity_tag) => tags.iter().any(|x| x == "*" || *x == entity_tag),
+                None => false,
+            };
+        }
+
+        match (self.if_modified_since, file.modified_at) {
+            // HTTP dates have no sub-second precision
+            (Some(since), Some(modified_at)) => modified_at.timestamp() <= since.timestamp(),
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+
+    fn file(modified_at: DateTime<Utc>) -> FileInfo {
+        FileInfo {
+            id: "id".to_string(),
+            name: "name.pdf".to_string(),
+            file_type: "application/pdf".to_string(),
+            modified_at: Some(modified_at),
+            size: Some(10),
+            md5_checksum: Some("abc".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_is_not_modified_by_entity_tag() {
+        let file = file(Utc::now());
+
+        let conditions = DownloadConditions {
+            if_none_match: Some(vec!["abc".to_string()]),
+            if_modified_since: None,
+        };
+        assert!(conditions.is_not_modified(&file));
+
+        // A mismatching tag wins over a matching date
+        let conditions = DownloadConditions {
+            if_none_match: Some(vec!["other".to_string()]),
+            if_modified_since: Some(Utc::now()),
+        };
+        assert!(!conditions.is_not_modified(&file));
+    }
+
+    #[test]
+    fn test_is_not_modified_by_date() {
+        let modified_at = Utc::now() - Duration::days(1);
+        let file = file(modified_at);
+
+        let conditions = DownloadConditions {
+            if_none_match: None,
+            if_modified_since: Some(modified_at),
+        };
+        assert!(conditions.is_not_modified(&file));
+
+        let conditions = DownloadConditions {
+            if_none_match: None,
+            if_modified_since: Some(modified_at - Duration::hours(1)),
+        };
+        assert!(!conditions.is_not_modified(&file));
+
+        assert!(!DownloadConditions::default().is_not_modified(&file));
+    }
+}
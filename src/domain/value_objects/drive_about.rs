@@ -0,0 +1,64 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+/// What Drive knows about the user's account: who they are there, their storage and
+/// the formats files can be converted from and to.
+#[derive(Debug, Default, Serialize)]
+pub struct DriveAbout {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub photo_link: Option<String>,
+    pub storage_quota: StorageQuota,
+    /// Largest file Drive accepts from this user, in bytes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_upload_size: Option<u64>,
+    /// Formats each source MIME type can be imported into
+    pub import_formats: HashMap<String, Vec<String>>,
+    /// Formats each Google document type can be exported to
+    pub export_formats: HashMap<String, Vec<String>>,
+}
+
+/// Storage of the account in bytes. It is shared by Drive, Gmail and Photos, so `usage`
+/// can be much more than what is in Drive.
+#[derive(Debug, Default, Serialize)]
+pub struct StorageQuota {
+    /// `None` when the storage is unlimited
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u64>,
+    pub usage: u64,
+    pub usage_in_drive: u64,
+    pub usage_in_drive_trash: u64,
+}
+
+impl StorageQuota {
+    /// Bytes that can still be stored, `None` when the storage is unlimited.
+    pub fn remaining(&self) -> Option<u64> {
+        self.limit.map(|x| x.saturating_sub(self.usage))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remaining() {
+        let quota = StorageQuota {
+            limit: Some(100),
+            usage: 40,
+            ..Default::default()
+        };
+        assert_eq!(quota.remaining(), Some(60));
+
+        let full = StorageQuota {
+            limit: Some(100),
+            usage: 120,
+            ..Default::default()
+        };
+        assert_eq!(full.remaining(), Some(0));
+
+        assert_eq!(StorageQuota::default().remaining(), None);
+    }
+}
@@ -0,0 +1,77 @@
+/// A format native Google files can be exported to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExportFormat {
+    /// Name used in requests, which is also the file extension
+    pub name: &'static str,
+    pub mime_type: &'static str,
+}
+
+const FORMATS: [ExportFormat; 15] = [
+    format("pdf", "application/pdf"),
+    format(
+        "docx",
+        "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+    ),
+    format(
+        "xlsx",
+        "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+    ),
+    format(
+        "pptx",
+        "application/vnd.openxmlformats-officedocument.presentationml.presentation",
+    ),
+    format("odt", "application/vnd.oasis.opendocument.text"),
+    format("ods", "application/vnd.oasis.opendocument.spreadsheet"),
+    format("odp", "application/vnd.oasis.opendocument.presentation"),
+    format("rtf", "application/rtf"),
+    format("epub", "application/epub+zip"),
+    format("txt", "text/plain"),
+    format("html", "text/html"),
+    format("csv", "text/csv"),
+    format("tsv", "text/tab-separated-values"),
+    format("png", "image/png"),
+    format("jpg", "image/jpeg"),
+];
+
+const fn format(name: &'static str, mime_type: &'static str) -> ExportFormat {
+    ExportFormat { name, mime_type }
+}
+
+impl ExportFormat {
+    pub fn find(name: &str) -> Option<Self> {
+        let name = name.trim().trim_start_matches('.').to_lowercase();
+        FORMATS.iter().find(|x| x.name == name).copied()
+    }
+
+    /// Formats Drive exports a native file type to, the first one is used by default.
+    /// Empty for files that are not native Google files.
+    pub fn supported_by(mime_type: &str) -> Vec<Self> {
+        let names: &[&str] = match mime_type {
+            "application/vnd.google-apps.document" => {
+                &["pdf", "docx", "odt", "rtf", "txt", "html", "epub"]
+            }
+            "application/vnd.google-apps.spreadsheet" => &["pdf", "xlsx", "ods", "csv", "tsv"],
+            "application/vnd.google-apps.presentation" => &["pdf", "pptx", "odp", "txt"],
+            "application/vnd.google-apps.drawing" => &["pdf", "png", "jpg"],
+            _ => &[],
+        };
+        names.iter().filter_map(|x| Self::find(x)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_supported_formats() {
+        let formats = ExportFormat::supported_by("application/vnd.google-apps.spreadsheet");
+        assert_eq!(formats[0].mime_type, "application/pdf");
+        assert!(formats.contains(&ExportFormat::find("CSV").unwrap()));
+        assert!(!formats.contains(&ExportFormat::find("docx").unwrap()));
+
+        assert!(ExportFormat::supported_by("application/pdf").is_empty());
+        assert!(ExportFormat::supported_by("application/vnd.google-apps.folder").is_empty());
+        assert!(ExportFormat::find("exe").is_none());
+    }
+}
@@ -0,0 +1,92 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use super::file_info::FileInfo;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    /// Created, edited, renamed or moved
+    Updated,
+    Trashed,
+    /// Deleted for good, or no longer visible to the user
+    Removed,
+}
+
+/// A change to a file of the user's Drive, as reported by Drive's change log.
+#[derive(Debug, Serialize)]
+pub struct FileChange {
+    pub file_id: String,
+    pub kind: ChangeKind,
+    pub time: Option<DateTime<Utc>>,
+    /// State of the file after the change, `None` when it was removed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<FileInfo>,
+}
+
+impl FileChange {
+    pub fn new(
+        file_id: String,
+        removed: bool,
+        time: Option<DateTime<Utc>>,
+        file: Option<FileInfo>,
+    ) -> Self {
+        let kind = match &file {
+            _ if removed => ChangeKind::Removed,
+            None => ChangeKind::Removed,
+            Some(file) if file.trashed == Some(true) => ChangeKind::Trashed,
+            Some(_) => ChangeKind::Updated,
+        };
+        Self {
+            file_id,
+            kind,
+            time,
+            file: if removed { None } else { file },
+        }
+    }
+
+    /// Folders the file is directly inside after the change, none when it was removed.
+    pub fn parents(&self) -> &[String] {
+        self.file
+            .as_ref()
+            .and_then(|x| x.parents.as_deref())
+            .unwrap_or_default()
+    }
+}
+
+/// One page of Drive's change log.
+#[derive(Debug)]
+pub struct ChangesPage {
+    pub changes: Vec<FileChange>,
+    /// Token of the next page, `None` on the last page
+    pub next_page_token: Option<String>,
+    /// Token to start from on the next sync, only given on the last page
+    pub new_start_page_token: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_change_kind() {
+        let file = |trashed| FileInfo {
+            id: "a".to_string(),
+            parents: Some(vec!["inbox".to_string()]),
+            trashed: Some(trashed),
+            ..Default::default()
+        };
+
+        let change = FileChange::new("a".to_string(), false, None, Some(file(false)));
+        assert_eq!(change.kind, ChangeKind::Updated);
+        assert_eq!(change.parents(), ["inbox"]);
+
+        let change = FileChange::new("a".to_string(), false, None, Some(file(true)));
+        assert_eq!(change.kind, ChangeKind::Trashed);
+
+        let change = FileChange::new("a".to_string(), true, None, Some(file(false)));
+        assert_eq!(change.kind, ChangeKind::Removed);
+        assert!(change.file.is_none());
+        assert!(change.parents().is_empty());
+    }
+}
@@ -1,10 +1,182 @@
+use std::collections::HashMap;
+
 use chrono::{DateTime, Utc};
 use serde::Serialize;
 
-#[derive(Debug, Serialize)]
+/// MIME type Drive gives to folders.
+pub const FOLDER_MIME_TYPE: &str = "application/vnd.google-apps.folder";
+
+#[derive(Debug, Default, Clone, Serialize)]
 pub struct FileInfo {
     pub id: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
     pub name: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
     pub file_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub created_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub modified_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub md5_checksum: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parents: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub owners: Option<Vec<FileOwner>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub web_view_link: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thumbnail_link: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trashed: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub starred: Option<bool>,
+    /// Properties this API stores on the file, such as the page count of a PDF
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub app_properties: Option<HashMap<String, String>>,
+    /// Shared drive the file is in, `None` in the user's own Drive
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub drive_id: Option<String>,
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct FileOwner {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+}
+
+impl FileInfo {
+    pub fn is_folder(&self) -> bool {
+        self.file_type == FOLDER_MIME_TYPE
+    }
+
+    /// Identifies the current content of the file, when Drive stores a checksum for it.
+    pub fn entity_tag(&self) -> Option<String> {
+        self.md5_checksum.clone()
+    }
+}
+
+/// A `FileInfo` field that can be requested from Drive.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FileField {
+    Id,
+    Name,
+    FileType,
+    CreatedAt,
+    ModifiedAt,
+    Size,
+    Md5Checksum,
+    Parents,
+    Owners,
+    WebViewLink,
+    ThumbnailLink,
+    Trashed,
+    Starred,
+    AppProperties,
+    DriveId,
+}
+
+impl FileField {
+    /// Fields returned when the client doesn't ask for a projection.
+    pub const DEFAULT: [FileField; 10] = [
+        FileField::Id,
+        FileField::Name,
+        FileField::FileType,
+        FileField::CreatedAt,
+        FileField::ModifiedAt,
+        FileField::Size,
+        FileField::Md5Checksum,
+        FileField::Parents,
+        FileField::Trashed,
+        FileField::Starred,
+    ];
+
+    pub const ALL: [FileField; 15] = [
+        FileField::Id,
+        FileField::Name,
+        FileField::FileType,
+        FileField::CreatedAt,
+        FileField::ModifiedAt,
+        FileField::Size,
+        FileField::Md5Checksum,
+        FileField::Parents,
+        FileField::Owners,
+        FileField::WebViewLink,
+        FileField::ThumbnailLink,
+        FileField::Trashed,
+        FileField::Starred,
+        FileField::AppProperties,
+        FileField::DriveId,
+    ];
+
+    /// Parses a comma separated projection such as `name,size,owners`, using the JSON names
+    /// of `FileInfo`. `default` and `*` expand to the default and to every field, and `id` is
+    /// always included.
+    pub fn parse_list(value: &str) -> Result<Vec<FileField>, String> {
+        let mut fields = vec![FileField::Id];
+        for name in value.split(',').map(str::trim).filter(|x| !x.is_empty()) {
+            let expanded: &[FileField] = match name {
+                "default" => &FileField::DEFAULT,
+                "*" => &FileField::ALL,
+                "id" => &[FileField::Id],
+                "name" => &[FileField::Name],
+                "file_type" => &[FileField::FileType],
+                "created_at" => &[FileField::CreatedAt],
+                "modified_at" => &[FileField::ModifiedAt],
+                "size" => &[FileField::Size],
+                "md5_checksum" => &[FileField::Md5Checksum],
+                "parents" => &[FileField::Parents],
+                "owners" => &[FileField::Owners],
+                "web_view_link" => &[FileField::WebViewLink],
+                "thumbnail_link" => &[FileField::ThumbnailLink],
+                "trashed" => &[FileField::Trashed],
+                "starred" => &[FileField::Starred],
+                "app_properties" => &[FileField::AppProperties],
+                "drive_id" => &[FileField::DriveId],
+                _ => return Err(format!("Unknown field '{}'", name)),
+            };
+            for field in expanded {
+                if !fields.contains(field) {
+                    fields.push(*field);
+                }
+            }
+        }
+        Ok(fields)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_field_list() {
+        assert_eq!(
+            FileField::parse_list("name, size"),
+            Ok(vec![FileField::Id, FileField::Name, FileField::Size])
+        );
+        assert_eq!(
+            FileField::parse_list("default,owners,name").unwrap().len(),
+            FileField::DEFAULT.len() + 1
+        );
+        assert_eq!(FileField::parse_list("*").unwrap(), FileField::ALL.to_vec());
+        assert!(FileField::parse_list("name,secret").is_err());
+    }
+
+    #[test]
+    fn test_sparse_serialization() {
+        let file = FileInfo {
+            id: "abc".to_string(),
+            size: Some(10),
+            ..Default::default()
+        };
+        assert_eq!(
+            serde_json::to_value(&file).unwrap(),
+            serde_json::json!({ "id": "abc", "size": 10 })
+        );
+    }
 }
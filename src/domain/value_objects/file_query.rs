@@ -0,0 +1,119 @@
+use chrono::{DateTime, Utc};
+
+use super::file_info::FileField;
+
+pub const DEFAULT_PAGE_SIZE: u32 = 100;
+pub const MAX_PAGE_SIZE: u32 = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SortField {
+    Name,
+    CreatedAt,
+    ModifiedAt,
+    Size,
+    Folder,
+    Starred,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SortKey {
+    pub field: SortField,
+    pub descending: bool,
+}
+
+impl SortKey {
+    /// Parses a comma separated sort list such as `folder,modified_at desc`.
+    pub fn parse_list(value: &str) -> Result<Vec<SortKey>, String> {
+        value
+            .split(',')
+            .map(str::trim)
+            .filter(|x| !x.is_empty())
+            .map(|key| {
+                let (field, descending) = match key.split_once(' ') {
+                    Some((field, "desc")) => (field, true),
+                    Some((field, "asc")) => (field, false),
+                    Some(_) => return Err(format!("Invalid sort direction in '{}'", key)),
+                    None => (key, false),
+                };
+                let field = match field {
+                    "name" => SortField::Name,
+                    "created_at" => SortField::CreatedAt,
+                    "modified_at" => SortField::ModifiedAt,
+                    "size" => SortField::Size,
+                    "folder" => SortField::Folder,
+                    "starred" => SortField::Starred,
+                    _ => return Err(format!("Cannot sort by '{}'", field)),
+                };
+                Ok(SortKey { field, descending })
+            })
+            .collect()
+    }
+}
+
+/// Which files of a folder to list, in which order and which page of them.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FileQuery {
+    pub folder_id: String,
+    /// Shared drive the folder is in, Drive finds it without it but lists faster with it
+    pub drive_id: Option<String>,
+    pub mime_type: Option<String>,
+    pub name_contains: Option<String>,
+    pub created_after: Option<DateTime<Utc>>,
+    pub created_before: Option<DateTime<Utc>>,
+    pub modified_after: Option<DateTime<Utc>>,
+    pub modified_before: Option<DateTime<Utc>>,
+    pub order_by: Vec<SortKey>,
+    pub page_size: Option<u32>,
+    pub page_token: Option<String>,
+    /// Projection of each listed file, the default one when empty
+    pub fields: Vec<FileField>,
+}
+
+impl FileQuery {
+    pub fn page_size(&self) -> u32 {
+        self.page_size
+            .unwrap_or(DEFAULT_PAGE_SIZE)
+            .clamp(1, MAX_PAGE_SIZE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_sort_list() {
+        assert_eq!(
+            SortKey::parse_list("folder, modified_at desc,name asc"),
+            Ok(vec![
+                SortKey {
+                    field: SortField::Folder,
+                    descending: false
+                },
+                SortKey {
+                    field: SortField::ModifiedAt,
+                    descending: true
+                },
+                SortKey {
+                    field: SortField::Name,
+                    descending: false
+                },
+            ])
+        );
+        assert!(SortKey::parse_list("owner").is_err());
+        assert!(SortKey::parse_list("name down").is_err());
+    }
+
+    #[test]
+    fn test_page_size() {
+        let mut query = FileQuery {
+            folder_id: "root".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(query.page_size(), DEFAULT_PAGE_SIZE);
+        query.page_size = Some(5000);
+        assert_eq!(query.page_size(), MAX_PAGE_SIZE);
+        query.page_size = Some(0);
+        assert_eq!(query.page_size(), 1);
+    }
+}
@@ -0,0 +1,244 @@
+/// Bytes from the start of a file that are enough to recognise its type.
+pub const SIGNATURE_LEN: usize = 512;
+
+/// A file type the server accepts, with the extensions it is uploaded under.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AllowedFileType {
+    pub mime_type: String,
+    pub extensions: Vec<String>,
+    /// Largest accepted upload of this type, in bytes
+    pub max_size: u64,
+}
+
+impl AllowedFileType {
+    /// Checks the first bytes of a file against the signature of this type.
+    pub fn matches_content(&self, prefix: &[u8]) -> bool {
+        Signature::for_mime_type(&self.mime_type).is_some_and(|x| x.matches(prefix))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileTypePolicy {
+    types: Vec<AllowedFileType>,
+}
+
+impl FileTypePolicy {
+    /// Parses a comma separated list of `mime_type:ext1|ext2[:max_size]` entries, such as
+    /// `application/pdf:pdf,image/jpeg:jpg|jpeg:10485760`. Types without a size use
+    /// `default_max_size`.
+    pub fn parse(value: &str, default_max_size: u64) -> Result<Self, String> {
+        let types = value
+            .split(',')
+            .map(str::trim)
+            .filter(|x| !x.is_empty())
+            .map(|entry| {
+                let mut parts = entry.split(':').map(str::trim);
+                let mime_type = parts.next().unwrap_or_default().to_lowercase();
+                let extensions: Vec<String> = parts
+                    .next()
+                    .unwrap_or_default()
+                    .split('|')
+                    .map(|x| x.trim().trim_start_matches('.').to_lowercase())
+                    .filter(|x| !x.is_empty())
+                    .collect();
+                let max_size = match parts.next() {
+                    Some(size) => size
+                        .parse()
+                        .map_err(|_| format!("Invalid size in '{}'", entry))?,
+                    None => default_max_size,
+                };
+                if parts.next().is_some() || extensions.is_empty() {
+                    return Err(format!("Invalid file type '{}'", entry));
+                }
+                // Only types whose content can be verified are accepted
+                if Signature::for_mime_type(&mime_type).is_none() {
+                    return Err(format!("No known signature for '{}'", mime_type));
+                }
+                Ok(AllowedFileType {
+                    mime_type,
+                    extensions,
+                    max_size,
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self { types })
+    }
+
+    /// The same policy restricted to one MIME type.
+    pub fn only(&self, mime_type: &str) -> Self {
+        Self {
+            types: self
+                .types
+                .iter()
+                .filter(|x| x.mime_type == mime_type)
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// The types of this policy listed in `mime_types`, or all of them when it is empty,
+    /// with their size limits lowered to `max_size`.
+    pub fn restrict(&self, mime_types: &[String], max_size: Option<u64>) -> Self {
+        Self {
+            types: self
+                .types
+                .iter()
+                .filter(|x| mime_types.is_empty() || mime_types.contains(&x.mime_type))
+                .map(|x| AllowedFileType {
+                    max_size: max_size.map_or(x.max_size, |max| max.min(x.max_size)),
+                    ..x.clone()
+                })
+                .collect(),
+        }
+    }
+
+    pub fn find_by_mime_type(&self, mime_type: &str) -> Option<&AllowedFileType> {
+        self.types.iter().find(|x| x.mime_type == mime_type)
+    }
+
+    pub fn find_by_file_name(&self, file_name: &str) -> Option<&AllowedFileType> {
+        let (_, extension) = file_name.rsplit_once('.')?;
+        let extension = extension.to_lowercase();
+        self.types
+            .iter()
+            .find(|x| x.extensions.contains(&extension))
+    }
+
+    /// Largest upload accepted for any of the types.
+    pub fn max_size(&self) -> u64 {
+        self.types.iter().map(|x| x.max_size).max().unwrap_or(0)
+    }
+}
+
+/// How the content of a file type starts.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Signature {
+    Pdf,
+    Png,
+    Jpeg,
+    Gif,
+    Webp,
+    Rtf,
+    /// ZIP archives, which includes Office Open XML documents
+    Zip,
+    /// Text without a signature, checked for being UTF-8 without control bytes
+    Text,
+}
+
+impl Signature {
+    fn for_mime_type(mime_type: &str) -> Option<Self> {
+        match mime_type {
+            "application/pdf" => Some(Signature::Pdf),
+            "image/png" => Some(Signature::Png),
+            "image/jpeg" => Some(Signature::Jpeg),
+            "image/gif" => Some(Signature::Gif),
+            "image/webp" => Some(Signature::Webp),
+            "application/rtf" => Some(Signature::Rtf),
+            "application/zip"
+            | "application/epub+zip"
+            | "application/vnd.openxmlformats-officedocument.wordprocessingml.document"
+            | "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet"
+            | "application/vnd.openxmlformats-officedocument.presentationml.presentation"
+            | "application/vnd.oasis.opendocument.text"
+            | "application/vnd.oasis.opendocument.spreadsheet"
+            | "application/vnd.oasis.opendocument.presentation" => Some(Signature::Zip),
+            "application/json" | "application/xml" => Some(Signature::Text),
+            _ if mime_type.starts_with("text/") => Some(Signature::Text),
+            _ => None,
+        }
+    }
+
+    fn matches(&self, prefix: &[u8]) -> bool {
+        match self {
+            Signature::Pdf => prefix.starts_with(b"%PDF-"),
+            Signature::Png => prefix.starts_with(b"\x89PNG\r\n\x1a\n"),
+            Signature::Jpeg => prefix.starts_with(b"\xff\xd8\xff"),
+            Signature::Gif => prefix.starts_with(b"GIF87a") || prefix.starts_with(b"GIF89a"),
+            Signature::Webp => {
+                prefix.len() >= 12 && &prefix[..4] == b"RIFF" && &prefix[8..12] == b"WEBP"
+            }
+            Signature::Rtf => prefix.starts_with(b"{\\rtf"),
+            Signature::Zip => {
+                prefix.starts_with(b"PK\x03\x04") || prefix.starts_with(b"PK\x05\x06")
+            }
+            Signature::Text => is_text(prefix),
+        }
+    }
+}
+
+fn is_text(prefix: &[u8]) -> bool {
+    let text = match std::str::from_utf8(prefix) {
+        Ok(text) => text,
+        // The prefix may cut a multi-byte character at the end
+        Err(err) if err.error_len().is_none() => {
+            std::str::from_utf8(&prefix[..err.valid_up_to()]).unwrap_or_default()
+        }
+        Err(_) => return false,
+    };
+    !text
+        .chars()
+        .any(|x| x.is_control() && !matches!(x, '\n' | '\r' | '\t' | '\u{c}'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_policy() {
+        let policy =
+            FileTypePolicy::parse("application/pdf:pdf, image/jpeg:.JPG|jpeg:1024", 10).unwrap();
+
+        let pdf = policy.find_by_file_name("report.PDF").unwrap();
+        assert_eq!(pdf.mime_type, "application/pdf");
+        assert_eq!(pdf.max_size, 10);
+        assert_eq!(
+            policy.find_by_file_name("photo.jpg").unwrap().max_size,
+            1024
+        );
+        assert!(policy.find_by_file_name("notes.txt").is_none());
+        assert!(policy.find_by_file_name("pdf").is_none());
+        assert_eq!(policy.max_size(), 1024);
+        assert!(policy
+            .only("application/pdf")
+            .find_by_file_name("a.jpg")
+            .is_none());
+
+        assert!(FileTypePolicy::parse("application/pdf", 10).is_err());
+        assert!(FileTypePolicy::parse("application/pdf:pdf:big", 10).is_err());
+        assert!(FileTypePolicy::parse("application/x-custom:bin", 10).is_err());
+    }
+
+    #[test]
+    fn test_restrict_policy() {
+        let policy = FileTypePolicy::parse("application/pdf:pdf:100,image/png:png:10", 0).unwrap();
+
+        let restricted = policy.restrict(&["application/pdf".to_string()], Some(50));
+        assert_eq!(restricted.find_by_file_name("a.pdf").unwrap().max_size, 50);
+        assert!(restricted.find_by_file_name("a.png").is_none());
+
+        let restricted = policy.restrict(&[], Some(50));
+        assert_eq!(restricted.find_by_file_name("a.png").unwrap().max_size, 10);
+        assert_eq!(policy.restrict(&[], None), policy);
+    }
+
+    #[test]
+    fn test_matches_content() {
+        let policy = FileTypePolicy::parse(
+            "application/pdf:pdf,image/webp:webp,text/csv:csv,application/vnd.openxmlformats-officedocument.wordprocessingml.document:docx",
+            10,
+        )
+        .unwrap();
+        let file_type = |name| policy.find_by_file_name(name).unwrap();
+
+        assert!(file_type("a.pdf").matches_content(b"%PDF-1.7\n"));
+        assert!(!file_type("a.pdf").matches_content(b"\x89PNG\r\n\x1a\n"));
+        assert!(file_type("a.webp").matches_content(b"RIFF\x10\x00\x00\x00WEBPVP8 "));
+        assert!(file_type("a.docx").matches_content(b"PK\x03\x04\x14\x00"));
+        assert!(file_type("a.csv").matches_content("name,año\n".as_bytes()));
+        // Cut in the middle of a two byte character
+        assert!(file_type("a.csv").matches_content(&"año".as_bytes()[..2]));
+        assert!(!file_type("a.csv").matches_content(b"MZ\x90\x00\x03"));
+    }
+}
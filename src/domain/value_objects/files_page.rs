@@ -0,0 +1,10 @@
+use serde::Serialize;
+
+use super::file_info::FileInfo;
+
+#[derive(Debug, Serialize)]
+pub struct FilesPage {
+    pub files: Vec<FileInfo>,
+    /// Pass back as `page_token` to get the next page, `None` on the last page
+    pub next_page_token: Option<String>,
+}
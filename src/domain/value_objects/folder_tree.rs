@@ -0,0 +1,75 @@
+use serde::Serialize;
+
+use super::file_info::FileInfo;
+
+/// A file or folder with everything found below it.
+#[derive(Debug, Serialize)]
+pub struct FolderTree {
+    #[serde(flatten)]
+    pub file: FileInfo,
+    /// Contents of a folder, `None` for files and for folders past the depth limit
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub children: Option<Vec<FolderTree>>,
+    /// Number of direct children that were listed
+    pub child_count: usize,
+    /// Size of the file, or of every file listed below the folder, in bytes
+    pub total_size: u64,
+}
+
+impl FolderTree {
+    /// Leaf node, for files and for folders that were not walked.
+    pub fn leaf(file: FileInfo) -> Self {
+        let total_size = file.size.unwrap_or(0);
+        Self {
+            file,
+            children: None,
+            child_count: 0,
+            total_size,
+        }
+    }
+
+    pub fn folder(file: FileInfo, children: Vec<FolderTree>) -> Self {
+        Self {
+            file,
+            child_count: children.len(),
+            total_size: children.iter().map(|x| x.total_size).sum(),
+            children: Some(children),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(id: &str, file_type: &str, size: Option<u64>) -> FileInfo {
+        FileInfo {
+            id: id.to_string(),
+            name: id.to_string(),
+            file_type: file_type.to_string(),
+            size,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_folder_totals() {
+        let folder = "application/vnd.google-apps.folder";
+        let nested = FolderTree::folder(
+            file("nested", folder, None),
+            vec![FolderTree::leaf(file("b", "application/pdf", Some(20)))],
+        );
+        let tree = FolderTree::folder(
+            file("root", folder, None),
+            vec![
+                FolderTree::leaf(file("a", "application/pdf", Some(10))),
+                nested,
+                FolderTree::leaf(file("doc", "application/vnd.google-apps.document", None)),
+            ],
+        );
+
+        assert_eq!(tree.child_count, 3);
+        assert_eq!(tree.total_size, 30);
+        assert_eq!(tree.children.as_ref().unwrap()[1].child_count, 1);
+    }
+}
@@ -0,0 +1,22 @@
+use chrono::{DateTime, Utc};
+
+#[derive(PartialEq, Clone, Debug)]
+pub struct GoogleToken {
+    pub access_token: String,
+    pub refresh_token: Option<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl GoogleToken {
+    pub fn new(
+        access_token: String,
+        refresh_token: Option<String>,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Self {
+        Self {
+            access_token,
+            refresh_token,
+            expires_at,
+        }
+    }
+}
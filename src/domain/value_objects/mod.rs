@@ -1,3 +1,20 @@
+pub mod byte_range;
+pub mod conflict_policy;
+pub mod download_conditions;
+pub mod drive_about;
 pub mod email;
+pub mod export_format;
+pub mod file_change;
 pub mod file_info;
+pub mod file_query;
+pub mod file_type;
+pub mod files_page;
+pub mod folder_tree;
+pub mod google_token;
 pub mod id;
+pub mod page_range;
+pub mod pdf_document;
+pub mod permission;
+pub mod revision;
+pub mod search_query;
+pub mod shared_drive;
@@ -0,0 +1,102 @@
+use std::fmt::Display;
+
+/// Pages of a PDF, numbered from 1, both ends inclusive. `end` is `None` for a range
+/// going up to the last page.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PageRange {
+    pub start: u32,
+    pub end: Option<u32>,
+}
+
+/// Page ranges in the order they are taken, such as `1-3,5,8-` for the first three
+/// pages, the fifth, then the eighth to the end. A page can be taken more than once.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PageRanges(pub Vec<PageRange>);
+
+impl PageRanges {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let ranges = value
+            .split(',')
+            .map(|x| x.trim())
+            .map(|range| {
+                let page = |x: &str| match x.trim().parse::<u32>() {
+                    Ok(page) if page > 0 => Ok(page),
+                    _ => Err(format!("Invalid page range: {}", range)),
+                };
+                match range.split_once('-') {
+                    Some((start, "")) => Ok(PageRange {
+                        start: page(start)?,
+                        end: None,
+                    }),
+                    Some((start, end)) => {
+                        let (start, end) = (page(start)?, page(end)?);
+                        if start > end {
+                            return Err(format!("Invalid page range: {}", range));
+                        }
+                        Ok(PageRange {
+                            start,
+                            end: Some(end),
+                        })
+                    }
+                    None => {
+                        let page = page(range)?;
+                        Ok(PageRange {
+                            start: page,
+                            end: Some(page),
+                        })
+                    }
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self(ranges))
+    }
+}
+
+impl Display for PageRanges {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let ranges: Vec<String> = self
+            .0
+            .iter()
+            .map(|x| match x.end {
+                Some(end) if end == x.start => x.start.to_string(),
+                Some(end) => format!("{}-{}", x.start, end),
+                None => format!("{}-", x.start),
+            })
+            .collect();
+        write!(f, "{}", ranges.join(","))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let ranges = PageRanges::parse("1-3, 5,8-").unwrap();
+        assert_eq!(
+            ranges.0,
+            vec![
+                PageRange {
+                    start: 1,
+                    end: Some(3)
+                },
+                PageRange {
+                    start: 5,
+                    end: Some(5)
+                },
+                PageRange {
+                    start: 8,
+                    end: None
+                },
+            ]
+        );
+        assert_eq!(ranges.to_string(), "1-3,5,8-");
+
+        assert!(PageRanges::parse("").is_err());
+        assert!(PageRanges::parse("0-2").is_err());
+        assert!(PageRanges::parse("4-2").is_err());
+        assert!(PageRanges::parse("1,,3").is_err());
+        assert!(PageRanges::parse("-3").is_err());
+    }
+}
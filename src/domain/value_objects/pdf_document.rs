@@ -0,0 +1,81 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// Drive limits each app property to this many bytes, key and value together.
+const APP_PROPERTY_MAX_LEN: usize = 124;
+
+/// What a PDF says about itself.
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct PdfInfo {
+    pub page_count: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub producer: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<DateTime<Utc>>,
+    pub encrypted: bool,
+}
+
+impl PdfInfo {
+    /// Drive app properties listings show, the title cut down to what Drive accepts.
+    pub fn app_properties(&self) -> HashMap<String, String> {
+        let mut properties =
+            HashMap::from([("page_count".to_string(), self.page_count.to_string())]);
+        if let Some(title) = self.title.as_deref().filter(|x| !x.is_empty()) {
+            let key = "title";
+            let mut end = title.len().min(APP_PROPERTY_MAX_LEN - key.len());
+            while !title.is_char_boundary(end) {
+                end -= 1;
+            }
+            properties.insert(key.to_string(), title[..end].to_string());
+        }
+        properties
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct PdfPage {
+    /// Numbered from 1
+    pub number: u32,
+    pub text: String,
+}
+
+/// A PDF's information along with the text of each of its pages.
+#[derive(Debug, Serialize)]
+pub struct PdfDocument {
+    #[serde(flatten)]
+    pub info: PdfInfo,
+    pub pages: Vec<PdfPage>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_app_properties() {
+        let info = PdfInfo {
+            page_count: 12,
+            title: Some("Contrato".to_string()),
+            ..Default::default()
+        };
+        let properties = info.app_properties();
+        assert_eq!(properties["page_count"], "12");
+        assert_eq!(properties["title"], "Contrato");
+
+        let info = PdfInfo {
+            title: Some("ñ".repeat(100)),
+            ..Default::default()
+        };
+        let title = &info.app_properties()["title"];
+        assert_eq!(title.len(), 118);
+        assert!(title.chars().all(|x| x == 'ñ'));
+
+        assert!(!PdfInfo::default().app_properties().contains_key("title"));
+    }
+}
@@ -0,0 +1,188 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Who a permission is granted to.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PermissionType {
+    User,
+    Group,
+    Domain,
+    /// Anyone with the link
+    Anyone,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PermissionRole {
+    Owner,
+    Organizer,
+    FileOrganizer,
+    Writer,
+    Commenter,
+    Reader,
+}
+
+impl PermissionRole {
+    /// Roles the API can grant, ownership and shared drive roles are managed in Drive.
+    pub fn is_grantable(&self) -> bool {
+        matches!(
+            self,
+            PermissionRole::Writer | PermissionRole::Commenter | PermissionRole::Reader
+        )
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Permission {
+    pub id: String,
+    pub permission_type: PermissionType,
+    pub role: PermissionRole,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email_address: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub domain: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// A new permission on a file or folder.
+#[derive(Debug, Clone)]
+pub struct PermissionGrant {
+    pub permission_type: PermissionType,
+    pub role: PermissionRole,
+    /// Required for users and groups
+    pub email_address: Option<String>,
+    /// Required for domains
+    pub domain: Option<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Drive always notifies when sharing with users and groups unless this is false
+    pub send_notification: bool,
+    pub message: Option<String>,
+}
+
+impl PermissionGrant {
+    pub fn validate(&self, now: DateTime<Utc>) -> Result<(), String> {
+        validate_role(self.role)?;
+        match self.permission_type {
+            PermissionType::User | PermissionType::Group if self.email_address.is_none() => {
+                return Err("An email address is required for users and groups".to_string())
+            }
+            PermissionType::Domain if self.domain.is_none() => {
+                return Err("A domain is required".to_string())
+            }
+            PermissionType::Domain | PermissionType::Anyone if self.expires_at.is_some() => {
+                return Err("Only user and group permissions can expire".to_string())
+            }
+            _ => {}
+        }
+        validate_expiration(self.expires_at, now)
+    }
+}
+
+/// Changes to an existing permission, fields left as `None` are kept.
+#[derive(Debug, Clone, Default)]
+pub struct PermissionUpdate {
+    pub role: Option<PermissionRole>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub remove_expiration: bool,
+}
+
+impl PermissionUpdate {
+    pub fn validate(&self, now: DateTime<Utc>) -> Result<(), String> {
+        if self.role.is_none() && self.expires_at.is_none() && !self.remove_expiration {
+            return Err("Nothing to update".to_string());
+        }
+        if self.expires_at.is_some() && self.remove_expiration {
+            return Err("Cannot set and remove the expiration at once".to_string());
+        }
+        if let Some(role) = self.role {
+            validate_role(role)?;
+        }
+        validate_expiration(self.expires_at, now)
+    }
+}
+
+fn validate_role(role: PermissionRole) -> Result<(), String> {
+    if role.is_grantable() {
+        Ok(())
+    } else {
+        Err("Only the reader, commenter and writer roles can be granted".to_string())
+    }
+}
+
+fn validate_expiration(
+    expires_at: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+) -> Result<(), String> {
+    match expires_at {
+        Some(expires_at) if expires_at <= now => {
+            Err("The expiration must be in the future".to_string())
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+
+    fn grant(permission_type: PermissionType) -> PermissionGrant {
+        PermissionGrant {
+            permission_type,
+            role: PermissionRole::Reader,
+            email_address: None,
+            domain: None,
+            expires_at: None,
+            send_notification: true,
+            message: None,
+        }
+    }
+
+    #[test]
+    fn test_validate_grant() {
+        let now = Utc::now();
+
+        assert!(grant(PermissionType::Anyone).validate(now).is_ok());
+        assert!(grant(PermissionType::User).validate(now).is_err());
+        assert!(grant(PermissionType::Domain).validate(now).is_err());
+
+        let mut user = grant(PermissionType::User);
+        user.email_address = Some("client@example.com".to_string());
+        user.expires_at = Some(now + Duration::days(1));
+        assert!(user.validate(now).is_ok());
+        user.expires_at = Some(now - Duration::days(1));
+        assert!(user.validate(now).is_err());
+        user.expires_at = None;
+        user.role = PermissionRole::Owner;
+        assert!(user.validate(now).is_err());
+
+        let mut anyone = grant(PermissionType::Anyone);
+        anyone.expires_at = Some(now + Duration::days(1));
+        assert!(anyone.validate(now).is_err());
+    }
+
+    #[test]
+    fn test_validate_update() {
+        let now = Utc::now();
+
+        assert!(PermissionUpdate::default().validate(now).is_err());
+        assert!(PermissionUpdate {
+            role: Some(PermissionRole::Commenter),
+            ..Default::default()
+        }
+        .validate(now)
+        .is_ok());
+        assert!(PermissionUpdate {
+            expires_at: Some(now + Duration::hours(1)),
+            remove_expiration: true,
+            ..Default::default()
+        }
+        .validate(now)
+        .is_err());
+    }
+}
@@ -0,0 +1,74 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use super::file_info::FileOwner;
+
+/// A stored version of a file's content.
+#[derive(Debug, Default, Serialize)]
+pub struct Revision {
+    pub id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub modified_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub md5_checksum: Option<String>,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub file_type: String,
+    /// Name of the file uploaded as this revision
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub original_file_name: Option<String>,
+    /// User who uploaded the revision
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub author: Option<FileOwner>,
+    /// Pinned revisions are never deleted by Drive, nor by `older_than_latest`
+    pub keep_forever: bool,
+}
+
+impl Revision {
+    /// Revisions older than the `keep` most recent ones, pinned revisions excepted. The
+    /// latest revision is always kept, it is the current content of the file.
+    pub fn older_than_latest(revisions: &[Revision], keep: usize) -> Vec<&Revision> {
+        let mut sorted: Vec<&Revision> = revisions.iter().collect();
+        sorted.sort_by_key(|x| std::cmp::Reverse(x.modified_at));
+        sorted
+            .into_iter()
+            .skip(keep.max(1))
+            .filter(|x| !x.keep_forever)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+
+    #[test]
+    fn test_older_than_latest() {
+        let now = Utc::now();
+        let revision = |id: &str, days: i64, keep_forever| Revision {
+            id: id.to_string(),
+            modified_at: Some(now - Duration::days(days)),
+            keep_forever,
+            ..Default::default()
+        };
+        let revisions = vec![
+            revision("a", 4, false),
+            revision("b", 3, true),
+            revision("c", 2, false),
+            revision("d", 1, false),
+        ];
+
+        let ids = |keep| -> Vec<&str> {
+            Revision::older_than_latest(&revisions, keep)
+                .iter()
+                .map(|x| x.id.as_str())
+                .collect()
+        };
+        assert_eq!(ids(2), vec!["a"]);
+        assert_eq!(ids(0), vec!["c", "a"]);
+        assert!(ids(4).is_empty());
+    }
+}
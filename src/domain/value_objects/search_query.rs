@@ -0,0 +1,77 @@
+use chrono::{DateTime, Utc};
+
+use super::{
+    file_info::FileField,
+    file_query::{DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE},
+};
+
+/// Filters of a search across the user's Drive, a file has to match all of them.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SearchQuery {
+    /// Words in the name, description or content of the file
+    pub full_text: Option<String>,
+    pub name_contains: Option<String>,
+    pub mime_type: Option<String>,
+    /// Email address of one of the owners
+    pub owner: Option<String>,
+    pub starred: Option<bool>,
+    /// Searches the trash instead of the rest of the Drive
+    pub trashed: bool,
+    pub modified_after: Option<DateTime<Utc>>,
+    pub modified_before: Option<DateTime<Utc>>,
+    /// Folders whose direct children are searched, the whole Drive when empty
+    pub folder_ids: Vec<String>,
+    /// Shared drive to search instead of the user's own files
+    pub drive_id: Option<String>,
+    pub page_size: Option<u32>,
+    pub page_token: Option<String>,
+    /// Projection of each file found, the default one when empty
+    pub fields: Vec<FileField>,
+}
+
+impl SearchQuery {
+    pub fn page_size(&self) -> u32 {
+        self.page_size
+            .unwrap_or(DEFAULT_PAGE_SIZE)
+            .clamp(1, MAX_PAGE_SIZE)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let blank = |x: &Option<String>| x.as_ref().is_some_and(|x| x.trim().is_empty());
+        if blank(&self.full_text) || blank(&self.name_contains) || blank(&self.owner) {
+            return Err("Search terms cannot be empty".to_string());
+        }
+        match (self.modified_after, self.modified_before) {
+            (Some(after), Some(before)) if after >= before => {
+                Err("modified_after must be before modified_before".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+
+    #[test]
+    fn test_validate() {
+        let now = Utc::now();
+        assert!(SearchQuery::default().validate().is_ok());
+        assert!(SearchQuery {
+            full_text: Some(" ".to_string()),
+            ..Default::default()
+        }
+        .validate()
+        .is_err());
+        assert!(SearchQuery {
+            modified_after: Some(now),
+            modified_before: Some(now - Duration::days(1)),
+            ..Default::default()
+        }
+        .validate()
+        .is_err());
+    }
+}
@@ -0,0 +1,19 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// A shared drive the user is a member of. Its id is also the id of its root folder.
+#[derive(Debug, Default, Serialize)]
+pub struct SharedDrive {
+    pub id: String,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<DateTime<Utc>>,
+    /// Hidden from the user's default Drive view
+    pub hidden: bool,
+}
+
+/// Folder a request is about: the one given, otherwise the root of the shared drive when
+/// there is one.
+pub fn folder_or_root(folder_id: Option<String>, drive_id: Option<&str>) -> Option<String> {
+    folder_id.or_else(|| drive_id.map(|x| x.to_string()))
+}
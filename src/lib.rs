@@ -1,9 +1,13 @@
+// Use case errors name their variants after the failure kind (`ConnectionError`, ...).
+#![allow(clippy::enum_variant_names)]
+
 mod adapters;
 mod application;
 mod domain;
 
 use axum::{
-    http::{HeaderValue, Method, StatusCode, Uri},
+    extract::DefaultBodyLimit,
+    http::{header, HeaderValue, Method, StatusCode, Uri},
     middleware,
     response::IntoResponse,
     routing::{get, post},
@@ -16,10 +20,14 @@ use tower_http::{
 };
 
 use adapters::driving::http::{handlers, middlewares};
+use domain::value_objects::file_type::FileTypePolicy;
 
 pub async fn router() -> Router {
     let app_state = adapters::driving::http::state::AppState::new().await;
 
+    // Sends Drive changes to the users' webhooks in the background
+    tokio::spawn(adapters::driving::changes_poller::run(app_state.clone()));
+
     let service_builder = ServiceBuilder::new()
         .layer(middleware::from_fn(
             middlewares::logging::log_request_response,
@@ -36,6 +44,15 @@ pub async fn router() -> Router {
             CorsLayer::new()
                 .allow_methods([Method::GET, Method::POST])
                 .allow_headers(Any)
+                // Lets the frontend PDF viewer read the range and caching headers
+                .expose_headers([
+                    header::ACCEPT_RANGES,
+                    header::CONTENT_RANGE,
+                    header::CONTENT_LENGTH,
+                    header::CONTENT_DISPOSITION,
+                    header::ETAG,
+                    header::LAST_MODIFIED,
+                ])
                 .allow_origin([
                     "http://localhost:5173".parse::<HeaderValue>().unwrap(),
                     "http://192.168.1.120:5173".parse::<HeaderValue>().unwrap(),
@@ -43,19 +60,89 @@ pub async fn router() -> Router {
                 ]),
         );
 
+    let allowed_types = app_state.config.allowed_file_types.clone();
+
     // Public routes
     let public_routes = Router::new()
         .route(
             "/google-auth-url",
             get(handlers::handler_get_google_auth_url),
         )
-        .route("/callback", get(handlers::handler_handle_google_callback));
+        .route("/callback", get(handlers::handler_handle_google_callback))
+        .route("/shared-file", get(handlers::handler_download_shared_file))
+        .route(
+            "/drive-notifications",
+            post(handlers::handler_drive_notification),
+        )
+        .route(
+            "/upload",
+            post(handlers::handler_upload_to_request)
+                .layer(DefaultBodyLimit::max(upload_body_limit(&allowed_types))),
+        );
 
     // Protected routes
     let protected_routes = Router::new()
+        .route("/me", get(handlers::handler_get_me))
         .route("/list-files", get(handlers::handler_get_list_files))
+        .route("/download", get(handlers::handler_download_file))
+        .route(
+            "/download-archive",
+            get(handlers::handler_download_archive),
+        )
+        .route(
+            "/upload",
+            post(handlers::handler_upload_file)
+                .layer(DefaultBodyLimit::max(upload_body_limit(&allowed_types))),
+        )
+        .route(
+            "/upload-batch",
+            post(handlers::handler_upload_files).layer(DefaultBodyLimit::max(
+                usize::try_from(app_state.config.batch_upload_max_size)
+                    .unwrap_or(usize::MAX)
+                    .saturating_add(64 * 1024),
+            )),
+        )
+        // Kept for existing clients, they only accept PDF files
         .route("/download-pdf", get(handlers::handler_download_pdf))
-        .route("/upload-pdf", post(handlers::handler_upload_pdf));
+        .route(
+            "/upload-pdf",
+            post(handlers::handler_upload_pdf).layer(DefaultBodyLimit::max(upload_body_limit(
+                &allowed_types.only("application/pdf"),
+            ))),
+        )
+        .route("/pdf/merge", post(handlers::handler_merge_pdfs))
+        .route("/pdf/split", post(handlers::handler_split_pdf))
+        .route("/pdf/extract", get(handlers::handler_extract_pdf))
+        .route("/trash-file", post(handlers::handler_trash_file))
+        .route("/restore-file", post(handlers::handler_restore_file))
+        .route("/delete-file", post(handlers::handler_delete_file))
+        .route("/rename-file", post(handlers::handler_rename_file))
+        .route("/move-file", post(handlers::handler_move_file))
+        .route("/copy-file", post(handlers::handler_copy_file))
+        .route("/create-folder", post(handlers::handler_create_folder))
+        .route("/folder-tree", get(handlers::handler_get_folder_tree))
+        .route("/search", get(handlers::handler_search_files))
+        .route("/drives", get(handlers::handler_get_drives))
+        .route("/permissions", get(handlers::handler_get_permissions))
+        .route("/grant-permission", post(handlers::handler_grant_permission))
+        .route("/update-permission", post(handlers::handler_update_permission))
+        .route("/revoke-permission", post(handlers::handler_revoke_permission))
+        .route("/thumbnail", get(handlers::handler_get_thumbnail))
+        .route("/revisions", get(handlers::handler_get_revisions))
+        .route("/download-revision", get(handlers::handler_download_revision))
+        .route("/keep-revision", post(handlers::handler_keep_revision))
+        .route("/delete-revisions", post(handlers::handler_delete_revisions))
+        .route("/create-share-link", post(handlers::handler_create_share_link))
+        .route("/share-links", get(handlers::handler_get_share_links))
+        .route("/revoke-share-link", post(handlers::handler_revoke_share_link))
+        .route(
+            "/create-upload-request",
+            post(handlers::handler_create_upload_request),
+        )
+        .route("/upload-requests", get(handlers::handler_get_upload_requests))
+        .route("/create-webhook", post(handlers::handler_create_webhook))
+        .route("/webhooks", get(handlers::handler_get_webhooks))
+        .route("/delete-webhook", post(handlers::handler_delete_webhook));
 
     // API
     let api = Router::new()
@@ -71,6 +158,14 @@ pub async fn router() -> Router {
         .with_state(app_state)
 }
 
+/// Request body limit for an upload route, the largest file it accepts plus room for the
+/// multipart framing.
+fn upload_body_limit(allowed_types: &FileTypePolicy) -> usize {
+    usize::try_from(allowed_types.max_size())
+        .unwrap_or(usize::MAX)
+        .saturating_add(64 * 1024)
+}
+
 // root handlers
 async fn handler_404(uri: Uri) -> impl IntoResponse {
     (StatusCode::NOT_FOUND, format!("No route for {}", uri))
@@ -1,5 +1,4 @@
 use tokio::{net::TcpListener, runtime::Builder};
-use tracing_subscriber;
 
 fn main() {
     tracing_subscriber::fmt()